@@ -0,0 +1,186 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use reqwest::blocking::Client;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::check_result::CheckResultJSON;
+
+/// How long an agent may take to check all domain names
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Remote agent i.e. hcc-server at another vantage point, checking domain names on behalf of
+/// coordinator through POST /check
+#[derive(Clone, Debug)]
+pub struct Agent {
+    /// Location of agent e.g. eu-west, results are reported as seen from it
+    pub location: String,
+    /// Base URL of agent e.g. https://agent.eu-west.example.com:9292
+    pub url: String,
+    /// Bearer token required by agent
+    pub token: Option<String>,
+}
+
+/// Options of checks sent to agents, as body of POST /check
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AgentRequest<'a> {
+    pub domain_names: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starttls: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sni: Option<String>,
+    pub grace_in_days: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl FromStr for Agent {
+    type Err = anyhow::Error;
+
+    /// Parse agent from `LOCATION=URL` e.g. `eu-west=https://agent.eu-west.example.com:9292`
+    ///
+    /// ```
+    /// # use hcc::Agent;
+    /// let agent: Agent = "eu-west=http://10.0.0.1:9292/".parse().unwrap();
+    /// assert_eq!("eu-west", agent.location);
+    /// assert_eq!("http://10.0.0.1:9292", agent.url);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (location, url) = match s.split_once('=') {
+            Some((location, url)) if !location.is_empty() && !url.is_empty() => (location, url),
+            _ => bail!("invalid agent {0}, expect LOCATION=URL", s),
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            bail!("invalid URL of agent {0}: {1}", location, url);
+        }
+        Ok(Agent {
+            location: location.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            token: None,
+        })
+    }
+}
+
+impl Agent {
+    /// Check domain names from agent, results are located at agent unless agent reports its own
+    /// location
+    pub fn check(&self, request: &AgentRequest) -> anyhow::Result<Vec<CheckResultJSON>> {
+        let url = format!("{0}/check", self.url);
+        let body = serde_json::to_string(request)?;
+        let mut builder = Client::builder()
+            .timeout(TIMEOUT)
+            .build()?
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .body(body);
+        if let Some(ref token) = self.token {
+            builder = builder.bearer_auth(token);
+        }
+        let response = builder
+            .send()
+            .with_context(|| format!("failed to request agent {0}", self.location))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .with_context(|| format!("failed to read reply of agent {0}", self.location))?;
+        if !status.is_success() {
+            bail!(
+                "agent {0} replies {1}: {2}",
+                self.location,
+                status,
+                body.trim()
+            );
+        }
+        let mut results: Vec<CheckResultJSON> = serde_json::from_str(&body)
+            .with_context(|| format!("failed to parse reply of agent {0}", self.location))?;
+        for result in results.iter_mut() {
+            result.location.get_or_insert_with(|| self.location.clone());
+        }
+        debug!(
+            location = self.location.as_str(),
+            count = results.len(),
+            "results of agent"
+        );
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::agent::{Agent, AgentRequest};
+    use crate::check_result::{CheckResult, CheckResultJSON};
+
+    /// Fake agent replying status and body, and returning body of request it received
+    fn agent_server(status: &'static str, body: String) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(sock.try_clone().unwrap());
+            let mut length = 0;
+            let mut header = String::new();
+            while header != "\r\n" {
+                header.clear();
+                reader.read_line(&mut header).unwrap();
+                let lower = header.to_ascii_lowercase();
+                if let Some(value) = lower.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if lower.starts_with("authorization:") {
+                    assert_eq!("authorization: bearer secret\r\n", lower);
+                }
+            }
+            let mut request = vec![0; length];
+            reader.read_exact(&mut request).unwrap();
+            write!(
+                sock,
+                "HTTP/1.1 {0}\r\nContent-Type: application/json\r\nContent-Length: {1}\r\nConnection: close\r\n\r\n{2}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (format!("http://{0}", addr), handle)
+    }
+
+    #[test]
+    fn test_check() {
+        let result = CheckResult {
+            domain_name: "example.com",
+            checked_at: 1,
+            ..Default::default()
+        };
+        let reply = serde_json::to_string(&[CheckResultJSON::new(&result)]).unwrap();
+        let (url, handle) = agent_server("200 OK", reply);
+        let mut agent: Agent = format!("eu-west={0}", url).parse().unwrap();
+        agent.token = Some("secret".into());
+        let request = AgentRequest {
+            domain_names: &["example.com"],
+            grace_in_days: 7,
+            ..Default::default()
+        };
+        let results = agent.check(&request).unwrap();
+        assert_eq!(1, results.len());
+        assert_eq!(Some("eu-west"), results[0].location.as_deref());
+        assert_eq!(
+            r#"{"domain_names":["example.com"],"grace_in_days":7}"#,
+            handle.join().unwrap()
+        );
+
+        let (url, _) = agent_server("400 Bad Request", r#"{"message":"no"}"#.into());
+        let agent: Agent = format!("us-east={0}", url).parse().unwrap();
+        assert!(agent.check(&request).is_err());
+        assert!("us-east".parse::<Agent>().is_err());
+        assert!("us-east=ftp://example.com".parse::<Agent>().is_err());
+    }
+}
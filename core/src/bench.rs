@@ -0,0 +1,274 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rustls::{
+    Certificate, ClientConfig, ClientSession, ClientSessionMemoryCache, ProtocolVersion,
+    RootCertStore, ServerCertVerified, ServerCertVerifier, Session, TLSError,
+};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::check_client::CheckClient;
+
+/// Longest wait for server to send session tickets and close connection after handshake
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Statistics of durations in milliseconds, percentiles by nearest rank
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Timings {
+    /// Number of samples
+    pub count: usize,
+    pub min: f64,
+    pub median: f64,
+    pub p95: f64,
+}
+
+impl Timings {
+    /// Statistics of samples, none without sample
+    fn of(samples: &[Duration]) -> Option<Self> {
+        let mut samples: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1e3).collect();
+        samples.sort_by(|a, b| a.partial_cmp(b).expect("duration is a number"));
+        let rank = |p: f64| samples[((p * samples.len() as f64).ceil() as usize).max(1) - 1];
+        Some(Timings {
+            count: samples.len(),
+            min: *samples.first()?,
+            median: rank(0.5),
+            p95: rank(0.95),
+        })
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {0:.2}ms, median {1:.2}ms, p95 {2:.2}ms of {3}",
+            self.min, self.median, self.p95, self.count
+        )
+    }
+}
+
+/// Connect and handshake times of repeated TLS handshakes with a host
+#[derive(Debug, Default, Serialize)]
+pub struct Benchmark {
+    /// Domain name that got benchmarked
+    pub domain_name: String,
+    /// Handshakes requested, fewer are done when run is cancelled
+    pub requested: usize,
+    /// Handshakes completed
+    pub completed: usize,
+    /// Connections or handshakes failed
+    pub failed: usize,
+    /// Handshakes offering session of handshake before
+    pub resumption_attempts: usize,
+    /// Handshakes server agreed to resume session in
+    pub resumed: usize,
+    /// From resolving name to connection ready for handshake, after proxy and STARTTLS if any
+    pub connect: Option<Timings>,
+    /// All handshakes completed
+    pub handshake: Option<Timings>,
+    /// Handshakes verifying certificate of server
+    pub full_handshake: Option<Timings>,
+    /// Handshakes resuming session, without certificate of server
+    pub resumed_handshake: Option<Timings>,
+}
+
+impl fmt::Display for Benchmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{0}", self.domain_name)?;
+        write!(
+            f,
+            "  handshakes: {0} of {1} completed, {2} of {3} resumed",
+            self.completed, self.requested, self.resumed, self.resumption_attempts
+        )?;
+        if self.failed > 0 {
+            write!(f, ", {0} failed", self.failed)?;
+        }
+        let timings = [
+            ("connect", &self.connect),
+            ("handshake", &self.handshake),
+            ("full handshake", &self.full_handshake),
+            ("resumed handshake", &self.resumed_handshake),
+        ];
+        for (name, timings) in &timings {
+            if let Some(timings) = timings {
+                write!(f, "\n  {0}: {1}", name, timings)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Verifier delegating to verifier of client, recording whether certificate is verified, which
+/// happens in full handshakes only
+struct RecordingVerifier {
+    config: Arc<ClientConfig>,
+    verified: Arc<AtomicBool>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        dns_name: webpki::DNSNameRef,
+        ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        self.verified.store(true, Ordering::SeqCst);
+        self.config.get_verifier().verify_server_cert(
+            roots,
+            presented_certs,
+            dns_name,
+            ocsp_response,
+        )
+    }
+}
+
+impl CheckClient {
+    /// Repeat TLS handshakes with one domain name and measure connect and handshake times,
+    /// every other handshake offers session of handshake before to measure resumption
+    ///
+    /// ```
+    /// # use hcc::CheckClient;
+    /// let client = CheckClient::new();
+    /// client.bench("sha512.badssl.com", 10);
+    /// ```
+    pub fn bench(&self, domain_name: &str, count: usize) -> anyhow::Result<Benchmark> {
+        if count == 0 {
+            anyhow::bail!("count of handshakes must be positive");
+        }
+        let target = self.target_of(domain_name)?;
+        let sni = self.sni_of(&target)?;
+
+        let verified = Arc::new(AtomicBool::new(false));
+        let mut base = (*self.config).clone();
+        base.dangerous()
+            .set_certificate_verifier(Arc::new(RecordingVerifier {
+                config: self.config.clone(),
+                verified: verified.clone(),
+            }));
+        let mut config = Arc::new(base.clone());
+
+        let mut benchmark = Benchmark {
+            domain_name: domain_name.to_string(),
+            requested: count,
+            ..Default::default()
+        };
+        let (mut connects, mut full, mut resumed) = (vec![], vec![], vec![]);
+        let mut last_error = None;
+        for i in 0..count {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let resuming = i % 2 == 1;
+            if resuming {
+                benchmark.resumption_attempts += 1;
+            } else {
+                // full handshake starts without session, and its session is resumed next
+                let mut fresh = base.clone();
+                fresh.session_persistence = ClientSessionMemoryCache::new(4);
+                config = Arc::new(fresh);
+            }
+            self.throttle();
+            verified.store(false, Ordering::SeqCst);
+
+            let origin = Instant::now();
+            let mut sock = match self.connect(&target) {
+                Ok(sock) => sock,
+                Err(e) => {
+                    debug!(target = %target, error = %e, "connect failed");
+                    benchmark.failed += 1;
+                    last_error = Some(anyhow::Error::from(e));
+                    continue;
+                }
+            };
+            let connect = origin.elapsed();
+            let _tracked = self.cancellation.track(&sock);
+            let handshake = match Self::handshake(&mut sock, &target, sni, &config) {
+                Ok(handshake) => handshake,
+                Err(e) => {
+                    benchmark.failed += 1;
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            benchmark.completed += 1;
+            connects.push(connect);
+            if verified.load(Ordering::SeqCst) {
+                full.push(handshake.elapsed);
+            } else {
+                benchmark.resumed += 1;
+                resumed.push(handshake.elapsed);
+            }
+            let tls13 = handshake.protocol_version == Some(ProtocolVersion::TLSv1_3);
+            close(&mut sock, handshake.session, tls13, self.timeout());
+        }
+        if benchmark.completed == 0 {
+            if let Some(e) = last_error {
+                return Err(e.context(format!("no handshake completes with {0}", target)));
+            }
+        }
+
+        let handshakes: Vec<Duration> = full.iter().chain(resumed.iter()).copied().collect();
+        benchmark.connect = Timings::of(&connects);
+        benchmark.handshake = Timings::of(&handshakes);
+        benchmark.full_handshake = Timings::of(&full);
+        benchmark.resumed_handshake = Timings::of(&resumed);
+        Ok(benchmark)
+    }
+}
+
+/// Close connection after handshake, reading until server closes it in TLS 1.3 where session
+/// tickets are sent after handshake
+fn close(
+    sock: &mut TcpStream,
+    mut session: ClientSession,
+    read_tickets: bool,
+    timeout: Option<Duration>,
+) {
+    session.send_close_notify();
+    let mut tls = rustls::Stream::new(&mut session, sock);
+    if tls.flush().is_err() || !read_tickets {
+        return;
+    }
+    let timeout = timeout.map_or(CLOSE_TIMEOUT, |t| t.min(CLOSE_TIMEOUT));
+    let _ = tls.sock.set_read_timeout(Some(timeout));
+    let _ = tls.sock.shutdown(Shutdown::Write);
+    let mut buf = [0; 1024];
+    while let Ok(n) = tls.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::bench::Timings;
+
+    #[test]
+    fn test_timings() {
+        assert_eq!(None, Timings::of(&[]));
+
+        let samples: Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+        let timings = Timings::of(&samples).unwrap();
+        assert_eq!(20, timings.count);
+        assert_eq!(1.0, timings.min);
+        assert_eq!(10.0, timings.median);
+        assert_eq!(19.0, timings.p95);
+        assert_eq!(
+            "min 1.00ms, median 10.00ms, p95 19.00ms of 20",
+            timings.to_string()
+        );
+
+        let timings = Timings::of(&[Duration::from_micros(1500)]).unwrap();
+        assert_eq!((1.5, 1.5, 1.5), (timings.min, timings.median, timings.p95));
+    }
+}
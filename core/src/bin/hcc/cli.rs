@@ -0,0 +1,1032 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use structopt::clap::Shell;
+use structopt::StructOpt;
+
+use hcc::{
+    Agent, Cidr, ColorChoice, Config, ExitCodeMapping, Format, NumberFormat, Sink, StartTls, Zone,
+    DEFAULT_S3_ENDPOINT,
+};
+
+use crate::model::{FormatterSink, LogFormat, PortSet, SortKey, Ttl};
+use crate::update;
+
+#[derive(Clone, Debug, Default, StructOpt)]
+#[structopt(author, about)]
+pub struct Opts {
+    /// Output in JSON format, shorthand of --output json
+    #[structopt(short, long)]
+    pub json: bool,
+    /// Output format, text, json, ndjson, prometheus, influx i.e. InfluxDB line protocol for
+    /// Telegraf exec plugin, or ics i.e. iCalendar with an event on each expiration date
+    #[structopt(
+        long,
+        env = "HCC_OUTPUT",
+        value_name = "FORMAT",
+        conflicts_with = "json"
+    )]
+    pub output: Option<Format>,
+    /// Write output to file instead of standard output, replaced atomically so readers never see
+    /// partial content, in format of --output or text otherwise
+    #[structopt(short, long, env = "HCC_OUT")]
+    pub out: Option<PathBuf>,
+    /// Sign output with Ed25519 private key in PKCS#8 PEM file, JSON output is wrapped with
+    /// signature embedded and other output is followed by a line of signature, see verify-report
+    #[structopt(
+        long,
+        env = "HCC_SIGN_KEY",
+        value_name = "PATH",
+        conflicts_with_all = &["flush-every", "flush-interval"]
+    )]
+    pub sign_key: Option<PathBuf>,
+    /// Output sink as FORMAT[:PATH], repeat to emit several at once e.g. --sink text --sink
+    /// json:results.json --sink pushgateway:http://localhost:9091, formats are text, json,
+    /// ndjson, prometheus, influx, ics, pushgateway, statsd and dogstatsd
+    #[structopt(long = "sink", number_of_values = 1)]
+    pub sinks: Vec<Sink>,
+    /// Formatter by name as NAME[:PATH], a built-in format or a formatter plugin in
+    /// configuration file, repeat to render several e.g. --formatter csv:report.csv
+    #[structopt(long = "formatter", number_of_values = 1, value_name = "NAME[:PATH]")]
+    pub formatters: Vec<FormatterSink>,
+    /// Deliver results to notifier plugin in configuration file by name after each check,
+    /// along with notifiers in configuration file
+    #[structopt(long = "notifier", number_of_values = 1, value_name = "NAME")]
+    pub notifiers: Vec<String>,
+    /// Remind of each expiration in ics output DAYS before it, repeat for several reminders
+    /// e.g. --ics-alarm 30 --ics-alarm 7
+    #[structopt(long = "ics-alarm", number_of_values = 1, value_name = "DAYS")]
+    pub ics_alarms: Vec<u32>,
+    /// Send expiry days and status of certificates as gauges to StatsD server at HOST:PORT,
+    /// shorthand of --sink statsd:HOST:PORT
+    #[structopt(long, env = "HCC_STATSD", value_name = "HOST:PORT")]
+    pub statsd: Option<String>,
+    /// Tag metrics sent to StatsD with domain name in DogStatsD format instead of embedding it
+    /// in metric names
+    #[structopt(long, requires = "statsd")]
+    pub dogstatsd: bool,
+    /// Override exit code of a condition as CONDITION=CODE, repeat to override several e.g.
+    /// --exit-code error=1 --exit-code warning=0, conditions are ok, warning, expired, unknown
+    /// and error i.e. check could not complete, defaults are 0, 1, 2, 3 and 1
+    #[structopt(long = "exit-code", number_of_values = 1)]
+    pub exit_codes: Vec<ExitCodeMapping>,
+    /// Flush results to standard output every N domain names during long runs, with progress
+    /// printed to standard error
+    #[structopt(long, env = "HCC_FLUSH_EVERY", value_name = "N")]
+    pub flush_every: Option<usize>,
+    /// Flush results to standard output every N seconds during long runs, with progress printed
+    /// to standard error
+    #[structopt(long, env = "HCC_FLUSH_INTERVAL", value_name = "SECONDS")]
+    pub flush_interval: Option<u64>,
+    /// Abort batch check as soon as an expired certificate is found
+    #[structopt(long)]
+    pub fail_fast: bool,
+    /// Order of results, days (remaining days ascending), domain, state or input
+    #[structopt(long, env = "HCC_SORT", default_value = "days")]
+    pub sort: SortKey,
+    /// Colorize text output by state of certificate, auto, always or never, auto respects NO_COLOR
+    #[structopt(long, env = "HCC_COLOR", default_value = "auto")]
+    pub color: ColorChoice,
+    /// Locale to group digits of numbers in text output by e.g. de or de_CH.UTF-8, defaults to
+    /// LC_ALL, LC_NUMERIC or LANG, then en
+    #[structopt(long, env = "HCC_LOCALE")]
+    pub locale: Option<NumberFormat>,
+    /// Print numbers in text output without thousands separators, friendly to machines
+    #[structopt(long)]
+    pub plain_numbers: bool,
+    /// Print remaining time in text output as years, months and days e.g. 1 year, 2 months, 3
+    /// days instead of days
+    #[structopt(long)]
+    pub human: bool,
+    /// Timezone of timestamps in text output and dashboard, IANA name e.g. Europe/Berlin, UTC or
+    /// local, JSON output keeps UTC
+    #[structopt(long, env = "HCC_TIMEZONE", default_value = "UTC", value_name = "TZ")]
+    pub timezone: Zone,
+    /// Verbose mode, show elapsed time and log connection, handshake and parsing events, repeat
+    /// for more details e.g. -vv
+    #[structopt(short, long, parse(from_occurrences))]
+    pub verbose: u8,
+    /// Format of logs printed to standard error, text or json
+    #[structopt(long, env = "HCC_LOG_FORMAT", default_value = "text")]
+    pub log_format: LogFormat,
+    /// Only output expired or soon-expiring certificates, print nothing if all are valid
+    #[structopt(short, long, visible_alias = "only-failures")]
+    pub quiet: bool,
+    /// Report each leaf certificate once with domain names sharing it e.g. behind SAN or
+    /// wildcard certificates, instead of one result per domain name
+    #[structopt(long)]
+    pub group_by_cert: bool,
+    /// Expand JSON output with full detail of leaf certificate i.e. subject and issuer, validity,
+    /// SANs, public key, signature algorithm and notable extensions
+    #[structopt(long)]
+    pub detail: bool,
+    /// Embed leaf certificate in PEM format in JSON output, e.g. for archiving
+    #[structopt(long)]
+    pub include_cert: bool,
+    /// Embed the rest of certificate chain in PEM format in JSON output besides leaf certificate
+    #[structopt(long)]
+    pub include_chain: bool,
+    /// Verify chain against trust store besides handshake and report whether each trusts it,
+    /// repeat to compare several e.g. --trust-store mozilla --trust-store system
+    /// --trust-store appliance=ca.pem, chain untrusted by any store is a warning
+    #[structopt(long = "trust-store", number_of_values = 1, value_name = "STORE")]
+    pub trust_stores: Vec<String>,
+    /// Verify certificate against name instead of server name sent in handshake, e.g. with
+    /// --sni tenant.example.com to request a tenant while expecting certificate of frontend
+    #[structopt(long, env = "HCC_VERIFY_HOSTNAME", value_name = "NAME")]
+    pub verify_hostname: Option<String>,
+    /// Grace period in days of intermediate and root certificates in chain, certificate in
+    /// chain expiring within it is a warning, defaults to grace period of leaf certificate
+    #[structopt(long, env = "HCC_CHAIN_GRACE_IN_DAYS", value_name = "DAYS")]
+    pub chain_grace_in_days: Option<i64>,
+    /// Send GET request for path instead of / once handshake completes, and capture status line
+    /// and headers of response e.g. Strict-Transport-Security in JSON output
+    #[structopt(long, env = "HCC_REQUEST", value_name = "PATH")]
+    pub request: Option<String>,
+    /// Host header of request, server name sent in handshake by default, and capture response
+    /// like --request
+    #[structopt(long, env = "HCC_REQUEST_HOST", value_name = "HOST")]
+    pub request_host: Option<String>,
+    /// Send no request after handshake, even if --request is given e.g. by environment
+    #[structopt(long)]
+    pub no_request: bool,
+    /// Upgrade plaintext connection to TLS before handshake with protocol, smtp, xmpp,
+    /// xmpp-server or ldap, check defaults to well-known port of protocol e.g. 5222 for xmpp
+    #[structopt(long, env = "HCC_STARTTLS", value_name = "PROTOCOL")]
+    pub starttls: Option<StartTls>,
+    /// Pause in milliseconds between start of consecutive checks, so scans of many hosts don't
+    /// look like an attack
+    #[structopt(long, env = "HCC_DELAY", value_name = "MS")]
+    pub delay: Option<u64>,
+    /// Start at most N checks per second on average, allowing bursts of N checks
+    #[structopt(long, env = "HCC_RATE_LIMIT", value_name = "N")]
+    pub rate_limit: Option<u32>,
+    /// Stop checking after runtime e.g. 90s, 5m or 1h, domain names left unchecked are
+    /// reported as skipped, as they are on SIGINT or SIGTERM
+    #[structopt(long, env = "HCC_MAX_RUNTIME", value_name = "TTL")]
+    pub max_runtime: Option<Ttl>,
+    /// Configuration file in TOML format
+    #[structopt(long, env = "HCC_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Reuse results of previous runs checked within --cache-ttl instead of handshaking again
+    #[structopt(long)]
+    pub cache: bool,
+    /// JSON file to store results for --cache
+    #[structopt(long, env = "HCC_CACHE_FILE", default_value = "hcc-cache.json")]
+    pub cache_file: PathBuf,
+    /// How long results are reused with --cache e.g. 90s, 30m, 1h or 1d
+    #[structopt(long, env = "HCC_CACHE_TTL", default_value = "1h", value_name = "TTL")]
+    pub cache_ttl: Ttl,
+    /// Only report domain names whose state, certificate or bucket of remaining days changed
+    /// since the previous run, for scheduled runs, exit code still counts every domain name
+    #[structopt(long, conflicts_with_all = &["flush-every", "flush-interval"])]
+    pub changed_only: bool,
+    /// JSON file to store outcome of each domain name for --changed-only
+    #[structopt(long, env = "HCC_STATE_FILE", default_value = "hcc-state.json")]
+    pub state_file: PathBuf,
+    /// JSON file to store remaining days of each domain name, so hooks fire only as
+    /// certificate crosses a threshold
+    #[structopt(long, env = "HCC_HOOK_STATE_FILE", default_value = "hcc-hooks.json")]
+    pub hook_state_file: PathBuf,
+    /// JSON file to store snoozed domain names
+    #[structopt(long, env = "HCC_SNOOZE_FILE", default_value = "snooze.json")]
+    pub snooze_file: PathBuf,
+    /// Print JSON schema of JSON output and exit
+    #[structopt(long)]
+    pub schema: bool,
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub enum Command {
+    /// Check domain name(s) immediately
+    #[structopt()]
+    Check {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// Server name sent in TLS handshake, required when checking IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Clear screen and check again every --interval seconds, highlighting changes since
+        /// the last check, until interrupted
+        #[structopt(long)]
+        watch: bool,
+        /// Seconds between checks in --watch mode
+        #[structopt(
+            long,
+            env = "HCC_INTERVAL",
+            default_value = "60",
+            value_name = "SECONDS",
+            parse(try_from_str = positive_seconds)
+        )]
+        interval: u64,
+        /// Only check domain names tagged with TAG in configuration file, repeat to require
+        /// several tags
+        #[structopt(long = "tag", number_of_values = 1, value_name = "TAG")]
+        tags: Vec<String>,
+        /// Require certificates to cover host name, where wildcard SANs count, repeat to require
+        /// several e.g. --expect-san api.example.com, certificate missing any is a warning
+        #[structopt(long = "expect-san", number_of_values = 1, value_name = "HOST")]
+        expect_sans: Vec<String>,
+        /// Check domain names on each of comma-separated ports e.g. --ports 443,8443, one result
+        /// per port, ignored for domain names with port or ports in configuration file
+        #[structopt(long, use_delimiter = true, value_name = "PORTS")]
+        ports: Vec<u16>,
+        /// Only check domain names matching glob pattern e.g. '*.example.com', repeat to match
+        /// any of several patterns
+        #[structopt(long, number_of_values = 1, value_name = "GLOB")]
+        include: Vec<String>,
+        /// Skip domain names matching glob pattern e.g. 'staging-*', repeat to skip several
+        #[structopt(long, number_of_values = 1, value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// Also check open TLS ports found in XML output of nmap or masscan, hosts without
+        /// hostname are checked by address and require --sni, skipped otherwise
+        #[structopt(long, env = "HCC_FROM_NMAP", value_name = "FILE")]
+        from_nmap: Option<PathBuf>,
+        /// Also check certificates issued by PKI secrets engine of HashiCorp Vault mounted at
+        /// path e.g. pki, at VAULT_ADDR with VAULT_TOKEN, repeat to check several mounts
+        #[structopt(long = "vault-pki", number_of_values = 1, value_name = "MOUNT")]
+        vault_pki: Vec<String>,
+        /// Also check certificates in AWS Certificate Manager of region with credentials of
+        /// AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, repeat to check several regions
+        #[structopt(long = "aws-acm", number_of_values = 1, value_name = "REGION")]
+        aws_acm: Vec<String>,
+        /// Also check server certificates of AWS IAM, which load balancers may use instead of
+        /// certificates in ACM
+        #[structopt(long)]
+        aws_iam: bool,
+        /// Also check certificates in Google Cloud Certificate Manager of project, at global
+        /// location unless PROJECT/LOCATION, with access token in GOOGLE_OAUTH_ACCESS_TOKEN,
+        /// repeat to check several
+        #[structopt(
+            long = "gcp-certificate-manager",
+            number_of_values = 1,
+            value_name = "PROJECT"
+        )]
+        gcp_certificate_manager: Vec<String>,
+        /// Run command when certificate drops below --hook-days e.g. --hook 'certbot renew
+        /// --cert-name {domain}', domain name replaces {domain} or is appended, repeat to run
+        /// several, or comma-separated commands in HCC_HOOKS
+        #[structopt(long = "hook", number_of_values = 1, value_name = "COMMAND")]
+        hooks: Vec<String>,
+        /// POST result in JSON format to URL when certificate drops below --hook-days, repeat to
+        /// post to several, or comma-separated URLs in HCC_WEBHOOKS
+        #[structopt(long = "webhook", number_of_values = 1, value_name = "URL")]
+        webhooks: Vec<String>,
+        /// Fire --hook and --webhook when certificate expires within days, grace period by
+        /// default
+        #[structopt(long, env = "HCC_HOOK_DAYS", value_name = "DAYS")]
+        hook_days: Option<i64>,
+        /// Also check from remote agent i.e. hcc-server at another vantage point as
+        /// LOCATION=URL, repeat to check from several, or comma-separated agents in HCC_AGENTS,
+        /// results are reported per location
+        #[structopt(long = "agent", number_of_values = 1, value_name = "LOCATION=URL")]
+        agents: Vec<Agent>,
+        /// Bearer token required by agents
+        #[structopt(long, env = "HCC_AGENT_TOKEN", hide_env_values = true)]
+        agent_token: Option<String>,
+        /// One or many domain names to check e.g. example.com:8443 or [2001:db8::1]:8443, check
+        /// domain names in configuration file if none is given
+        #[structopt()]
+        domain_names: Vec<String>,
+    },
+    /// Check certificates of mail servers of domain name(s) in MX records, with STARTTLS on
+    /// ports 25 and 587 and implicit TLS on port 465
+    #[structopt()]
+    CheckMx {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// SMTP port to check, repeat to check several e.g. -p 25 -p 465 -p 587
+        #[structopt(
+            short,
+            long = "port",
+            env = "HCC_MX_PORTS",
+            number_of_values = 1,
+            use_delimiter = true,
+            default_value = "25"
+        )]
+        ports: Vec<u16>,
+        /// Also validate MTA-STS policy of domain name(s), mail servers not allowed by policy in
+        /// enforce mode are degraded to warning
+        #[structopt(long)]
+        mta_sts: bool,
+        /// One or many mail domain names e.g. example.com
+        #[structopt(required = true)]
+        domain_names: Vec<String>,
+    },
+    /// Check certificate(s) published at HTTP(S) URL(s) in PEM or DER format e.g. certificate
+    /// of a CA, the earliest expiring one counts when PEM bundles several
+    #[structopt()]
+    CheckUrl {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// One or many URLs to check e.g. https://letsencrypt.org/certs/isrgrootx1.pem
+        #[structopt()]
+        urls: Vec<String>,
+    },
+    /// Check certificate(s) in PEM or DER file(s), or standard input given `-` e.g. piped from
+    /// openssl s_client or vault, the earliest expiring one counts when PEM bundles several
+    #[structopt()]
+    CheckFile {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// One or many paths of certificate files, `-` reads standard input
+        #[structopt(required = true)]
+        paths: Vec<String>,
+    },
+    /// Check certificates in certificate store of operating system, SChannel store on
+    /// Windows, keychains on macOS and system CA bundle elsewhere
+    #[structopt()]
+    CheckStore {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// Store to check e.g. CurrentUser\My on Windows, path of keychain on macOS or path of
+        /// PEM bundle elsewhere, LocalMachine\My, keychains in search list or system CA bundle
+        /// by default
+        #[structopt(long, env = "HCC_STORE", value_name = "STORE")]
+        store: Option<String>,
+    },
+    /// Check OpenSSH host certificates of SSH server(s), on port 22 unless host carries a
+    /// port e.g. bastion.example.com:2222
+    #[structopt()]
+    Ssh {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// One or many SSH servers e.g. bastion.example.com
+        #[structopt(required = true)]
+        hosts: Vec<String>,
+    },
+    /// Show domain names in an interactive dashboard refreshed every interval, with keys to
+    /// sort, view details and recheck
+    #[structopt()]
+    Tui {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// Server name sent in TLS handshake, required when checking IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Seconds between checks of all domain names
+        #[structopt(
+            long,
+            env = "HCC_INTERVAL",
+            default_value = "300",
+            value_name = "SECONDS",
+            parse(try_from_str = positive_seconds)
+        )]
+        interval: u64,
+        /// Only check domain names tagged with TAG in configuration file, repeat to require
+        /// several tags
+        #[structopt(long = "tag", number_of_values = 1, value_name = "TAG")]
+        tags: Vec<String>,
+        /// Only check domain names matching glob pattern e.g. '*.example.com', repeat to match
+        /// any of several patterns
+        #[structopt(long, number_of_values = 1, value_name = "GLOB")]
+        include: Vec<String>,
+        /// Skip domain names matching glob pattern e.g. 'staging-*', repeat to skip several
+        #[structopt(long, number_of_values = 1, value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// One or many domain names to check, check domain names in configuration file if none
+        /// is given
+        #[structopt()]
+        domain_names: Vec<String>,
+    },
+    /// Compare certificates presented by two domain names, or a domain name and a PEM or DER
+    /// file, exit with 4 when they differ
+    #[structopt()]
+    Compare {
+        /// Server name sent in TLS handshake, required when checking IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Domain name e.g. example.com:8443 or https://example.com, or certificate file as
+        /// file:PATH
+        #[structopt()]
+        left: String,
+        /// Domain name e.g. origin.example.com, or certificate file as file:PATH
+        #[structopt()]
+        right: String,
+    },
+    /// Scan IP address range(s) for TLS certificates e.g. to discover forgotten appliances,
+    /// certificates are accepted without verification, addresses presenting none are skipped
+    #[structopt()]
+    Scan {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// Port to scan, repeat to scan several e.g. -p 443 -p 8443
+        #[structopt(
+            short,
+            long = "port",
+            env = "HCC_SCAN_PORTS",
+            number_of_values = 1,
+            use_delimiter = true,
+            default_value = "443"
+        )]
+        ports: Vec<u16>,
+        /// Server name sent in TLS handshake, none is sent by default
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Seconds to wait for each address to connect and complete handshake
+        #[structopt(
+            long,
+            env = "HCC_SCAN_TIMEOUT",
+            default_value = "2",
+            value_name = "SECONDS"
+        )]
+        timeout: u64,
+        /// Addresses scanned at once
+        #[structopt(
+            long,
+            env = "HCC_SCAN_CONCURRENCY",
+            default_value = "32",
+            value_name = "N"
+        )]
+        concurrency: usize,
+        /// One or many ranges in CIDR notation e.g. 10.0.0.0/24, or single addresses
+        #[structopt(required = true)]
+        ranges: Vec<Cidr>,
+    },
+    /// Probe ports of a host for TLS e.g. to find admin UIs of appliances on odd ports, and
+    /// report certificate presented on each port completing handshake
+    #[structopt()]
+    ScanPorts {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// Comma-separated ports and ranges e.g. 443,8000-9000, or common for ports commonly
+        /// serving TLS
+        #[structopt(
+            long,
+            env = "HCC_SCAN_PORTS",
+            default_value = "common",
+            value_name = "PORTS"
+        )]
+        ports: PortSet,
+        /// Server name sent in TLS handshake, host by default, none for IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Seconds to wait for each port to connect and complete handshake
+        #[structopt(
+            long,
+            env = "HCC_SCAN_TIMEOUT",
+            default_value = "2",
+            value_name = "SECONDS"
+        )]
+        timeout: u64,
+        /// Ports probed at once
+        #[structopt(
+            long,
+            env = "HCC_SCAN_CONCURRENCY",
+            default_value = "32",
+            value_name = "N"
+        )]
+        concurrency: usize,
+        /// Domain name or IP address to probe
+        #[structopt()]
+        host: String,
+    },
+    /// Check domain names and publish results as a static status site, index.html and
+    /// status.json, to a directory or a bucket of S3-compatible storage
+    #[structopt()]
+    Publish {
+        /// Grace period in days, defaults to grace_in_days in configuration file or 7
+        #[structopt(short, long = "grace", env = "HCC_GRACE")]
+        grace_in_days: Option<i64>,
+        /// Server name sent in TLS handshake, required when checking IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Only check domain names tagged with TAG in configuration file, repeat to require
+        /// several tags
+        #[structopt(long = "tag", number_of_values = 1, value_name = "TAG")]
+        tags: Vec<String>,
+        /// Only check domain names matching glob pattern e.g. '*.example.com', repeat to match
+        /// any of several patterns
+        #[structopt(long, number_of_values = 1, value_name = "GLOB")]
+        include: Vec<String>,
+        /// Skip domain names matching glob pattern e.g. 'staging-*', repeat to skip several
+        #[structopt(long, number_of_values = 1, value_name = "GLOB")]
+        exclude: Vec<String>,
+        /// Title of status page
+        #[structopt(long, env = "HCC_PUBLISH_TITLE", default_value = "Certificate status")]
+        title: String,
+        /// Directory to write site to, created if missing
+        #[structopt(long, env = "HCC_PUBLISH_DIR", value_name = "DIR")]
+        dir: Option<PathBuf>,
+        /// Bucket to upload site to as s3://BUCKET[/PREFIX], signed with AWS_ACCESS_KEY_ID and
+        /// AWS_SECRET_ACCESS_KEY
+        #[structopt(long, env = "HCC_PUBLISH_S3", value_name = "URL")]
+        s3: Option<String>,
+        /// Endpoint of S3-compatible storage e.g. MinIO or Cloudflare R2
+        #[structopt(long, env = "HCC_S3_ENDPOINT", default_value = DEFAULT_S3_ENDPOINT, value_name = "URL")]
+        s3_endpoint: String,
+        /// Region of bucket
+        #[structopt(long, env = "AWS_REGION", default_value = "us-east-1")]
+        s3_region: String,
+        /// One or many domain names to check, check domain names in configuration file if none
+        /// is given
+        #[structopt()]
+        domain_names: Vec<String>,
+    },
+    /// Inspect TLS versions and certificate chain of domain name(s), and estimate minimum
+    /// versions of common clients able to connect
+    #[structopt()]
+    Inspect {
+        /// Server name sent in TLS handshake, required when checking IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Also fetch grades from public SSL Labs API, which may take minutes per host
+        #[structopt(long)]
+        ssllabs: bool,
+        /// One or many domain names to inspect
+        #[structopt()]
+        domain_names: Vec<String>,
+    },
+    /// Repeat TLS handshakes with domain name and report connect and handshake times, full
+    /// handshakes and session resumptions apart
+    #[structopt()]
+    Bench {
+        /// Server name sent in TLS handshake, required when checking IP address
+        #[structopt(long, env = "HCC_SNI")]
+        sni: Option<String>,
+        /// Number of handshakes, every other one resumes session of handshake before
+        #[structopt(
+            short,
+            long,
+            env = "HCC_BENCH_COUNT",
+            default_value = "10",
+            value_name = "N"
+        )]
+        count: usize,
+        /// Domain name to benchmark
+        #[structopt()]
+        domain_name: String,
+    },
+    /// Silence alerts of domain name(s) until a date, or list snoozed domain names
+    #[structopt()]
+    Snooze {
+        /// Date in YYYY-MM-DD format until which alerts are silenced
+        #[structopt(short, long)]
+        until: Option<NaiveDate>,
+        /// Remove snooze of domain name(s) instead
+        #[structopt(short, long)]
+        remove: bool,
+        /// One or many domain names to snooze, list snoozed domain names if none is given
+        #[structopt()]
+        domain_names: Vec<String>,
+    },
+    /// Print shell completion script to standard output e.g. hcc completions bash >
+    /// /etc/bash_completion.d/hcc
+    #[structopt()]
+    Completions {
+        /// Shell to generate completion script for
+        #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+        shell: Shell,
+    },
+    /// Print man page in roff format to standard output e.g. hcc man > /usr/share/man/man1/hcc.1
+    #[structopt()]
+    Man,
+    /// Replace this executable with binary of the latest release on GitHub for this platform,
+    /// after verifying signature of checksums and its checksum
+    #[structopt()]
+    SelfUpdate {
+        /// Only tell whether a newer release is available, exit code is 1 if so
+        #[structopt(long)]
+        check: bool,
+        /// Install release of tag e.g. v0.2.0 instead of the latest, even if it's older
+        #[structopt(long)]
+        tag: Option<String>,
+        /// Ed25519 public key in base64 to verify signature of checksums with, defaults to key
+        /// releases of hcc are signed with
+        #[structopt(long, env = "HCC_UPDATE_PUBLIC_KEY", default_value = update::PUBLIC_KEY)]
+        public_key: String,
+        /// Repository on GitHub to download releases from as OWNER/NAME
+        #[structopt(long, env = "HCC_UPDATE_REPOSITORY", default_value = update::REPOSITORY)]
+        repository: String,
+    },
+    /// Verify signature of report written with --sign-key, fails if report is not signed by
+    /// key or was altered
+    #[structopt()]
+    VerifyReport {
+        /// Ed25519 public key in PEM file e.g. of `openssl pkey -pubout`
+        #[structopt(long, env = "HCC_VERIFY_KEY", value_name = "PATH")]
+        public_key: PathBuf,
+        /// Path of report, `-` reads standard input
+        #[structopt()]
+        path: String,
+    },
+}
+
+pub const DEFAULT_GRACE_IN_DAYS: i64 = 7;
+
+/// Fill flags and lists not given on command line from environment variables e.g. HCC_JSON=1
+/// or HCC_DOMAINS=example.com,example.org, since clap reads environment variables only for
+/// options taking one value
+pub fn apply_env<F: Fn(&str) -> Option<String>>(opts: &mut Opts, var: F) -> anyhow::Result<()> {
+    let enabled = |name: &str| {
+        var(name).is_some_and(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+    };
+    let list = |name: &str| -> Vec<String> {
+        var(name)
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    opts.json |= enabled("HCC_JSON");
+    opts.fail_fast |= enabled("HCC_FAIL_FAST");
+    opts.quiet |= enabled("HCC_QUIET");
+    opts.group_by_cert |= enabled("HCC_GROUP_BY_CERT");
+    opts.dogstatsd |= enabled("HCC_DOGSTATSD");
+    opts.cache |= enabled("HCC_CACHE");
+    opts.changed_only |= enabled("HCC_CHANGED_ONLY");
+    opts.detail |= enabled("HCC_DETAIL");
+    opts.include_cert |= enabled("HCC_INCLUDE_CERT");
+    opts.include_chain |= enabled("HCC_INCLUDE_CHAIN");
+    opts.plain_numbers |= enabled("HCC_PLAIN_NUMBERS");
+    opts.human |= enabled("HCC_HUMAN");
+    opts.no_request |= enabled("HCC_NO_REQUEST");
+    if opts.locale.is_none() {
+        // POSIX precedence, unknown locales fall back to default
+        opts.locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| var(name).filter(|v| !v.is_empty()))
+            .next()
+            .and_then(|v| v.parse().ok());
+    }
+    if opts.verbose == 0 {
+        if let Some(v) = var("HCC_VERBOSE") {
+            opts.verbose = v.parse().context("invalid HCC_VERBOSE")?;
+        }
+    }
+    if opts.sinks.is_empty() {
+        for s in list("HCC_SINK") {
+            opts.sinks.push(s.parse().context("invalid HCC_SINK")?);
+        }
+    }
+    if opts.formatters.is_empty() {
+        for s in list("HCC_FORMATTER") {
+            opts.formatters
+                .push(s.parse().context("invalid HCC_FORMATTER")?);
+        }
+    }
+    if opts.notifiers.is_empty() {
+        opts.notifiers = list("HCC_NOTIFIER");
+    }
+    if opts.ics_alarms.is_empty() {
+        for s in list("HCC_ICS_ALARMS") {
+            opts.ics_alarms
+                .push(s.parse().context("invalid HCC_ICS_ALARMS")?);
+        }
+    }
+    if opts.exit_codes.is_empty() {
+        for s in list("HCC_EXIT_CODE") {
+            opts.exit_codes
+                .push(s.parse().context("invalid HCC_EXIT_CODE")?);
+        }
+    }
+    if opts.trust_stores.is_empty() {
+        opts.trust_stores = list("HCC_TRUST_STORES");
+    }
+    if let Some(Command::Check {
+        ref mut expect_sans,
+        ..
+    }) = opts.command
+    {
+        if expect_sans.is_empty() {
+            *expect_sans = list("HCC_EXPECT_SANS");
+        }
+    }
+    if let Some(Command::Check {
+        ref mut watch,
+        ref mut hooks,
+        ref mut webhooks,
+        ..
+    }) = opts.command
+    {
+        *watch |= enabled("HCC_WATCH");
+        if hooks.is_empty() {
+            *hooks = list("HCC_HOOKS");
+        }
+        if webhooks.is_empty() {
+            *webhooks = list("HCC_WEBHOOKS");
+        }
+    }
+    if let Some(Command::CheckMx {
+        ref mut mta_sts, ..
+    }) = opts.command
+    {
+        *mta_sts |= enabled("HCC_MTA_STS");
+    }
+    if let Some(Command::Check {
+        ref mut vault_pki, ..
+    }) = opts.command
+    {
+        if vault_pki.is_empty() {
+            *vault_pki = list("HCC_VAULT_PKI");
+        }
+    }
+    if let Some(Command::Check {
+        ref mut aws_acm,
+        ref mut aws_iam,
+        ..
+    }) = opts.command
+    {
+        if aws_acm.is_empty() {
+            *aws_acm = list("HCC_AWS_ACM");
+        }
+        *aws_iam |= enabled("HCC_AWS_IAM");
+    }
+    if let Some(Command::Check {
+        ref mut gcp_certificate_manager,
+        ..
+    }) = opts.command
+    {
+        if gcp_certificate_manager.is_empty() {
+            *gcp_certificate_manager = list("HCC_GCP_CERTIFICATE_MANAGER");
+        }
+    }
+    if let Some(Command::Check { ref mut agents, .. }) = opts.command {
+        if agents.is_empty() {
+            for s in list("HCC_AGENTS") {
+                agents.push(s.parse().context("invalid HCC_AGENTS")?);
+            }
+        }
+    }
+    if let Some(Command::Check { ref mut ports, .. }) = opts.command {
+        if ports.is_empty() {
+            for s in list("HCC_PORTS") {
+                ports.push(s.parse().context("invalid HCC_PORTS")?);
+            }
+        }
+    }
+    match opts.command {
+        Some(Command::Check {
+            ref mut domain_names,
+            ref mut tags,
+            ref mut include,
+            ref mut exclude,
+            ..
+        })
+        | Some(Command::Tui {
+            ref mut domain_names,
+            ref mut tags,
+            ref mut include,
+            ref mut exclude,
+            ..
+        })
+        | Some(Command::Publish {
+            ref mut domain_names,
+            ref mut tags,
+            ref mut include,
+            ref mut exclude,
+            ..
+        }) => {
+            if domain_names.is_empty() {
+                *domain_names = list("HCC_DOMAINS");
+            }
+            if tags.is_empty() {
+                *tags = list("HCC_TAGS");
+            }
+            if include.is_empty() {
+                *include = list("HCC_INCLUDE");
+            }
+            if exclude.is_empty() {
+                *exclude = list("HCC_EXCLUDE");
+            }
+        }
+        Some(Command::CheckUrl { ref mut urls, .. }) if urls.is_empty() => {
+            *urls = list("HCC_URLS");
+            if urls.is_empty() {
+                anyhow::bail!("one or many URLs are required, or set HCC_URLS");
+            }
+        }
+        Some(Command::Inspect {
+            ref mut domain_names,
+            ref mut ssllabs,
+            ..
+        }) => {
+            *ssllabs |= enabled("HCC_SSLLABS");
+            if domain_names.is_empty() {
+                *domain_names = list("HCC_DOMAINS");
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Seconds between repeated checks, zero would check again without pause
+fn positive_seconds(s: &str) -> anyhow::Result<u64> {
+    match s.parse()? {
+        0 => anyhow::bail!("interval must be positive"),
+        secs => Ok(secs),
+    }
+}
+
+/// Grace period in days from command line or environment, then configuration file
+pub fn grace_in_days_of(grace_in_days: Option<i64>, config: &Config) -> i64 {
+    grace_in_days
+        .or(config.grace_in_days)
+        .unwrap_or(DEFAULT_GRACE_IN_DAYS)
+}
+
+pub fn completions_command<W: std::io::Write>(shell: Shell, buf: &mut W) {
+    Opts::clap().gen_completions_to(env!("CARGO_BIN_NAME"), shell, buf);
+}
+
+/// Render help of command and subcommands as sections of a roff man page
+pub fn man_command<W: std::io::Write>(buf: &mut W) -> anyhow::Result<()> {
+    let bin_name = env!("CARGO_BIN_NAME");
+    let app = Opts::clap().set_term_width(80);
+    let mut page = format!(
+        ".TH {0} 1 \"\" \"{1} {2}\" \"User Commands\"\n",
+        bin_name.to_uppercase(),
+        bin_name,
+        env!("CARGO_PKG_VERSION")
+    );
+    page.push_str(&format!(
+        ".SH NAME\n{0} \\- {1}\n",
+        bin_name,
+        env!("CARGO_PKG_DESCRIPTION")
+    ));
+    page.push_str(".SH DESCRIPTION\n");
+    page.push_str(&roff_block(&help_of(&app, &[bin_name])?));
+    page.push_str(".SH COMMANDS\n");
+    for subcommand in app.p.subcommands.iter().map(|s| s.get_name()) {
+        page.push_str(&format!(".SS {0}\n", subcommand));
+        page.push_str(&roff_block(&help_of(
+            &app,
+            &[bin_name, subcommand, "--help"],
+        )?));
+    }
+    buf.write_all(page.as_bytes())?;
+    Ok(())
+}
+
+/// Help message clap prints for arguments
+pub fn help_of(app: &structopt::clap::App, args: &[&str]) -> anyhow::Result<String> {
+    let mut args = args.to_vec();
+    if args.len() == 1 {
+        args.push("--help");
+    }
+    match app.clone().get_matches_from_safe(args) {
+        Err(e) if e.kind == structopt::clap::ErrorKind::HelpDisplayed => Ok(e.message),
+        Err(e) => Err(e.into()),
+        Ok(_) => anyhow::bail!("no help message printed"),
+    }
+}
+
+/// Preformatted roff block, escaping backslashes and leading control characters
+pub fn roff_block(s: &str) -> String {
+    let mut block = String::from(".nf\n");
+    for line in s.lines() {
+        let line = line.replace('\\', "\\e");
+        if line.starts_with('.') || line.starts_with('\'') {
+            block.push_str("\\&");
+        }
+        block.push_str(&line);
+        block.push('\n');
+    }
+    block.push_str(".fi\n");
+    block
+}
+
+#[cfg(test)]
+mod test {
+    use structopt::clap::Shell;
+    use structopt::StructOpt;
+
+    use hcc::{Config, ExitCodes, NumberFormat};
+
+    use crate::cli::{
+        apply_env, completions_command, grace_in_days_of, man_command, Command, Opts,
+    };
+
+    #[test]
+    fn test_completions_command() {
+        for shell in &[Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut buf = vec![];
+            completions_command(*shell, &mut buf);
+            let s = String::from_utf8(buf).unwrap();
+            assert!(s.contains("snooze"), "{0:?}", shell);
+        }
+    }
+
+    #[test]
+    fn test_man_command() {
+        let mut buf = vec![];
+        man_command(&mut buf).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(s.starts_with(".TH HCC 1"));
+        assert!(s.contains(".SS check\n"));
+        assert!(s.contains("--grace"));
+    }
+
+    #[test]
+    fn test_interval() {
+        let opts = Opts::from_iter(&["hcc", "check", "--watch", "--interval", "5"]);
+        assert!(matches!(
+            opts.command,
+            Some(Command::Check { interval: 5, .. })
+        ));
+        assert!(Opts::from_iter_safe(&["hcc", "check", "--watch", "--interval", "0"]).is_err());
+        assert!(Opts::from_iter_safe(&["hcc", "tui", "--interval", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_env() {
+        let env = |name: &str| match name {
+            "HCC_JSON" => Some("true".to_string()),
+            "HCC_VERBOSE" => Some("2".to_string()),
+            "HCC_EXIT_CODE" => Some("error=7, warning=0".to_string()),
+            "HCC_DOMAINS" => Some("example.com,example.org".to_string()),
+            "HCC_TAGS" => Some("prod".to_string()),
+            "HCC_WATCH" => Some("1".to_string()),
+            "HCC_HOOKS" => Some("certbot renew".to_string()),
+            "HCC_TRUST_STORES" => Some("mozilla,system".to_string()),
+            "HCC_NO_REQUEST" => Some("yes".to_string()),
+            "HCC_MTA_STS" => Some("on".to_string()),
+            "LC_NUMERIC" => Some(String::new()),
+            "LANG" => Some("de_DE.UTF-8".to_string()),
+            _ => None,
+        };
+
+        let mut opts = Opts::from_iter(&["hcc", "check"]);
+        apply_env(&mut opts, env).unwrap();
+        assert!(opts.json);
+        assert!(!opts.quiet);
+        assert_eq!(2, opts.verbose);
+        assert_eq!(7, ExitCodes::new(&opts.exit_codes).error);
+        assert_eq!("de".parse().ok(), opts.locale);
+        assert_eq!(vec!["mozilla", "system"], opts.trust_stores);
+        assert!(opts.no_request);
+        match opts.command {
+            Some(Command::Check {
+                domain_names,
+                tags,
+                watch,
+                hooks,
+                ..
+            }) => {
+                assert_eq!(vec!["example.com", "example.org"], domain_names);
+                assert_eq!(vec!["prod"], tags);
+                assert!(watch);
+                assert_eq!(vec!["certbot renew"], hooks);
+            }
+            _ => unreachable!(),
+        }
+
+        let mut opts = Opts::from_iter(&["hcc", "check-mx", "example.com"]);
+        apply_env(&mut opts, env).unwrap();
+        assert!(matches!(
+            opts.command,
+            Some(Command::CheckMx { mta_sts: true, .. })
+        ));
+
+        // command line wins over environment variables
+        let mut opts = Opts::from_iter(&[
+            "hcc",
+            "-v",
+            "--exit-code",
+            "error=9",
+            "--locale",
+            "C",
+            "check",
+            "a.com",
+        ]);
+        apply_env(&mut opts, env).unwrap();
+        assert_eq!(1, opts.verbose);
+        assert_eq!(Some(NumberFormat::Plain), opts.locale);
+        assert_eq!(9, ExitCodes::new(&opts.exit_codes).error);
+        match opts.command {
+            Some(Command::Check { domain_names, .. }) => {
+                assert_eq!(vec!["a.com"], domain_names)
+            }
+            _ => unreachable!(),
+        }
+
+        let mut opts = Opts::from_iter(&["hcc", "check-url"]);
+        assert!(apply_env(&mut opts, env).is_err());
+    }
+
+    #[test]
+    fn test_grace_in_days_of() {
+        let config: Config = "grace_in_days = 14".parse().unwrap();
+        assert_eq!(3, grace_in_days_of(Some(3), &config));
+        assert_eq!(14, grace_in_days_of(None, &config));
+        assert_eq!(7, grace_in_days_of(None, &Config::default()));
+    }
+}
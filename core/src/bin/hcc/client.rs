@@ -0,0 +1,1145 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use chrono::{SubsecRound, Utc};
+use futures::executor;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use tracing::warn;
+use uuid::Uuid;
+
+use hcc::{
+    has_mta_sts_record, mail_servers, Agent, AgentRequest, Cache, Cancellation, CheckClient,
+    CheckClientBuilder, CheckError, CheckResult, CheckResultJSON, CheckState, Cidr,
+    CloudCertificate, Config, ExitCodes, HookState, HttpRequest, IncludeCertificates, MtaStsMode,
+    MtaStsPolicy, OsStore, Policy, RateLimiter, S3Bucket, Sink, Snoozes, StartTls, StatusPage,
+    Summary, Target, TextStyle, TrustStore, VaultCertificate, SMTPS_PORT, SMTP_PORTS,
+};
+
+use crate::cli::Opts;
+use crate::output::{build_sinks, report, sort_results, text_style};
+use crate::policy::{annotate, exit_code};
+
+/// Cancellation of this run, by SIGINT, SIGTERM or --max-runtime
+static CANCELLATION: OnceLock<Cancellation> = OnceLock::new();
+
+/// Cancellation of this run, signals are handled from the first call on i.e. when checks
+/// start, and a second signal terminates at once
+pub fn cancellation(opts: &Opts) -> &'static Cancellation {
+    CANCELLATION.get_or_init(|| {
+        let cancellation = Cancellation::new(opts.max_runtime.as_ref().map(|ttl| ttl.0));
+        match Signals::new([SIGINT, SIGTERM]) {
+            Ok(mut signals) => {
+                let cancellation = cancellation.clone();
+                thread::spawn(move || {
+                    for (i, signal) in signals.forever().enumerate() {
+                        if i > 0 {
+                            std::process::exit(128 + signal);
+                        }
+                        eprintln!("interrupted, reporting results so far, again to quit at once");
+                        cancellation.cancel();
+                    }
+                });
+            }
+            Err(e) => warn!("failed to handle signals: {0}", e),
+        }
+        cancellation
+    })
+}
+
+/// Whether this run is cancelled, never before checks start
+pub fn cancelled() -> bool {
+    CANCELLATION.get().is_some_and(Cancellation::is_cancelled)
+}
+
+pub fn build_client(
+    opts: &Opts,
+    policy: Option<Policy>,
+    grace_in_days: i64,
+    sni: Option<String>,
+) -> anyhow::Result<CheckClient> {
+    Ok(client_builder(opts, policy, grace_in_days, sni)?.build())
+}
+
+fn client_builder(
+    opts: &Opts,
+    policy: Option<Policy>,
+    grace_in_days: i64,
+    sni: Option<String>,
+) -> anyhow::Result<CheckClientBuilder> {
+    let mut builder = CheckClient::builder();
+    builder
+        .cancellation(cancellation(opts).clone())
+        .detail(opts.detail)
+        .elapsed(opts.verbose > 0)
+        .grace_in_days(grace_in_days)
+        .chain_grace_in_days(opts.chain_grace_in_days)
+        .policy(policy)
+        .sni(sni)
+        .starttls(opts.starttls);
+    if opts.no_request {
+        builder.no_request(true);
+    } else if opts.request.is_some() || opts.request_host.is_some() {
+        let request = HttpRequest {
+            path: opts.request.clone().unwrap_or_else(|| "/".to_string()),
+            host: opts.request_host.clone(),
+        };
+        request.validate()?;
+        builder.http_request(Some(request));
+    }
+    if opts.include_chain {
+        builder.include_certificates(IncludeCertificates::Chain);
+    } else if opts.include_cert {
+        builder.include_certificates(IncludeCertificates::Leaf);
+    }
+    builder.verify_hostname(opts.verify_hostname.clone())?;
+    for spec in &opts.trust_stores {
+        builder.trust_store(TrustStore::open(spec)?);
+    }
+    if let Some(ms) = opts.delay.filter(|ms| *ms > 0) {
+        builder.rate_limiter(RateLimiter::delay(Duration::from_millis(ms)));
+    }
+    if let Some(n) = opts.rate_limit.filter(|n| *n > 0) {
+        builder.rate_limiter(RateLimiter::new(n as f64, n));
+    }
+    Ok(builder)
+}
+
+pub fn check_command(
+    opts: &Opts,
+    config: &Config,
+    domain_names: &[&str],
+    inventory: &Inventory,
+    agents: &[Agent],
+    grace_in_days: i64,
+    sni: Option<String>,
+) -> anyhow::Result<i32> {
+    let policy = config.effective_policy();
+    // options affecting results, results checked with other options are not reused
+    let variant = format!(
+        "grace={0},chain_grace={4},sni={1},verify={5},starttls={2},trust={3},policy={6:?}",
+        grace_in_days,
+        sni.as_deref().unwrap_or_default(),
+        opts.starttls.map(|s| s.to_string()).unwrap_or_default(),
+        opts.trust_stores.join("+"),
+        opts.chain_grace_in_days.unwrap_or(grace_in_days),
+        opts.verify_hostname.as_deref().unwrap_or_default(),
+        policy
+    );
+    // cache keeps no certificate nor response, so results embedding certificates, their detail
+    // or response to request are always checked again
+    let requested = (opts.request.is_some() || opts.request_host.is_some()) && !opts.no_request;
+    let mut cache =
+        if opts.cache && !opts.detail && !opts.include_cert && !opts.include_chain && !requested {
+            Some(Cache::load(&opts.cache_file, opts.cache_ttl.0)?)
+        } else {
+            None
+        };
+    let client = build_client(opts, policy, grace_in_days, sni.clone())?;
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    let sinks = build_sinks(opts);
+    let incremental = opts.flush_every.is_some() || opts.flush_interval.is_some();
+
+    // agents check while domain names are checked here
+    let pending: Vec<_> = agents
+        .iter()
+        .map(|agent| {
+            let agent = agent.clone();
+            let location = agent.clone();
+            let domain_names: Vec<String> = domain_names.iter().map(|d| d.to_string()).collect();
+            let starttls = opts.starttls.map(|s| s.to_string());
+            let sni = sni.clone();
+            let handle = thread::spawn(move || {
+                let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+                let request = AgentRequest {
+                    domain_names: &domain_names,
+                    starttls,
+                    sni,
+                    grace_in_days,
+                    ..Default::default()
+                };
+                agent.check(&request)
+            });
+            (location, handle)
+        })
+        .collect();
+
+    let mut results = if incremental || opts.fail_fast {
+        let mut results = vec![];
+        let mut flushed = 0;
+        let mut last_flush = Instant::now();
+        for domain_name in domain_names {
+            let mut result = match cache
+                .as_ref()
+                .and_then(|c| c.get(domain_name, &variant, &now))
+            {
+                Some(result) => result,
+                None => {
+                    let result = match executor::block_on(client.check_certificate(domain_name)) {
+                        Ok(result) => result,
+                        Err(e) if e.is_usage_error() => return Err(e.into()),
+                        Err(e) => CheckResult::failed(Uuid::new_v4(), domain_name, &now, e),
+                    };
+                    if let Some(ref mut cache) = cache {
+                        cache.insert(&result, &variant);
+                    }
+                    result
+                }
+            };
+            annotate(&mut result, config, &snoozes, &now);
+            let abort = opts.fail_fast
+                && result.is_failure()
+                && matches!(result.state, CheckState::Expired);
+            results.push(result);
+
+            let due = opts
+                .flush_every
+                .is_some_and(|n| results.len() - flushed >= n)
+                || opts
+                    .flush_interval
+                    .is_some_and(|secs| last_flush.elapsed() >= Duration::from_secs(secs));
+            let last = abort || results.len() == domain_names.len();
+            if incremental && (due || last) {
+                emit_batch(opts, &sinks, results[flushed..].to_vec())?;
+                eprintln!(
+                    "progress: {0}/{1}, {2}",
+                    results.len(),
+                    domain_names.len(),
+                    Summary::new(&results, grace_in_days)
+                );
+                flushed = results.len();
+                last_flush = Instant::now();
+            }
+            if abort {
+                eprintln!(
+                    "abort after {0} expired, {1} domain name(s) left unchecked",
+                    domain_name,
+                    domain_names.len() - results.len()
+                );
+                break;
+            }
+        }
+        results
+    } else {
+        let cached: Vec<Option<CheckResult>> = domain_names
+            .iter()
+            .map(|d| cache.as_ref().and_then(|c| c.get(d, &variant, &now)))
+            .collect();
+        let missing: Vec<&str> = domain_names
+            .iter()
+            .zip(cached.iter())
+            .filter(|(_, c)| c.is_none())
+            .map(|(d, _)| *d)
+            .collect();
+        let mut checked = client.check_certificates(&missing)?.into_iter();
+        let mut results = vec![];
+        for result in cached {
+            let mut result = match result {
+                Some(result) => result,
+                None => {
+                    let result = checked.next().context("missing result")?;
+                    if let Some(ref mut cache) = cache {
+                        cache.insert(&result, &variant);
+                    }
+                    result
+                }
+            };
+            annotate(&mut result, config, &snoozes, &now);
+            results.push(result);
+        }
+        results
+    };
+
+    // certificates in Vault and clouds may have no endpoint to connect to, so they are
+    // checked as stored
+    let mut stored = vec![];
+    for certificate in &inventory.vault {
+        let result = client
+            .check_pem(&certificate.name, certificate.pem.as_bytes())
+            .unwrap_or_else(|e| CheckResult::failed(Uuid::new_v4(), &certificate.name, &now, e));
+        stored.push(result);
+    }
+    stored.extend(inventory.cloud.iter().map(|c| client.check_inventory(c)));
+    for result in stored.iter_mut() {
+        annotate(result, config, &snoozes, &now);
+    }
+    if incremental && !stored.is_empty() {
+        emit_batch(opts, &sinks, stored.clone())?;
+    }
+    results.extend(stored);
+
+    let cancellation = cancellation(opts);
+    while !cancellation.is_cancelled() && pending.iter().any(|(_, h)| !h.is_finished()) {
+        thread::sleep(Duration::from_millis(100));
+    }
+    // agents still checking when run is cancelled have nothing to report, and agent panicked
+    // fails results of its location like unreachable one
+    let remote: Vec<(Agent, Option<anyhow::Result<Vec<CheckResultJSON>>>)> = pending
+        .into_iter()
+        .map(|(agent, handle)| {
+            if !handle.is_finished() {
+                return (agent, None);
+            }
+            let checked = handle
+                .join()
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("agent panicked")));
+            (agent, Some(checked))
+        })
+        .collect();
+    if !remote.is_empty() {
+        for result in results.iter_mut() {
+            result.location = Some(LOCAL.to_string());
+        }
+        let mut located = vec![];
+        for (agent, checked) in &remote {
+            let error = |domain_name: &str| match checked {
+                Some(Err(e)) => CheckError::ConnectFailed {
+                    target: agent.url.clone(),
+                    reason: format!("{0:#}", e),
+                },
+                _ => CheckError::Skipped {
+                    target: domain_name.to_string(),
+                    reason: cancellation.reason().unwrap_or_default().to_string(),
+                },
+            };
+            match checked {
+                Some(Ok(checked)) => {
+                    for json in checked {
+                        located.push(json.to_result()?);
+                    }
+                    continue;
+                }
+                // unreachable agent fails results of its location only
+                Some(Err(e)) => {
+                    warn!(location = agent.location.as_str(), "agent failed: {0:#}", e)
+                }
+                None => warn!(
+                    location = agent.location.as_str(),
+                    "agent is still checking"
+                ),
+            }
+            for domain_name in domain_names {
+                let mut result =
+                    CheckResult::failed(Uuid::new_v4(), domain_name, &now, error(domain_name));
+                result.location = Some(agent.location.clone());
+                located.push(result);
+            }
+        }
+        for result in located.iter_mut() {
+            annotate(result, config, &snoozes, &now);
+        }
+        results.extend(located);
+        compare_locations(&mut results);
+    }
+
+    if let Some(ref mut cache) = cache {
+        cache.prune(&now);
+        cache.save(&opts.cache_file)?;
+    }
+    fire_hooks(opts, config, &results, grace_in_days)?;
+
+    report(opts, config, &sinks, results, grace_in_days, incremental)
+}
+
+/// Location of results checked here, when results of agents are reported alongside
+pub const LOCAL: &str = "local";
+
+/// Note results of domain names serving different certificates at different locations e.g.
+/// behind CDN or geo-DNS, each result gets the locations of the other certificates
+fn compare_locations(results: &mut [CheckResult]) {
+    let seen: Vec<(String, String, String)> = results
+        .iter()
+        .filter_map(|r| {
+            let fingerprint = r.fingerprint.clone()?;
+            let location = r.location.clone()?;
+            Some((r.domain_name.to_string(), fingerprint, location))
+        })
+        .collect();
+    for result in results.iter_mut() {
+        let fingerprint = match result.fingerprint {
+            Some(ref fingerprint) => fingerprint,
+            None => continue,
+        };
+        let others: Vec<&str> = seen
+            .iter()
+            .filter(|(d, f, _)| d == result.domain_name && f != fingerprint)
+            .map(|(_, _, l)| l.as_str())
+            .collect();
+        if !others.is_empty() {
+            let finding = format!("different certificate seen from {0}", others.join(", "));
+            result.findings.push(finding);
+        }
+    }
+}
+
+/// Certificates checked as stored in Vault or inventories of clouds rather than by
+/// connecting to an endpoint
+#[derive(Default)]
+pub struct Inventory {
+    pub vault: Vec<VaultCertificate>,
+    pub cloud: Vec<CloudCertificate>,
+}
+
+/// Emit batch of results to streaming sinks, sorted and filtered as whole results are
+fn emit_batch(opts: &Opts, sinks: &[Sink], mut batch: Vec<CheckResult>) -> anyhow::Result<()> {
+    sort_results(&mut batch, &opts.sort);
+    if opts.quiet {
+        batch.retain(CheckResult::is_failure);
+    }
+    if !batch.is_empty() {
+        for sink in sinks.iter().filter(|s| s.is_streaming()) {
+            sink.emit(&batch)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fire hooks matching results whose certificates crossed a threshold since the previous run,
+/// failed hook is logged rather than failing the check so results are still reported
+fn fire_hooks(
+    opts: &Opts,
+    config: &Config,
+    results: &[CheckResult],
+    grace_in_days: i64,
+) -> anyhow::Result<()> {
+    if config.hooks.is_empty() {
+        return Ok(());
+    }
+    let mut state = HookState::load(&opts.hook_state_file)?;
+    for result in results {
+        for hook in &config.hooks {
+            if state.fires(hook, result, grace_in_days) {
+                if let Err(e) = hook.fire(result) {
+                    warn!(domain_name = result.domain_name, "hook failed: {0:#}", e);
+                }
+            }
+            state.record(hook, result, grace_in_days);
+        }
+    }
+    state.save(&opts.hook_state_file)
+}
+
+/// Clear screen and check domain names again every interval until interrupted, highlighting
+/// results changed since the last check
+pub fn watch_command(
+    opts: &Opts,
+    config: &Config,
+    domain_names: &[&str],
+    grace_in_days: i64,
+    sni: &Option<String>,
+    interval: Duration,
+) -> anyhow::Result<i32> {
+    let color = opts.color.enabled();
+    let style = text_style(opts);
+    let mut previous = BTreeMap::new();
+    loop {
+        // client remembers when it's created, so build one for each round
+        let client = build_client(opts, config.effective_policy(), grace_in_days, sni.clone())?;
+        let snoozes = Snoozes::load(&opts.snooze_file)?;
+        let now = Utc::now().round_subsecs(0);
+        let mut results = client.check_certificates(domain_names)?;
+        for result in results.iter_mut() {
+            annotate(result, config, &snoozes, &now);
+        }
+        sort_results(&mut results, &opts.sort);
+
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "Every {0}s: {1} domain name(s), last checked at {2}\n",
+            interval.as_secs(),
+            domain_names.len(),
+            style.zone.format(now.timestamp())
+        );
+        print!("{0}", render_watch(&results, &previous, color, &style));
+        println!("\n{0}", Summary::new(&results, grace_in_days));
+        io::stdout().flush()?;
+
+        if cancelled() {
+            return Ok(exit_code(&results, &ExitCodes::new(&opts.exit_codes)));
+        }
+        previous = results
+            .iter()
+            .map(|r| (r.domain_name.to_string(), watch_key(r)))
+            .collect();
+        let round = Instant::now();
+        while round.elapsed() < interval && !cancelled() {
+            thread::sleep(Duration::from_millis(100).min(interval));
+        }
+    }
+}
+
+/// What counts as a change between rounds of watch mode
+fn watch_key(result: &CheckResult) -> (i32, i64) {
+    (result.state.exit_code(), result.not_after)
+}
+
+/// Lines of results, results changed since the last round are marked with an asterisk and
+/// shown in reverse video when colorized
+fn render_watch(
+    results: &[CheckResult],
+    previous: &BTreeMap<String, (i32, i64)>,
+    color: bool,
+    style: &TextStyle,
+) -> String {
+    let mut s = String::new();
+    for result in results {
+        let changed = previous
+            .get(result.domain_name)
+            .is_some_and(|key| *key != watch_key(result));
+        let line = result.line_styled(style);
+        let line = match (changed, color) {
+            (true, true) => format!("* \x1b[7m{0}\x1b[0m\n", line),
+            (true, false) => format!("* {0}\n", line),
+            (false, _) => format!("  {0}\n", line),
+        };
+        s.push_str(&line);
+    }
+    s
+}
+
+/// Check certificates of mail servers of domain names on SMTP ports
+pub fn check_mx_command(
+    opts: &Opts,
+    config: &Config,
+    domain_names: &[String],
+    ports: &[u16],
+    grace_in_days: i64,
+    mta_sts: bool,
+) -> anyhow::Result<i32> {
+    if let Some(port) = ports.iter().find(|p| !SMTP_PORTS.contains(p)) {
+        anyhow::bail!("{0} is not an SMTP port, expect 25, 465 or 587", port);
+    }
+    // target with mail domain and mail server of it, mail domains may share a mail server
+    let mut starttls = vec![];
+    let mut implicit = vec![];
+    for domain_name in domain_names {
+        for server in mail_servers(domain_name)? {
+            for port in ports {
+                let target = format!("{0}:{1}", server, port);
+                let origin = (domain_name.as_str(), server.clone());
+                if *port == SMTPS_PORT {
+                    implicit.push((target, origin));
+                } else {
+                    starttls.push((target, origin));
+                }
+            }
+        }
+    }
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    // clients share rate limiters, and mail servers are sent no HTTP request
+    let mut builder = client_builder(opts, config.effective_policy(), grace_in_days, None)?;
+    builder.no_request(true);
+    let smtps = builder.starttls(None).build();
+    let smtp = builder.starttls(Some(StartTls::Smtp)).build();
+    let mut results = vec![];
+    let starttls_targets: Vec<&str> = starttls.iter().map(|(t, _)| t.as_str()).collect();
+    let implicit_targets: Vec<&str> = implicit.iter().map(|(t, _)| t.as_str()).collect();
+    results.extend(smtp.check_certificates(&starttls_targets)?);
+    results.extend(smtps.check_certificates(&implicit_targets)?);
+    // results are in order of targets
+    let origins: Vec<&(&str, String)> = starttls
+        .iter()
+        .chain(implicit.iter())
+        .map(|(_, origin)| origin)
+        .collect();
+
+    let mut policies = BTreeMap::new();
+    if mta_sts {
+        for domain_name in domain_names {
+            policies.insert(domain_name.as_str(), mta_sts_findings(domain_name));
+        }
+    }
+    for (result, (domain_name, server)) in results.iter_mut().zip(origins) {
+        annotate(result, config, &snoozes, &now);
+        let findings = match policies.get(domain_name) {
+            Some((Some(policy), findings)) => {
+                let mut findings = findings.clone();
+                findings.extend(policy.findings(domain_name, server));
+                if policy.mode == MtaStsMode::Enforce && !findings.is_empty() {
+                    result.degrade();
+                }
+                findings
+            }
+            Some((None, findings)) => findings.clone(),
+            None => vec![],
+        };
+        result.findings.extend(findings);
+    }
+    report(
+        opts,
+        config,
+        &build_sinks(opts),
+        results,
+        grace_in_days,
+        false,
+    )
+}
+
+/// MTA-STS policy of mail domain if any, and findings of the domain e.g. missing TXT record
+fn mta_sts_findings(domain_name: &str) -> (Option<MtaStsPolicy>, Vec<String>) {
+    let mut findings = vec![];
+    let policy = match MtaStsPolicy::fetch(domain_name) {
+        Ok(policy) => Some(policy),
+        Err(e) => {
+            findings.push(format!("no valid MTA-STS policy: {0:#}", e));
+            None
+        }
+    };
+    if policy.is_some() && !has_mta_sts_record(domain_name).unwrap_or(false) {
+        findings.push(format!(
+            "no _mta-sts TXT record of {0}, senders won't discover MTA-STS policy",
+            domain_name
+        ));
+    }
+    (policy, findings)
+}
+
+/// Check certificates published at HTTP(S) URLs
+pub fn check_url_command(
+    opts: &Opts,
+    config: &Config,
+    urls: &[String],
+    grace_in_days: i64,
+) -> anyhow::Result<i32> {
+    let client = build_client(opts, config.effective_policy(), grace_in_days, None)?;
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    let mut results = vec![];
+    for url in urls {
+        let mut result = client.check_url(url)?;
+        annotate(&mut result, config, &snoozes, &now);
+        results.push(result);
+    }
+    report(
+        opts,
+        config,
+        &build_sinks(opts),
+        results,
+        grace_in_days,
+        false,
+    )
+}
+
+pub fn check_file_command(
+    opts: &Opts,
+    config: &Config,
+    paths: &[String],
+    grace_in_days: i64,
+) -> anyhow::Result<i32> {
+    let client = build_client(opts, config.effective_policy(), grace_in_days, None)?;
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    let mut results = vec![];
+    for path in paths {
+        let mut result = client.check_file(path)?;
+        annotate(&mut result, config, &snoozes, &now);
+        results.push(result);
+    }
+    report(
+        opts,
+        config,
+        &build_sinks(opts),
+        results,
+        grace_in_days,
+        false,
+    )
+}
+
+/// Check certificates in certificate store of operating system
+pub fn check_store_command(
+    opts: &Opts,
+    config: &Config,
+    store: &OsStore,
+    grace_in_days: i64,
+) -> anyhow::Result<i32> {
+    let client = build_client(opts, config.effective_policy(), grace_in_days, None)?;
+    let certificates = store.certificates()?;
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    let mut results = vec![];
+    for certificate in &certificates {
+        let mut result = client.check_pem(&certificate.name, &certificate.der)?;
+        annotate(&mut result, config, &snoozes, &now);
+        results.push(result);
+    }
+    report(
+        opts,
+        config,
+        &build_sinks(opts),
+        results,
+        grace_in_days,
+        false,
+    )
+}
+
+/// Check host certificates of SSH servers, server whose check could not complete gets a
+/// failed result
+pub fn ssh_command(
+    opts: &Opts,
+    config: &Config,
+    hosts: &[String],
+    grace_in_days: i64,
+) -> anyhow::Result<i32> {
+    let client = build_client(opts, config.effective_policy(), grace_in_days, None)?;
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    let mut results = vec![];
+    for host in hosts {
+        let mut result = match client.check_ssh(host) {
+            Ok(result) => result,
+            Err(e) if e.is_usage_error() => return Err(e.into()),
+            Err(e) => CheckResult::failed(Uuid::new_v4(), host, &now, e),
+        };
+        annotate(&mut result, config, &snoozes, &now);
+        results.push(result);
+    }
+    report(
+        opts,
+        config,
+        &build_sinks(opts),
+        results,
+        grace_in_days,
+        false,
+    )
+}
+
+/// Where status site is published
+pub struct Site<'a> {
+    pub title: &'a str,
+    pub dir: Option<&'a Path>,
+    pub bucket: Option<S3Bucket>,
+}
+
+/// Check domain names and publish results as static status site to directory and bucket
+pub fn publish_command(
+    opts: &Opts,
+    config: &Config,
+    domain_names: &[&str],
+    grace_in_days: i64,
+    sni: Option<String>,
+    site: &Site,
+) -> anyhow::Result<i32> {
+    let client = build_client(opts, config.effective_policy(), grace_in_days, sni)?;
+
+    let snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    let mut results = client.check_certificates(domain_names)?;
+    for result in results.iter_mut() {
+        annotate(result, config, &snoozes, &now);
+    }
+    sort_results(&mut results, &opts.sort);
+
+    let page = StatusPage::new(site.title, &results, grace_in_days, text_style(opts));
+    if let Some(dir) = site.dir {
+        page.write_dir(dir)?;
+        println!("published to {0}", dir.display());
+    }
+    if let Some(ref bucket) = site.bucket {
+        for (name, content_type, body) in page.files()? {
+            bucket.put(name, content_type, body.as_bytes())?;
+        }
+        println!("published to {0}", bucket.url(""));
+    }
+    println!("{0}", Summary::new(&results, grace_in_days));
+    Ok(exit_code(&results, &ExitCodes::new(&opts.exit_codes)))
+}
+
+/// Options of scans, shared by ranges and ports of a host
+pub struct Scan {
+    /// Server name sent in handshakes, none to omit it unless host is a domain name
+    pub sni: Option<String>,
+    /// Timeout of each connection
+    pub timeout: Duration,
+    /// Targets checked at once
+    pub concurrency: usize,
+}
+
+/// Addresses scanned at most in one run, so a typo in prefix length doesn't scan for days
+const MAX_SCAN_TARGETS: u128 = 65536;
+
+/// Scan ports of addresses in ranges, and report certificates found with their subjects,
+/// addresses which refuse connection or fail handshake are skipped
+pub fn scan_command(
+    opts: &Opts,
+    config: &Config,
+    ranges: &[Cidr],
+    ports: &[u16],
+    grace_in_days: i64,
+    scan: Scan,
+) -> anyhow::Result<i32> {
+    // size of a large IPv6 range times ports overflows
+    let total = ranges.iter().try_fold(0u128, |total, r| {
+        r.size()
+            .checked_mul(ports.len() as u128)?
+            .checked_add(total)
+    });
+    match total {
+        Some(total) if total <= MAX_SCAN_TARGETS => {}
+        Some(total) => anyhow::bail!(
+            "{0} targets to scan exceed {1}, split ranges into smaller ones",
+            total,
+            MAX_SCAN_TARGETS
+        ),
+        None => anyhow::bail!(
+            "targets to scan exceed {0}, split ranges into smaller ones",
+            MAX_SCAN_TARGETS
+        ),
+    }
+    let targets: Vec<String> = ranges
+        .iter()
+        .flat_map(|r| r.addresses())
+        .flat_map(|host| {
+            ports.iter().map(move |&port| {
+                Target {
+                    host: host.to_string(),
+                    port,
+                }
+                .to_string()
+            })
+        })
+        .collect();
+    let omit_sni = scan.sni.is_none();
+    scan_targets(opts, config, &targets, grace_in_days, scan, omit_sni)
+}
+
+/// Probe ports of host, and report certificates found with their subjects, ports which refuse
+/// connection or fail handshake are skipped
+pub fn scan_ports_command(
+    opts: &Opts,
+    config: &Config,
+    host: &str,
+    ports: &[u16],
+    grace_in_days: i64,
+    scan: Scan,
+) -> anyhow::Result<i32> {
+    let host: Target = host.parse()?;
+    let targets: Vec<String> = ports
+        .iter()
+        .map(|&port| {
+            Target {
+                port,
+                ..host.clone()
+            }
+            .to_string()
+        })
+        .collect();
+    // host name is sent as server name unless host is an IP address
+    let omit_sni = scan.sni.is_none() && host.ip().is_some();
+    scan_targets(opts, config, &targets, grace_in_days, scan, omit_sni)
+}
+
+/// Check targets concurrently without verification, and report certificates found with their
+/// subjects, targets which refuse connection or fail handshake are skipped
+fn scan_targets(
+    opts: &Opts,
+    config: &Config,
+    targets: &[String],
+    grace_in_days: i64,
+    scan: Scan,
+    omit_sni: bool,
+) -> anyhow::Result<i32> {
+    let Scan {
+        sni,
+        timeout,
+        concurrency,
+    } = scan;
+    let mut builder = client_builder(opts, config.effective_policy(), grace_in_days, sni)?;
+    let client = builder
+        .insecure(true)
+        .omit_sni(omit_sni)
+        .detail(true)
+        // ports scanned are not necessarily HTTP
+        .no_request(true)
+        .timeout(Some(timeout))
+        .build();
+
+    let next = AtomicUsize::new(0);
+    let found = Mutex::new(vec![]);
+    thread::scope(|s| {
+        for _ in 0..concurrency.clamp(1, targets.len().max(1)) {
+            s.spawn(|| loop {
+                if cancelled() {
+                    break;
+                }
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let target = match targets.get(i) {
+                    Some(target) => target,
+                    None => break,
+                };
+                // only certificates found are reported, most targets are expected to be silent
+                if let Ok(mut result) = executor::block_on(client.check_certificate(target)) {
+                    if result.error.is_none() {
+                        if let Some(ref detail) = result.detail {
+                            let subject: Vec<String> = detail
+                                .subject
+                                .iter()
+                                .map(|c| format!("{0}={1}", c.name, c.value))
+                                .collect();
+                            result
+                                .findings
+                                .insert(0, format!("subject {0}", subject.join(", ")));
+                        }
+                        found.lock().unwrap().push((i, result));
+                    }
+                }
+            });
+        }
+    });
+    if cancelled() {
+        let scanned = next.into_inner().min(targets.len());
+        eprintln!(
+            "scan stopped after {0} of {1} target(s)",
+            scanned,
+            targets.len()
+        );
+    }
+    let mut found = found.into_inner().unwrap();
+    found.sort_by_key(|(i, _)| *i);
+    let results = found.into_iter().map(|(_, result)| result).collect();
+    report(
+        opts,
+        config,
+        &build_sinks(opts),
+        results,
+        grace_in_days,
+        false,
+    )
+}
+
+/// Exit code of compare when certificates differ, apart from 1 of errors
+const CERTIFICATES_DIFFER: i32 = 4;
+
+/// Compare certificates of two sources, exit code is 4 when they differ
+pub fn compare_command(
+    opts: &Opts,
+    left: &str,
+    right: &str,
+    sni: Option<String>,
+) -> anyhow::Result<i32> {
+    let client = build_client(opts, None, 0, sni)?;
+    let comparison = client.compare(left, right)?;
+    if opts.json {
+        println!("{0}", serde_json::to_string(&comparison)?);
+    } else {
+        println!("{0}", comparison);
+    }
+    Ok(if comparison.is_same() {
+        0
+    } else {
+        CERTIFICATES_DIFFER
+    })
+}
+
+pub fn inspect_command(
+    opts: &Opts,
+    domain_names: &[String],
+    sni: Option<String>,
+    ssllabs: bool,
+) -> anyhow::Result<()> {
+    let client = build_client(opts, None, 0, sni)?;
+    let mut inspections = vec![];
+    for domain_name in domain_names {
+        let mut inspection = client.inspect(domain_name)?;
+        if ssllabs {
+            inspection.grade_with_ssllabs()?;
+        }
+        inspections.push(inspection);
+    }
+    if opts.json {
+        println!("{0}", serde_json::to_string(&inspections)?);
+    } else {
+        for inspection in inspections {
+            println!("{0}", inspection);
+        }
+    }
+    Ok(())
+}
+
+pub fn bench_command(
+    opts: &Opts,
+    domain_name: &str,
+    sni: Option<String>,
+    count: usize,
+) -> anyhow::Result<()> {
+    let client = build_client(opts, None, 0, sni)?;
+    let benchmark = client.bench(domain_name, count)?;
+    if opts.json {
+        println!("{0}", serde_json::to_string(&benchmark)?);
+    } else {
+        println!("{0}", benchmark);
+    }
+    if let Some(reason) = cancellation(opts).reason() {
+        eprintln!(
+            "{0}, {1} of {2} handshake(s) done",
+            reason,
+            benchmark.completed + benchmark.failed,
+            benchmark.requested
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use hcc::{CheckResult, CheckState, ColorChoice, Config, TextStyle};
+
+    use crate::cli::Opts;
+    use crate::client::{check_command, compare_locations, render_watch, watch_key, Inventory};
+
+    fn build_opts(json: bool) -> Opts {
+        Opts {
+            json,
+            color: ColorChoice::Never,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_command() {
+        let opts = build_opts(false);
+        check_command(
+            &opts,
+            &Config::default(),
+            &["sha512.badssl.com"],
+            &Inventory::default(),
+            &[],
+            7,
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_check_command_json() {
+        let opts = build_opts(true);
+        check_command(
+            &opts,
+            &Config::default(),
+            &["sha512.badssl.com"],
+            &Inventory::default(),
+            &[],
+            7,
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_check_command_expired() {
+        let opts = build_opts(false);
+        let code = check_command(
+            &opts,
+            &Config::default(),
+            &["expired.badssl.com"],
+            &Inventory::default(),
+            &[],
+            7,
+            None,
+        )
+        .unwrap();
+        assert_eq!(2, code);
+    }
+
+    #[test]
+    fn test_check_command_fail_fast() {
+        let opts = Opts {
+            fail_fast: true,
+            ..build_opts(false)
+        };
+        let domain_names = ["expired.badssl.com", "sha512.badssl.com"];
+        let code = check_command(
+            &opts,
+            &Config::default(),
+            &domain_names,
+            &Inventory::default(),
+            &[],
+            7,
+            None,
+        )
+        .unwrap();
+        assert_eq!(2, code);
+    }
+
+    #[test]
+    fn test_check_command_expired_json() {
+        let opts = build_opts(true);
+        check_command(
+            &opts,
+            &Config::default(),
+            &["expired.badssl.com"],
+            &Inventory::default(),
+            &[],
+            7,
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_render_watch() {
+        let results = vec![
+            CheckResult {
+                state: CheckState::Ok,
+                domain_name: "a.example.com",
+                not_after: 2,
+                ..Default::default()
+            },
+            CheckResult {
+                state: CheckState::Ok,
+                domain_name: "b.example.com",
+                not_after: 1,
+                ..Default::default()
+            },
+        ];
+        let s = render_watch(&results, &BTreeMap::new(), false, &TextStyle::default());
+        assert!(s.lines().all(|l| l.starts_with("  ")));
+
+        let mut previous = BTreeMap::new();
+        previous.insert("a.example.com".to_string(), watch_key(&results[0]));
+        previous.insert("b.example.com".to_string(), (0, 0));
+        let s = render_watch(&results, &previous, false, &TextStyle::default());
+        let lines: Vec<&str> = s.lines().collect();
+        assert!(lines[0].starts_with("  "));
+        assert!(lines[1].starts_with("* "));
+    }
+
+    #[test]
+    fn test_compare_locations() {
+        let build = |fingerprint: &str, location: &str| CheckResult {
+            domain_name: "example.com",
+            fingerprint: Some(fingerprint.to_string()),
+            location: Some(location.to_string()),
+            ..Default::default()
+        };
+        let mut results = vec![
+            build("AB", "local"),
+            build("AB", "eu-west"),
+            build("CD", "ap-east"),
+        ];
+        compare_locations(&mut results);
+        assert_eq!(
+            vec!["different certificate seen from ap-east"],
+            results[0].findings
+        );
+        assert_eq!(
+            vec!["different certificate seen from local, eu-west"],
+            results[2].findings
+        );
+    }
+}
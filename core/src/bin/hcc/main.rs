@@ -0,0 +1,401 @@
+#![forbid(unsafe_code)]
+use std::time::Duration;
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use structopt::StructOpt;
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use hcc::{
+    Agent, AwsInventory, DomainFilter, ExitCodes, GcpInventory, Hook, OsStore, S3Bucket, VaultPki,
+    JSON_SCHEMA,
+};
+
+use crate::cli::{apply_env, completions_command, grace_in_days_of, man_command, Command, Opts};
+use crate::client::{
+    bench_command, check_command, check_file_command, check_mx_command, check_store_command,
+    check_url_command, compare_command, inspect_command, publish_command, scan_command,
+    scan_ports_command, ssh_command, watch_command, Inventory, Scan, Site,
+};
+use crate::model::LogFormat;
+use crate::output::verify_report_command;
+use crate::policy::{load_config, load_nmap, snooze_command};
+use crate::tui::tui_command;
+use crate::update::self_update_command;
+
+mod cli;
+mod client;
+mod model;
+mod output;
+mod policy;
+mod telemetry;
+mod tui;
+mod update;
+
+fn main() {
+    let mut opts: Opts = Opts::from_args();
+    let applied = apply_env(&mut opts, |name| std::env::var(name).ok());
+    let provider = init_tracing(&opts);
+    let code = match applied.and_then(|_| run(&opts)) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {0:?}", e);
+            ExitCodes::new(&opts.exit_codes).error
+        }
+    };
+    // flush spans before exit
+    if let Some(provider) = provider {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Error: failed to export spans: {0}", e);
+        }
+    }
+    std::process::exit(code);
+}
+
+fn run(opts: &Opts) -> anyhow::Result<i32> {
+    if opts.schema {
+        print!("{0}", JSON_SCHEMA);
+        return Ok(0);
+    }
+    let code = match opts.command {
+        Some(Command::Check {
+            ref domain_names,
+            grace_in_days,
+            ref sni,
+            watch,
+            interval,
+            ref tags,
+            ref include,
+            ref exclude,
+            ref expect_sans,
+            ref ports,
+            ref from_nmap,
+            ref vault_pki,
+            ref aws_acm,
+            aws_iam,
+            ref gcp_certificate_manager,
+            ref hooks,
+            ref webhooks,
+            hook_days,
+            ref agents,
+            ref agent_token,
+        }) => {
+            let mut config = load_config(opts)?;
+            config.required_sans.extend(expect_sans.iter().cloned());
+            let mut added = hooks
+                .iter()
+                .map(|c| Hook::command(c))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            added.extend(webhooks.iter().map(|u| Hook::webhook(u)));
+            config.hooks.extend(added.into_iter().map(|hook| Hook {
+                days: hook_days,
+                ..hook
+            }));
+            let mut domain_names = domain_names.clone();
+            if let Some(path) = from_nmap {
+                let scanned = load_nmap(path, sni.is_some())?;
+                if scanned.is_empty() && domain_names.is_empty() {
+                    anyhow::bail!("no open TLS port found in {0:?}", path);
+                }
+                domain_names.extend(scanned);
+            }
+            let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+            let domain_names = config.select(&domain_names, tags);
+            let domain_names = DomainFilter::new(include, exclude).apply(&domain_names);
+            // ports default to well-known port of STARTTLS protocol
+            let ports = match opts.starttls {
+                Some(starttls) if ports.is_empty() => vec![starttls.default_port()],
+                _ => ports.clone(),
+            };
+            let domain_names = config.expand_ports(&domain_names, &ports);
+            let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            let mut inventory = Inventory::default();
+            for mount in vault_pki {
+                inventory
+                    .vault
+                    .extend(VaultPki::from_env(mount)?.certificates()?);
+            }
+            for region in aws_acm {
+                inventory
+                    .cloud
+                    .extend(AwsInventory::from_env(region)?.acm()?);
+            }
+            if aws_iam {
+                let region = std::env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".into());
+                inventory
+                    .cloud
+                    .extend(AwsInventory::from_env(&region)?.iam()?);
+            }
+            for project in gcp_certificate_manager {
+                inventory
+                    .cloud
+                    .extend(GcpInventory::from_env(project)?.certificates()?);
+            }
+            let agents: Vec<Agent> = agents
+                .iter()
+                .map(|agent| Agent {
+                    token: agent_token.clone(),
+                    ..agent.clone()
+                })
+                .collect();
+            if watch {
+                let interval = Duration::from_secs(interval);
+                watch_command(opts, &config, &domain_names, grace_in_days, sni, interval)?
+            } else {
+                check_command(
+                    opts,
+                    &config,
+                    &domain_names,
+                    &inventory,
+                    &agents,
+                    grace_in_days,
+                    sni.clone(),
+                )?
+            }
+        }
+        Some(Command::Tui {
+            ref domain_names,
+            grace_in_days,
+            ref sni,
+            interval,
+            ref tags,
+            ref include,
+            ref exclude,
+        }) => {
+            let config = load_config(opts)?;
+            let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+            let domain_names = config.select(&domain_names, tags);
+            let domain_names = DomainFilter::new(include, exclude).apply(&domain_names);
+            let domain_names = config.expand_ports(&domain_names, &[]);
+            let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            let interval = Duration::from_secs(interval);
+            tui_command(
+                opts,
+                &config,
+                &domain_names,
+                grace_in_days,
+                sni.clone(),
+                interval,
+            )?
+        }
+        Some(Command::Publish {
+            ref domain_names,
+            grace_in_days,
+            ref sni,
+            ref tags,
+            ref include,
+            ref exclude,
+            ref title,
+            ref dir,
+            ref s3,
+            ref s3_endpoint,
+            ref s3_region,
+        }) => {
+            if dir.is_none() && s3.is_none() {
+                anyhow::bail!("publish requires --dir or --s3");
+            }
+            let bucket = match s3 {
+                Some(url) => Some(S3Bucket::from_env(url, s3_endpoint, s3_region)?),
+                None => None,
+            };
+            let site = Site {
+                title,
+                dir: dir.as_deref(),
+                bucket,
+            };
+            let config = load_config(opts)?;
+            let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+            let domain_names = config.select(&domain_names, tags);
+            let domain_names = DomainFilter::new(include, exclude).apply(&domain_names);
+            let domain_names = config.expand_ports(&domain_names, &[]);
+            let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            publish_command(
+                opts,
+                &config,
+                &domain_names,
+                grace_in_days,
+                sni.clone(),
+                &site,
+            )?
+        }
+        Some(Command::Compare {
+            ref sni,
+            ref left,
+            ref right,
+        }) => compare_command(opts, left, right, sni.clone())?,
+        Some(Command::CheckMx {
+            grace_in_days,
+            ref ports,
+            mta_sts,
+            ref domain_names,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            check_mx_command(opts, &config, domain_names, ports, grace_in_days, mta_sts)?
+        }
+        Some(Command::CheckUrl {
+            ref urls,
+            grace_in_days,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            check_url_command(opts, &config, urls, grace_in_days)?
+        }
+        Some(Command::CheckFile {
+            ref paths,
+            grace_in_days,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            check_file_command(opts, &config, paths, grace_in_days)?
+        }
+        Some(Command::CheckStore {
+            grace_in_days,
+            ref store,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            let store = OsStore::new(store.as_deref());
+            check_store_command(opts, &config, &store, grace_in_days)?
+        }
+        Some(Command::Ssh {
+            grace_in_days,
+            ref hosts,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            ssh_command(opts, &config, hosts, grace_in_days)?
+        }
+        Some(Command::Scan {
+            grace_in_days,
+            ref ports,
+            ref sni,
+            timeout,
+            concurrency,
+            ref ranges,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            let timeout = Duration::from_secs(timeout);
+            scan_command(
+                opts,
+                &config,
+                ranges,
+                ports,
+                grace_in_days,
+                Scan {
+                    sni: sni.clone(),
+                    timeout,
+                    concurrency,
+                },
+            )?
+        }
+        Some(Command::ScanPorts {
+            grace_in_days,
+            ref ports,
+            ref sni,
+            timeout,
+            concurrency,
+            ref host,
+        }) => {
+            let config = load_config(opts)?;
+            let grace_in_days = grace_in_days_of(grace_in_days, &config);
+            let timeout = Duration::from_secs(timeout);
+            scan_ports_command(
+                opts,
+                &config,
+                host,
+                &ports.0,
+                grace_in_days,
+                Scan {
+                    sni: sni.clone(),
+                    timeout,
+                    concurrency,
+                },
+            )?
+        }
+        Some(Command::Inspect {
+            ref domain_names,
+            ref sni,
+            ssllabs,
+        }) => {
+            inspect_command(opts, domain_names, sni.clone(), ssllabs)?;
+            0
+        }
+        Some(Command::Bench {
+            ref domain_name,
+            ref sni,
+            count,
+        }) => {
+            bench_command(opts, domain_name, sni.clone(), count)?;
+            0
+        }
+        Some(Command::Snooze {
+            ref domain_names,
+            until,
+            remove,
+        }) => {
+            snooze_command(opts, domain_names, until, remove)?;
+            0
+        }
+        Some(Command::Completions { shell }) => {
+            completions_command(shell, &mut std::io::stdout());
+            0
+        }
+        Some(Command::Man) => {
+            man_command(&mut std::io::stdout())?;
+            0
+        }
+        Some(Command::SelfUpdate {
+            check,
+            ref tag,
+            ref public_key,
+            ref repository,
+        }) => self_update_command(repository, tag.as_deref(), check, public_key)?,
+        Some(Command::VerifyReport {
+            ref public_key,
+            ref path,
+        }) => verify_report_command(public_key, path)?,
+        None => 0,
+    };
+    Ok(code)
+}
+
+/// Print logs to standard error by verbosity, and export spans of checks over OTLP when
+/// configured regardless of verbosity
+fn init_tracing(opts: &Opts) -> Option<SdkTracerProvider> {
+    let level = match opts.verbose {
+        0 => Level::WARN,
+        // events of checks are logged at debug level
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    let fmt = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    let fmt = match opts.log_format {
+        LogFormat::Text => fmt.boxed(),
+        LogFormat::Json => fmt.json().boxed(),
+    };
+    // tracing is not essential to checks, so carry on without it when exporter fails
+    let provider =
+        telemetry::tracer_provider(|name| std::env::var(name).ok()).unwrap_or_else(|e| {
+            eprintln!("Error: failed to initialize OpenTelemetry: {0:?}", e);
+            None
+        });
+    let otel = provider.as_ref().map(|p| {
+        tracing_opentelemetry::layer()
+            .with_tracer(p.tracer("hcc"))
+            .with_filter(LevelFilter::INFO)
+    });
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(LevelFilter::from_level(level)))
+        .with(otel)
+        .init();
+    provider
+}
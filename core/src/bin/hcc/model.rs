@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use hcc::Destination;
+
+#[derive(Clone, Debug, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => anyhow::bail!("unknown log format {0}", s),
+        }
+    }
+}
+
+/// Time to live e.g. 90s, 30m, 1h or 1d, in seconds without unit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ttl(pub Duration);
+
+impl Default for Ttl {
+    fn default() -> Self {
+        Ttl(Duration::from_secs(3600))
+    }
+}
+
+impl FromStr for Ttl {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (n, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        let n: u64 = n
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid TTL {0}", s))?;
+        let secs = match unit {
+            "" | "s" => n,
+            "m" => n * 60,
+            "h" => n * 60 * 60,
+            "d" => n * 60 * 60 * 24,
+            _ => anyhow::bail!("unknown unit of TTL {0}, expect s, m, h or d", s),
+        };
+        Ok(Ttl(Duration::from_secs(secs)))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SortKey {
+    #[default]
+    Days,
+    Domain,
+    State,
+    Input,
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "days" => Ok(SortKey::Days),
+            "domain" => Ok(SortKey::Domain),
+            "state" => Ok(SortKey::State),
+            "input" => Ok(SortKey::Input),
+            _ => anyhow::bail!(
+                "unknown sort key {0}, expect days, domain, state or input",
+                s
+            ),
+        }
+    }
+}
+
+/// Ports commonly serving TLS, including admin UIs of appliances e.g. 8006 of Proxmox or
+/// 10000 of Webmin
+const COMMON_TLS_PORTS: &[u16] = &[
+    443, 636, 853, 989, 990, 992, 993, 994, 995, 2376, 2484, 3269, 4443, 5061, 5986, 6443, 6514,
+    8006, 8443, 8444, 8834, 9091, 9443, 10000, 10250,
+];
+
+/// Ports to scan, comma-separated ports and ranges e.g. 443,8000-9000, or `common` for ports
+/// commonly serving TLS
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortSet(pub Vec<u16>);
+
+impl FromStr for PortSet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ports = vec![];
+        for part in s.split(',').map(str::trim) {
+            if part == "common" {
+                ports.extend_from_slice(COMMON_TLS_PORTS);
+                continue;
+            }
+            let parse = |p: &str| {
+                p.parse::<u16>()
+                    .ok()
+                    .filter(|p| *p > 0)
+                    .ok_or_else(|| anyhow::anyhow!("invalid port {0} in {1}", p, s))
+            };
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse(start)?, parse(end)?);
+                    if start > end {
+                        anyhow::bail!("invalid port range {0}", part);
+                    }
+                    ports.extend(start..=end);
+                }
+                None => ports.push(parse(part)?),
+            }
+        }
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(PortSet(ports))
+    }
+}
+
+/// Formatter registered by name writing to standard output or file, NAME[:PATH]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatterSink {
+    pub name: String,
+    pub destination: Destination,
+}
+
+impl FromStr for FormatterSink {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, destination) = match s.split_once(':') {
+            Some((name, "-")) => (name, Destination::Stdout),
+            Some((_, "")) => anyhow::bail!("empty path in formatter {0}", s),
+            Some((name, path)) => (name, Destination::File(PathBuf::from(path))),
+            None => (s, Destination::Stdout),
+        };
+        if name.is_empty() {
+            anyhow::bail!("empty name of formatter {0}", s);
+        }
+        Ok(FormatterSink {
+            name: name.to_string(),
+            destination,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use hcc::Destination;
+
+    use crate::model::{FormatterSink, PortSet, Ttl, COMMON_TLS_PORTS};
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(Ttl(Duration::from_secs(90)), "90".parse().unwrap());
+        assert_eq!(Ttl(Duration::from_secs(90)), "90s".parse().unwrap());
+        assert_eq!(Ttl(Duration::from_secs(1800)), "30m".parse().unwrap());
+        assert_eq!(Ttl(Duration::from_secs(3600)), "1h".parse().unwrap());
+        assert_eq!(Ttl(Duration::from_secs(86400)), "1d".parse().unwrap());
+        assert!("1w".parse::<Ttl>().is_err());
+        assert!("h".parse::<Ttl>().is_err());
+    }
+
+    #[test]
+    fn test_parse_port_set() {
+        assert_eq!(PortSet(vec![443]), "443".parse().unwrap());
+        assert_eq!(
+            PortSet(vec![443, 8000, 8001, 8002]),
+            "8000-8002,443,8001".parse().unwrap()
+        );
+        assert_eq!(10000, "1-10000".parse::<PortSet>().unwrap().0.len());
+        let common: PortSet = "common".parse().unwrap();
+        assert_eq!(COMMON_TLS_PORTS.len(), common.0.len());
+        assert!("0".parse::<PortSet>().is_err());
+        assert!("9000-8000".parse::<PortSet>().is_err());
+        assert!("https".parse::<PortSet>().is_err());
+        assert!("1-70000".parse::<PortSet>().is_err());
+    }
+
+    #[test]
+    fn test_parse_formatter_sink() {
+        let sink: FormatterSink = "csv".parse().unwrap();
+        assert_eq!("csv", sink.name);
+        assert_eq!(Destination::Stdout, sink.destination);
+        let sink: FormatterSink = "csv:report.csv".parse().unwrap();
+        assert_eq!(Destination::File("report.csv".into()), sink.destination);
+        assert!("csv:".parse::<FormatterSink>().is_err());
+        assert!(":report.csv".parse::<FormatterSink>().is_err());
+    }
+}
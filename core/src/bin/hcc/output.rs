@@ -0,0 +1,326 @@
+use std::path::Path;
+
+use anyhow::Context;
+use hcc::{
+    group_by_certificate, verify_report, CheckResult, Config, Destination, ExitCodes, Format,
+    NumberFormat, PluginRegistry, ReportSigner, RunState, Sink, Summary, TextStyle,
+};
+use tracing::warn;
+
+use crate::cli::Opts;
+use crate::client::cancellation;
+use crate::model::SortKey;
+use crate::policy::exit_code;
+
+/// Emit results to sinks, formatters and notifiers and return exit code, streaming sinks are
+/// skipped when they already got results incrementally
+pub fn report(
+    opts: &Opts,
+    config: &Config,
+    sinks: &[Sink],
+    mut results: Vec<CheckResult>,
+    grace_in_days: i64,
+    incremental: bool,
+) -> anyhow::Result<i32> {
+    let code = exit_code(&results, &ExitCodes::new(&opts.exit_codes));
+    let summary = Summary::new(&results, grace_in_days);
+    let skipped = results.iter().filter(|r| r.is_skipped()).count();
+    if skipped > 0 {
+        eprintln!(
+            "{0}, {1} of {2} domain name(s) left unchecked",
+            cancellation(opts).reason().unwrap_or("run was cancelled"),
+            skipped,
+            results.len()
+        );
+    }
+
+    let mut state = None;
+    if opts.changed_only {
+        let mut loaded = RunState::load(&opts.state_file)?;
+        let mut changed = vec![];
+        for result in &results {
+            changed.push(loaded.changed(result, grace_in_days));
+            loaded.record(result, grace_in_days);
+        }
+        let mut changed = changed.into_iter();
+        results.retain(|_| changed.next().unwrap_or(true));
+        state = Some(loaded);
+    }
+
+    let delivered =
+        results.is_empty() || deliver(opts, config, sinks, results, &summary, incremental)?;
+    // state is saved once delivered, so results failed to be delivered are reported again
+    if let Some(state) = state.filter(|_| delivered) {
+        state.save(&opts.state_file)?;
+    }
+    Ok(code)
+}
+
+/// Emit results to sinks, formatters and notifiers, grouped, sorted and filtered as requested,
+/// false when a notifier fails
+fn deliver(
+    opts: &Opts,
+    config: &Config,
+    sinks: &[Sink],
+    mut results: Vec<CheckResult>,
+    summary: &Summary,
+    incremental: bool,
+) -> anyhow::Result<bool> {
+    if opts.group_by_cert {
+        results = group_by_certificate(results);
+    }
+    sort_results(&mut results, &opts.sort);
+
+    if opts.quiet {
+        results.retain(CheckResult::is_failure);
+        if results.is_empty() {
+            return Ok(true);
+        }
+    }
+
+    let signer = match opts.sign_key {
+        Some(ref path) => Some(ReportSigner::from_file(path)?),
+        None => None,
+    };
+    let text_on_stdout =
+        |s: &Sink| s.format == Format::Text && s.destination == Destination::Stdout;
+    let print_summary = summary.checked > 1 && sinks.iter().any(text_on_stdout);
+
+    // streaming sinks already got results batch by batch
+    for sink in sinks.iter().filter(|s| !incremental || !s.is_streaming()) {
+        match signer {
+            // only reports kept in files or standard output are worth signing, not metrics
+            Some(ref signer)
+                if matches!(sink.destination, Destination::Stdout | Destination::File(_)) =>
+            {
+                let mut s = sink.render(&results)?;
+                // summary is signed along with results it sums up
+                if print_summary && text_on_stdout(sink) {
+                    s.push_str(&format!("{0}\n", summary));
+                }
+                sink.write(&signer.sign(sink.format, &s)?)?;
+            }
+            _ => sink.emit(&results)?,
+        }
+    }
+
+    let mut delivered = true;
+    let notifiers: Vec<&String> = config.notifiers.iter().chain(&opts.notifiers).collect();
+    if !opts.formatters.is_empty() || !notifiers.is_empty() {
+        let mut registry = PluginRegistry::new(&config.plugins)?;
+        for sink in &opts.formatters {
+            // built-in formats render as sinks writing to the same destination do
+            if let Ok(format) = sink.name.parse::<Format>() {
+                let builtin = Sink {
+                    destination: sink.destination.clone(),
+                    ..Sink::stdout(format)
+                };
+                registry.register_formatter(&sink.name, Box::new(styled(opts, builtin)));
+            }
+            let s = registry.formatter(&sink.name)?.format(&results)?;
+            match signer {
+                Some(ref signer) => sink.destination.write(&signer.sign(Format::Text, &s)?)?,
+                None => sink.destination.write(&s)?,
+            }
+        }
+        for name in notifiers {
+            // a notifier failing is no reason to withhold results from others
+            if let Err(e) = registry.notifier(name)?.notify(&results) {
+                warn!(notifier = name.as_str(), "notifier failed: {0:#}", e);
+                delivered = false;
+            }
+        }
+    }
+
+    if print_summary && signer.is_none() {
+        println!("{0}", summary);
+    }
+
+    Ok(delivered)
+}
+
+/// Verify signature of report at path or standard input given `-`
+pub fn verify_report_command(public_key: &Path, path: &str) -> anyhow::Result<i32> {
+    let public_key =
+        std::fs::read(public_key).with_context(|| format!("failed to read {0:?}", public_key))?;
+    let report = if path == "-" {
+        std::io::read_to_string(std::io::stdin()).context("failed to read standard input")?
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {0}", path))?
+    };
+    let signature = verify_report(&report, &public_key)
+        .with_context(|| format!("failed to verify {0}", path))?;
+    println!("{0} is signed by key {1}", path, signature.key_id);
+    Ok(0)
+}
+
+pub fn sort_results(results: &mut [CheckResult], key: &SortKey) {
+    match key {
+        SortKey::Days => results.sort_by_key(|r| r.days),
+        SortKey::Domain => results.sort_by(|a, b| a.domain_name.cmp(b.domain_name)),
+        SortKey::State => results.sort_by_key(|r| std::cmp::Reverse(r.state.exit_code())),
+        SortKey::Input => {}
+    }
+}
+
+/// How results are rendered in text output, from --plain-numbers, --locale, --human and
+/// --timezone
+pub fn text_style(opts: &Opts) -> TextStyle {
+    let numbers = if opts.plain_numbers {
+        NumberFormat::Plain
+    } else {
+        opts.locale.unwrap_or_default()
+    };
+    TextStyle {
+        numbers,
+        human: opts.human,
+        zone: opts.timezone,
+    }
+}
+
+pub fn build_sinks(opts: &Opts) -> Vec<Sink> {
+    let mut sinks = opts.sinks.clone();
+    let format = match opts.output {
+        Some(format) => format,
+        None if opts.json => Format::Json,
+        None => Format::Text,
+    };
+    match opts.out {
+        Some(ref path) => sinks.push(Sink {
+            format,
+            destination: Destination::File(path.clone()),
+            color: false,
+            style: TextStyle::default(),
+            alarms: vec![],
+        }),
+        None if opts.json || opts.output.is_some() => sinks.push(Sink::stdout(format)),
+        None => {}
+    }
+    if let Some(ref addr) = opts.statsd {
+        sinks.push(Sink::statsd(addr, opts.dogstatsd));
+    }
+    // formatters replace default text output as other sinks do
+    if sinks.is_empty() && opts.formatters.is_empty() {
+        sinks.push(Sink::stdout(Format::Text));
+    }
+    sinks.into_iter().map(|sink| styled(opts, sink)).collect()
+}
+
+/// Sink rendering with --color, text style and --ics-alarm, only text on standard output is
+/// colored
+fn styled(opts: &Opts, mut sink: Sink) -> Sink {
+    sink.style = text_style(opts);
+    sink.alarms = opts.ics_alarms.clone();
+    sink.color = opts.color.enabled()
+        && sink.format == Format::Text
+        && sink.destination == Destination::Stdout;
+    sink
+}
+
+#[cfg(test)]
+mod test {
+    use hcc::{CheckResult, CheckState, ColorChoice, Destination, Format, Sink};
+
+    use crate::cli::Opts;
+    use crate::model::SortKey;
+    use crate::output::{build_sinks, sort_results};
+
+    fn build_opts(json: bool) -> Opts {
+        Opts {
+            json,
+            color: ColorChoice::Never,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_sinks() {
+        let sinks = build_sinks(&build_opts(false));
+        assert_eq!(vec![Sink::stdout(Format::Text)], sinks);
+
+        let opts = Opts {
+            json: true,
+            sinks: vec!["text".parse().unwrap()],
+            color: ColorChoice::Never,
+            ..Default::default()
+        };
+        let sinks = build_sinks(&opts);
+        assert_eq!(
+            vec![Sink::stdout(Format::Text), Sink::stdout(Format::Json)],
+            sinks
+        );
+
+        let opts = Opts {
+            sinks: vec!["text".parse().unwrap(), "text:out.txt".parse().unwrap()],
+            color: ColorChoice::Always,
+            ..Default::default()
+        };
+        let sinks = build_sinks(&opts);
+        assert!(sinks[0].color);
+        assert!(!sinks[1].color);
+
+        let opts = Opts {
+            json: true,
+            out: Some("results.json".into()),
+            ..Default::default()
+        };
+        let sinks = build_sinks(&opts);
+        assert_eq!(1, sinks.len());
+        assert_eq!(Format::Json, sinks[0].format);
+        assert_eq!(
+            Destination::File("results.json".into()),
+            sinks[0].destination
+        );
+
+        let opts = Opts {
+            output: Some(Format::Influx),
+            ..Default::default()
+        };
+        assert_eq!(vec![Sink::stdout(Format::Influx)], build_sinks(&opts));
+    }
+
+    #[test]
+    fn test_sort_results() {
+        let mut results = vec![
+            CheckResult {
+                domain_name: "b.example.com",
+                days: 30,
+                state: CheckState::Ok,
+                ..Default::default()
+            },
+            CheckResult {
+                domain_name: "c.example.com",
+                days: 0,
+                state: CheckState::Expired,
+                ..Default::default()
+            },
+            CheckResult {
+                domain_name: "a.example.com",
+                days: 5,
+                state: CheckState::Warning,
+                ..Default::default()
+            },
+        ];
+        let domain_names = |results: &[CheckResult]| -> Vec<String> {
+            results.iter().map(|r| r.domain_name.to_string()).collect()
+        };
+
+        sort_results(&mut results, &SortKey::Days);
+        assert_eq!(
+            vec!["c.example.com", "a.example.com", "b.example.com"],
+            domain_names(&results)
+        );
+
+        sort_results(&mut results, &SortKey::Domain);
+        assert_eq!(
+            vec!["a.example.com", "b.example.com", "c.example.com"],
+            domain_names(&results)
+        );
+
+        sort_results(&mut results, &SortKey::State);
+        assert_eq!(
+            vec!["c.example.com", "a.example.com", "b.example.com"],
+            domain_names(&results)
+        );
+    }
+}
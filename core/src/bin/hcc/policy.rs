@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use tracing::warn;
+
+use hcc::{nmap_targets, CheckResult, Config, ExitCodes, Snoozes, Target};
+
+use crate::cli::Opts;
+
+/// Apply snoozes, tags, expectations and requirements in configuration to result
+pub fn annotate(result: &mut CheckResult, config: &Config, snoozes: &Snoozes, now: &DateTime<Utc>) {
+    result.snoozed_until = snoozes.until(result.domain_name, now);
+    if let Some(domain) = config.domain(result.domain_name) {
+        result.tags = domain.tags.clone();
+        if let Some(ref expected_sans) = domain.expected_sans {
+            result.expect_sans(expected_sans);
+        }
+        if !domain.required_sans.is_empty() {
+            result.require_sans(&domain.required_sans);
+        }
+    }
+}
+
+pub fn load_config(opts: &Opts) -> anyhow::Result<Config> {
+    match opts.config {
+        Some(ref path) => Config::load(path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Targets of open TLS ports in XML output of nmap or masscan, targets by IP address are
+/// skipped with warning unless server name is given
+pub fn load_nmap(path: &Path, has_sni: bool) -> anyhow::Result<Vec<String>> {
+    let xml = fs::read_to_string(path).with_context(|| format!("failed to read {0:?}", path))?;
+    let (by_address, by_name): (Vec<Target>, Vec<Target>) = nmap_targets(&xml)
+        .with_context(|| format!("invalid scan {0:?}", path))?
+        .into_iter()
+        .partition(|t| t.ip().is_some());
+    let mut targets: Vec<String> = by_name.iter().map(ToString::to_string).collect();
+    if has_sni {
+        targets.extend(by_address.iter().map(ToString::to_string));
+    } else if !by_address.is_empty() {
+        warn!(
+            count = by_address.len(),
+            "skip targets without hostname in scan, pass --sni to check them"
+        );
+    }
+    Ok(targets)
+}
+
+/// Exit code of the most severe failure, exit code of OK if there is none
+pub fn exit_code(results: &[CheckResult], codes: &ExitCodes) -> i32 {
+    results
+        .iter()
+        .filter(|r| r.is_failure())
+        .max_by_key(|r| r.state.exit_code())
+        .map_or(codes.ok, |r| codes.code_of(&r.state))
+}
+
+pub fn snooze_command(
+    opts: &Opts,
+    domain_names: &[String],
+    until: Option<NaiveDate>,
+    remove: bool,
+) -> anyhow::Result<()> {
+    let mut snoozes = Snoozes::load(&opts.snooze_file)?;
+    let now = Utc::now().round_subsecs(0);
+    snoozes.prune(&now);
+
+    if domain_names.is_empty() {
+        for (domain_name, until) in snoozes.iter() {
+            println!("{0} is snoozed until {1}", domain_name, until);
+        }
+        return Ok(());
+    }
+
+    for domain_name in domain_names {
+        if remove {
+            if !snoozes.remove(domain_name) {
+                println!("{0} is not snoozed", domain_name);
+            }
+        } else {
+            let until = until.context("--until is required to snooze domain name(s)")?;
+            snoozes.snooze(domain_name, until);
+        }
+    }
+    snoozes.save(&opts.snooze_file)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, Utc};
+
+    use hcc::{CheckResult, CheckState, Config, DomainConfig, ExitCodes, Snoozes};
+
+    use crate::cli::Opts;
+    use crate::policy::{annotate, exit_code, snooze_command};
+
+    #[test]
+    fn test_exit_code() {
+        let build = |state| CheckResult {
+            state,
+            ..Default::default()
+        };
+        let codes = ExitCodes::default();
+        assert_eq!(0, exit_code(&[], &codes));
+        assert_eq!(0, exit_code(&[build(CheckState::Ok)], &codes));
+        let results = [
+            build(CheckState::Warning),
+            build(CheckState::Expired),
+            build(CheckState::Ok),
+        ];
+        assert_eq!(2, exit_code(&results, &codes));
+
+        let codes = ExitCodes::new(&["expired=1".parse().unwrap(), "warning=9".parse().unwrap()]);
+        assert_eq!(1, exit_code(&results, &codes));
+
+        let mut snoozed = build(CheckState::Expired);
+        snoozed.snoozed_until = NaiveDate::from_ymd_opt(9999, 12, 31);
+        assert_eq!(0, exit_code(&[snoozed], &codes));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let config: Config = r#"
+            [[domains]]
+            name = "example.com"
+            expected_sans = ["example.com", "www.example.com"]
+            tags = ["prod"]
+        "#
+        .parse()
+        .unwrap();
+        let mut result = CheckResult {
+            domain_name: "example.com",
+            state: CheckState::Ok,
+            sans: vec!["example.com".into()],
+            ..Default::default()
+        };
+        annotate(&mut result, &config, &Snoozes::default(), &Utc::now());
+        assert!(matches!(result.state, CheckState::Warning));
+        assert_eq!(vec!["missing SAN www.example.com"], result.findings);
+        assert_eq!(vec!["prod"], result.tags);
+
+        // required_sans at top level is checked by policy of client instead
+        let mut config: Config = "required_sans = [\"api.example.org\"]".parse().unwrap();
+        config.domains.push(DomainConfig {
+            name: "example.com".into(),
+            required_sans: vec!["www.example.com".into(), "api.example.com".into()],
+            ..Default::default()
+        });
+        let mut result = CheckResult {
+            domain_name: "example.com",
+            state: CheckState::Ok,
+            sans: vec!["*.example.com".into()],
+            ..Default::default()
+        };
+        annotate(&mut result, &config, &Snoozes::default(), &Utc::now());
+        assert!(matches!(result.state, CheckState::Ok));
+
+        let mut result = CheckResult {
+            domain_name: "example.com",
+            state: CheckState::Ok,
+            sans: vec!["www.example.com".into()],
+            ..Default::default()
+        };
+        annotate(&mut result, &config, &Snoozes::default(), &Utc::now());
+        assert!(matches!(result.state, CheckState::Warning));
+        assert_eq!(vec!["SAN api.example.com is not covered"], result.findings);
+    }
+
+    #[test]
+    fn test_snooze_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = Opts {
+            snooze_file: dir.path().join("snoozes.json"),
+            ..Default::default()
+        };
+        let domain_names = vec!["example.com".to_string()];
+        let until = NaiveDate::from_ymd_opt(9999, 12, 31);
+        snooze_command(&opts, &domain_names, until, false).unwrap();
+        let snoozes = Snoozes::load(&opts.snooze_file).unwrap();
+        assert_eq!(1, snoozes.iter().count());
+
+        snooze_command(&opts, &domain_names, None, true).unwrap();
+        let snoozes = Snoozes::load(&opts.snooze_file).unwrap();
+        assert_eq!(0, snoozes.iter().count());
+    }
+}
@@ -0,0 +1,47 @@
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+
+/// Variables enabling OTLP exporter, other OTEL_* variables e.g. OTEL_EXPORTER_OTLP_HEADERS
+/// are read by exporter itself
+const ENDPOINT_VARS: &[&str] = &[
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+];
+
+/// Tracer provider exporting spans over OTLP/HTTP, when OTLP endpoint is configured with
+/// standard environment variables
+pub fn tracer_provider<F>(var: F) -> anyhow::Result<Option<SdkTracerProvider>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let enabled = ENDPOINT_VARS
+        .iter()
+        .any(|name| var(name).is_some_and(|v| !v.is_empty()));
+    if !enabled || var("OTEL_SDK_DISABLED").is_some_and(|v| v == "true") {
+        return Ok(None);
+    }
+    let exporter = SpanExporter::builder().with_http().build()?;
+    let service_name = var("OTEL_SERVICE_NAME").unwrap_or_else(|| "hcc".to_string());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    Ok(Some(provider))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::telemetry::tracer_provider;
+
+    #[test]
+    fn test_tracer_provider() {
+        assert!(tracer_provider(|_| None).unwrap().is_none());
+        let var = |name: &str| match name {
+            "OTEL_EXPORTER_OTLP_ENDPOINT" => Some("http://localhost:4318".to_string()),
+            "OTEL_SDK_DISABLED" => Some("true".to_string()),
+            _ => None,
+        };
+        assert!(tracer_provider(var).unwrap().is_none());
+    }
+}
@@ -0,0 +1,408 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{SubsecRound, Utc};
+use futures::executor;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row as TableRow, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use hcc::{CheckResult, CheckState, Config, Snoozes, Zone};
+
+use crate::cli::Opts;
+use crate::client::{build_client, cancelled};
+use crate::model::SortKey;
+use crate::policy::annotate;
+
+/// Result of one domain name shown in dashboard
+#[derive(Clone, Debug, Default)]
+struct Row {
+    /// Position of domain name in input
+    index: usize,
+    domain_name: String,
+    state: CheckState,
+    days: i64,
+    not_after: i64,
+    checked_at: i64,
+    elapsed: Option<u128>,
+    sans: Vec<String>,
+    findings: Vec<String>,
+    /// Whether check is in progress
+    checking: bool,
+}
+
+impl Row {
+    fn new(index: usize, result: &CheckResult) -> Self {
+        Row {
+            index,
+            domain_name: result.domain_name.to_string(),
+            state: result.state.clone(),
+            days: result.days,
+            not_after: result.not_after,
+            checked_at: result.checked_at,
+            elapsed: result.elapsed,
+            sans: result.sans.clone(),
+            // why check failed comes first
+            findings: result
+                .error
+                .iter()
+                .map(ToString::to_string)
+                .chain(result.findings.iter().cloned())
+                .collect(),
+            checking: false,
+        }
+    }
+
+    /// Row of domain name whose check could not complete e.g. connection failure
+    fn failed(index: usize, domain_name: &str, error: &anyhow::Error) -> Self {
+        Row {
+            index,
+            domain_name: domain_name.to_string(),
+            checked_at: Utc::now().timestamp(),
+            findings: vec![format!("{0:#}", error)],
+            ..Default::default()
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self.state {
+            CheckState::Ok => Color::Green,
+            CheckState::Warning => Color::Yellow,
+            CheckState::Expired | CheckState::Unknown => Color::Red,
+        }
+    }
+}
+
+/// State of dashboard
+struct App {
+    rows: Vec<Row>,
+    sort: SortKey,
+    table: TableState,
+    detail: bool,
+    refreshed_at: Instant,
+    /// Timezone of timestamps
+    zone: Zone,
+}
+
+impl App {
+    fn new(domain_names: &[&str], sort: SortKey) -> Self {
+        let rows = domain_names
+            .iter()
+            .enumerate()
+            .map(|(index, domain_name)| Row {
+                index,
+                domain_name: domain_name.to_string(),
+                checking: true,
+                ..Default::default()
+            })
+            .collect();
+        let mut app = App {
+            rows,
+            sort,
+            table: TableState::default().with_selected(Some(0)),
+            detail: false,
+            refreshed_at: Instant::now(),
+            zone: Zone::default(),
+        };
+        app.sort_rows();
+        app
+    }
+
+    fn selected(&self) -> Option<&Row> {
+        self.table.selected().and_then(|i| self.rows.get(i))
+    }
+
+    /// Sort rows and keep selection on the same domain name
+    fn sort_rows(&mut self) {
+        let selected = self.selected().map(|r| r.index);
+        match self.sort {
+            SortKey::Days => self.rows.sort_by_key(|r| (r.days, r.index)),
+            SortKey::Domain => self.rows.sort_by(|a, b| a.domain_name.cmp(&b.domain_name)),
+            SortKey::State => self
+                .rows
+                .sort_by_key(|r| (std::cmp::Reverse(r.state.exit_code()), r.index)),
+            SortKey::Input => self.rows.sort_by_key(|r| r.index),
+        }
+        if let Some(selected) = selected {
+            let position = self.rows.iter().position(|r| r.index == selected);
+            self.table.select(position);
+        }
+    }
+
+    fn next_sort(&mut self) {
+        self.sort = match self.sort {
+            SortKey::Days => SortKey::Domain,
+            SortKey::Domain => SortKey::State,
+            SortKey::State => SortKey::Input,
+            SortKey::Input => SortKey::Days,
+        };
+        self.sort_rows();
+    }
+
+    /// Replace row of the same domain name with result
+    fn update(&mut self, row: Row) {
+        if let Some(r) = self.rows.iter_mut().find(|r| r.index == row.index) {
+            *r = row;
+        }
+        self.sort_rows();
+    }
+
+    /// Mark rows as checking and return them as requests to worker
+    fn recheck(&mut self, all: bool) -> Vec<(usize, String)> {
+        let selected = self.table.selected();
+        let mut requests = vec![];
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            if all || Some(i) == selected {
+                row.checking = true;
+                requests.push((row.index, row.domain_name.clone()));
+            }
+        }
+        if all {
+            self.refreshed_at = Instant::now();
+        }
+        requests
+    }
+
+    fn draw(&mut self, frame: &mut Frame, interval: Duration) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        match self.selected().filter(|_| self.detail) {
+            Some(row) => {
+                let paragraph = Paragraph::new(detail_lines(row, &self.zone))
+                    .block(Block::bordered().title(format!(" {0} ", row.domain_name)));
+                frame.render_widget(paragraph, main);
+            }
+            None => {
+                let header = TableRow::new(["Domain name", "State", "Days", "Expires at"])
+                    .style(Style::default().add_modifier(Modifier::BOLD));
+                let rows = self.rows.iter().map(|r| {
+                    let (state, days, expires_at) = match (r.checking, r.not_after) {
+                        (true, _) => ("checking".to_string(), String::new(), String::new()),
+                        // check could not complete
+                        (false, 0) => (r.state.to_string(), String::new(), String::new()),
+                        _ => (
+                            r.state.to_string(),
+                            r.days.to_string(),
+                            self.zone.format(r.not_after),
+                        ),
+                    };
+                    TableRow::new([
+                        Cell::from(r.domain_name.clone()),
+                        Cell::from(state).style(Style::default().fg(r.color())),
+                        Cell::from(days),
+                        Cell::from(expires_at),
+                    ])
+                });
+                let widths = [
+                    Constraint::Fill(1),
+                    Constraint::Length(10),
+                    Constraint::Length(8),
+                    Constraint::Length(26),
+                ];
+                let table = Table::new(rows, widths)
+                    .header(header)
+                    .block(Block::bordered().title(" hcc "))
+                    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                frame.render_stateful_widget(table, main, &mut self.table);
+            }
+        }
+
+        let checking = self.rows.iter().filter(|r| r.checking).count();
+        let status = if checking > 0 {
+            format!("checking {0}", checking)
+        } else {
+            let left = interval.saturating_sub(self.refreshed_at.elapsed());
+            format!("refresh in {0}s", left.as_secs())
+        };
+        let help = format!(
+            " q quit | enter detail | s sort by {0:?} | r recheck | R recheck all | {1}",
+            self.sort, status
+        );
+        frame.render_widget(Line::from(help), footer);
+    }
+}
+
+fn detail_lines(row: &Row, zone: &Zone) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(format!("State:      {0}", row.state)),
+        Line::from(format!("Days:       {0}", row.days)),
+        Line::from(format!("Expires at: {0}", zone.format(row.not_after))),
+        Line::from(format!("Checked at: {0}", zone.format(row.checked_at))),
+    ];
+    if let Some(elapsed) = row.elapsed {
+        lines.push(Line::from(format!("Elapsed:    {0}ms", elapsed)));
+    }
+    lines.push(Line::from("SANs:"));
+    lines.extend(row.sans.iter().map(|s| Line::from(format!("  {0}", s))));
+    if !row.findings.is_empty() {
+        lines.push(Line::from("Findings:"));
+        lines.extend(row.findings.iter().map(|f| Line::from(format!("  {0}", f))));
+    }
+    lines
+}
+
+/// Check domain names requested by dashboard one by one in background, so dashboard stays
+/// responsive
+fn spawn_worker(
+    opts: Opts,
+    config: Config,
+    grace_in_days: i64,
+    sni: Option<String>,
+    requests: Receiver<Vec<(usize, String)>>,
+    rows: Sender<Row>,
+) {
+    thread::spawn(move || {
+        for request in requests {
+            // client remembers when it's created, so build one for each request
+            let client = build_client(&opts, config.effective_policy(), grace_in_days, sni.clone());
+            let snoozes = Snoozes::load(&opts.snooze_file).unwrap_or_default();
+            let now = Utc::now().round_subsecs(0);
+            for (index, domain_name) in request {
+                let checked = client
+                    .as_ref()
+                    .map_err(|e| anyhow::anyhow!("{0:#}", e))
+                    .and_then(|c| Ok(executor::block_on(c.check_certificate(&domain_name))?));
+                let row = match checked {
+                    Ok(mut result) => {
+                        annotate(&mut result, &config, &snoozes, &now);
+                        Row::new(index, &result)
+                    }
+                    Err(e) => Row::failed(index, &domain_name, &e),
+                };
+                if rows.send(row).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Show domain names in a table refreshed every interval, until q is pressed
+pub fn tui_command(
+    opts: &Opts,
+    config: &Config,
+    domain_names: &[&str],
+    grace_in_days: i64,
+    sni: Option<String>,
+    interval: Duration,
+) -> anyhow::Result<i32> {
+    if domain_names.is_empty() {
+        anyhow::bail!("no domain name to check, pass domain names or --config");
+    }
+    let (request_tx, request_rx) = mpsc::channel();
+    let (row_tx, row_rx) = mpsc::channel();
+    spawn_worker(
+        opts.clone(),
+        config.clone(),
+        grace_in_days,
+        sni,
+        request_rx,
+        row_tx,
+    );
+
+    let mut app = App::new(domain_names, opts.sort.clone());
+    app.zone = opts.timezone;
+    request_tx.send(app.recheck(true))?;
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app, &request_tx, &row_rx, interval);
+    ratatui::restore();
+    result.map(|_| 0)
+}
+
+fn run(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    requests: &Sender<Vec<(usize, String)>>,
+    rows: &Receiver<Row>,
+    interval: Duration,
+) -> anyhow::Result<()> {
+    loop {
+        // SIGTERM or --max-runtime ends dashboard as q does
+        if cancelled() {
+            return Ok(());
+        }
+        while let Ok(row) = rows.try_recv() {
+            app.update(row);
+        }
+        if app.refreshed_at.elapsed() >= interval && app.rows.iter().all(|r| !r.checking) {
+            requests.send(app.recheck(true))?;
+        }
+
+        terminal.draw(|frame| app.draw(frame, interval))?;
+
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match key.code {
+            KeyCode::Char('q') => return Ok(()),
+            KeyCode::Esc if app.detail => app.detail = false,
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Enter => app.detail = !app.detail,
+            KeyCode::Down | KeyCode::Char('j') => app.table.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => app.table.select_previous(),
+            KeyCode::Char('s') => app.next_sort(),
+            KeyCode::Char('r') => requests.send(app.recheck(false))?,
+            KeyCode::Char('R') => requests.send(app.recheck(true))?,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hcc::{CheckResult, CheckState};
+
+    use crate::model::SortKey;
+    use crate::tui::{App, Row};
+
+    fn domain_names(app: &App) -> Vec<&str> {
+        app.rows.iter().map(|r| r.domain_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_update() {
+        let mut app = App::new(&["a.example.com", "b.example.com"], SortKey::Days);
+        assert!(app.rows.iter().all(|r| r.checking));
+        assert_eq!(Some(0), app.table.selected());
+
+        let result = CheckResult {
+            state: CheckState::Ok,
+            domain_name: "a.example.com",
+            days: 42,
+            ..Default::default()
+        };
+        app.update(Row::new(0, &result));
+        // selection follows a.example.com after it's sorted after b.example.com
+        assert_eq!(vec!["b.example.com", "a.example.com"], domain_names(&app));
+        assert_eq!(Some(1), app.table.selected());
+        assert!(!app.rows[1].checking);
+
+        app.next_sort();
+        assert_eq!(SortKey::Domain, app.sort);
+        assert_eq!(vec!["a.example.com", "b.example.com"], domain_names(&app));
+        assert_eq!(Some(0), app.table.selected());
+    }
+
+    #[test]
+    fn test_recheck() {
+        let mut app = App::new(&["a.example.com", "b.example.com"], SortKey::Input);
+        for row in app.rows.iter_mut() {
+            row.checking = false;
+        }
+        app.table.select(Some(1));
+        assert_eq!(vec![(1, "b.example.com".to_string())], app.recheck(false));
+        assert!(app.rows[1].checking);
+        assert!(!app.rows[0].checking);
+        assert_eq!(2, app.recheck(true).len());
+    }
+}
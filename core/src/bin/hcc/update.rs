@@ -0,0 +1,270 @@
+use std::env::consts::{ARCH, OS};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use ring::digest::{digest, SHA256};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::Deserialize;
+use tracing::debug;
+
+/// Repository on GitHub releases are published to
+pub const REPOSITORY: &str = "henry40408/hcc";
+
+/// Ed25519 public key in base64 of key the release workflow signs checksums with
+pub const PUBLIC_KEY: &str = "qqdGT60UHjkqTElN3cEwxqfMq4hTJwMpdoXKBaX6HTA=";
+
+/// Asset listing SHA-256 checksums of binaries in format of sha256sum
+const CHECKSUMS: &str = "SHA256SUMS";
+
+/// Asset of Ed25519 signature of checksums
+const SIGNATURE: &str = "SHA256SUMS.sig";
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> anyhow::Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .with_context(|| format!("release {0} has no asset {1}", self.tag_name, name))
+    }
+}
+
+/// Name of release asset built for this platform e.g. hcc-x86_64-linux
+fn asset_name(os: &str, arch: &str) -> String {
+    let bin_name = env!("CARGO_BIN_NAME");
+    if os == "windows" {
+        format!("{0}-{1}-{2}.exe", bin_name, arch, os)
+    } else {
+        format!("{0}-{1}-{2}", bin_name, arch, os)
+    }
+}
+
+/// Major, minor and patch of version e.g. v1.2.3, pre-release and build metadata are ignored
+fn parse_version(s: &str) -> anyhow::Result<(u64, u64, u64)> {
+    let core = s
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default();
+    let parts = core
+        .split('.')
+        .map(|p| p.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .with_context(|| format!("invalid version {0}", s))?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => bail!("invalid version {0}", s),
+    }
+}
+
+/// Expected checksum of asset in checksums in format of sha256sum, where name may be prefixed
+/// with * in binary mode
+fn checksum_of<'a>(checksums: &'a str, name: &str) -> Option<&'a str> {
+    checksums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let checksum = parts.next()?;
+        let file = parts.next()?.trim_start_matches('*');
+        if file == name {
+            Some(checksum)
+        } else {
+            None
+        }
+    })
+}
+
+/// Verify SHA-256 checksum of binary against checksums
+fn verify_checksum(binary: &[u8], checksums: &str, name: &str) -> anyhow::Result<()> {
+    let expected =
+        checksum_of(checksums, name).with_context(|| format!("no checksum of {0}", name))?;
+    let actual: String = digest(&SHA256, binary)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{0:02x}", b))
+        .collect();
+    if !expected.eq_ignore_ascii_case(&actual) {
+        bail!(
+            "checksum of {0} mismatches, expected {1} but got {2}",
+            name,
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Verify Ed25519 signature of checksums with public key in base64
+fn verify_signature(checksums: &[u8], signature: &[u8], public_key: &str) -> anyhow::Result<()> {
+    let public_key = base64::decode(public_key.trim()).context("invalid public key")?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(checksums, signature)
+        .map_err(|_| anyhow::anyhow!("signature of {0} is invalid", CHECKSUMS))
+}
+
+/// Replace executable with binary, by renaming a file next to it, so executable is never
+/// partially written
+fn replace_executable(exe: &Path, binary: &[u8]) -> anyhow::Result<()> {
+    let file_name = exe.file_name().context("executable has no file name")?;
+    let staged = exe.with_file_name(format!(".{0}.new", file_name.to_string_lossy()));
+    fs::write(&staged, binary).with_context(|| format!("failed to write {0:?}", staged))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    // running executable could be renamed but not overwritten on Windows
+    #[cfg(windows)]
+    fs::rename(exe, exe.with_extension("old"))?;
+    fs::rename(&staged, exe).with_context(|| format!("failed to replace {0:?}", exe))
+}
+
+/// Update executable to the latest release or release of tag on GitHub, after verifying
+/// signature of checksums with public key, and checksum of binary
+pub fn self_update_command(
+    repository: &str,
+    tag: Option<&str>,
+    check: bool,
+    public_key: &str,
+) -> anyhow::Result<i32> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()?;
+    let url = match tag {
+        Some(tag) => format!(
+            "https://api.github.com/repos/{0}/releases/tags/{1}",
+            repository, tag
+        ),
+        None => format!(
+            "https://api.github.com/repos/{0}/releases/latest",
+            repository
+        ),
+    };
+    let download = |url: &str| {
+        client
+            .get(url)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.bytes())
+            .with_context(|| format!("failed to download {0}", url))
+    };
+    let release: Release =
+        serde_json::from_slice(&download(&url)?).context("failed to parse release")?;
+    debug!(tag = %release.tag_name, "found release");
+
+    let current = env!("CARGO_PKG_VERSION");
+    let newer = parse_version(&release.tag_name)? > parse_version(current)?;
+    if check {
+        if newer {
+            println!(
+                "{0} is available, current version is {1}",
+                release.tag_name, current
+            );
+            return Ok(1);
+        }
+        println!("{0} is up to date", current);
+        return Ok(0);
+    }
+    // release of tag is installed even if it's older
+    if tag.is_none() && !newer {
+        println!("{0} is up to date", current);
+        return Ok(0);
+    }
+
+    let name = asset_name(OS, ARCH);
+    let binary = download(&release.asset(&name)?.browser_download_url)?;
+    let checksums = download(&release.asset(CHECKSUMS)?.browser_download_url)?;
+    // checksums of the same release prove nothing unless signed
+    let signature = download(&release.asset(SIGNATURE)?.browser_download_url)?;
+    verify_signature(&checksums, &signature, public_key)?;
+    let checksums = String::from_utf8_lossy(&checksums);
+    verify_checksum(&binary, &checksums, &name)?;
+
+    let exe = std::env::current_exe().context("failed to locate executable")?;
+    replace_executable(&exe, &binary)?;
+    println!(
+        "updated {0} from {1} to {2}",
+        exe.display(),
+        current,
+        release.tag_name
+    );
+    Ok(0)
+}
+
+#[cfg(test)]
+mod test {
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use crate::update::{
+        asset_name, checksum_of, parse_version, replace_executable, verify_checksum,
+        verify_signature, PUBLIC_KEY,
+    };
+
+    const CHECKSUMS: &str = "\
+2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  hcc-x86_64-linux
+486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7 *hcc-x86_64-windows.exe
+";
+
+    #[test]
+    fn test_asset_name() {
+        assert_eq!("hcc-x86_64-linux", asset_name("linux", "x86_64"));
+        assert_eq!("hcc-aarch64-macos", asset_name("macos", "aarch64"));
+        assert_eq!("hcc-x86_64-windows.exe", asset_name("windows", "x86_64"));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!((1, 2, 3), parse_version("v1.2.3").unwrap());
+        assert_eq!((0, 1, 0), parse_version("0.1.0-rc.1").unwrap());
+        assert!(parse_version("v1.2.3").unwrap() > parse_version("1.2.0").unwrap());
+        assert!(parse_version("latest").is_err());
+        assert!(parse_version("1.2").is_err());
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        assert_eq!(
+            Some("486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7"),
+            checksum_of(CHECKSUMS, "hcc-x86_64-windows.exe")
+        );
+        verify_checksum(b"hello", CHECKSUMS, "hcc-x86_64-linux").unwrap();
+        let e = verify_checksum(b"world", CHECKSUMS, "hcc-x86_64-linux").unwrap_err();
+        assert!(e.to_string().contains("mismatches"), "{0}", e);
+        assert!(verify_checksum(b"hello", CHECKSUMS, "hcc-aarch64-macos").is_err());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public_key = base64::encode(pair.public_key().as_ref());
+        let signature = pair.sign(CHECKSUMS.as_bytes());
+        verify_signature(CHECKSUMS.as_bytes(), signature.as_ref(), &public_key).unwrap();
+        assert!(verify_signature(b"tampered", signature.as_ref(), &public_key).is_err());
+        assert!(verify_signature(CHECKSUMS.as_bytes(), signature.as_ref(), PUBLIC_KEY).is_err());
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("hcc");
+        std::fs::write(&exe, b"old").unwrap();
+        replace_executable(&exe, b"new").unwrap();
+        assert_eq!(b"new", std::fs::read(&exe).unwrap().as_slice());
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::check_result::{CheckResult, CheckState};
+use crate::error::CheckError;
+use crate::sink::write_atomic;
+use crate::trust_store::TrustStatus;
+
+/// Results of previous checks on disk, reused within TTL instead of handshaking again
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    #[serde(skip)]
+    ttl: Duration,
+    #[serde(flatten)]
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Options affecting result e.g. grace period, result checked with other options is not
+    /// reused
+    variant: String,
+    id: Uuid,
+    state: CheckState,
+    checked_at: i64,
+    days: i64,
+    not_after: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chain_not_after: Option<i64>,
+    elapsed: Option<u128>,
+    sans: Vec<String>,
+    findings: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<CheckError>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trust: Vec<TrustStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+}
+
+impl Cache {
+    /// Load cache from JSON file, missing file is treated as empty cache
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use hcc::Cache;
+    /// let cache = Cache::load("/nonexistent/cache.json", Duration::from_secs(3600)).unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P, ttl: Duration) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut cache: Cache = match fs::read_to_string(path) {
+            Ok(s) => {
+                serde_json::from_str(&s).with_context(|| format!("failed to parse {0:?}", path))?
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Cache::default(),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {0:?}", path)),
+        };
+        cache.ttl = ttl;
+        Ok(cache)
+    }
+
+    /// Save cache to JSON file atomically, so concurrent invocations never read partial content
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let s = serde_json::to_string_pretty(self)?;
+        write_atomic(path, s.as_bytes()).with_context(|| format!("failed to write {0:?}", path))
+    }
+
+    /// Result of domain name checked with the same variant within TTL
+    pub fn get<'a>(
+        &self,
+        domain_name: &'a str,
+        variant: &str,
+        now: &DateTime<Utc>,
+    ) -> Option<CheckResult<'a>> {
+        let entry = self
+            .entries
+            .get(domain_name)
+            .filter(|e| e.variant == variant && Self::is_fresh(e, self.ttl, now))?;
+        Some(CheckResult {
+            id: entry.id,
+            state: entry.state.clone(),
+            checked_at: entry.checked_at,
+            days: entry.days,
+            domain_name,
+            not_after: entry.not_after,
+            chain_not_after: entry.chain_not_after,
+            elapsed: entry.elapsed,
+            sans: entry.sans.clone(),
+            findings: entry.findings.clone(),
+            error: entry.error.clone(),
+            trust: entry.trust.clone(),
+            fingerprint: entry.fingerprint.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// Remember result of domain name checked with variant
+    pub fn insert(&mut self, result: &CheckResult, variant: &str) {
+        // nothing was checked, so there is nothing to reuse
+        if result.is_skipped() {
+            return;
+        }
+        let entry = Entry {
+            variant: variant.to_string(),
+            id: result.id,
+            state: result.state.clone(),
+            checked_at: result.checked_at,
+            days: result.days,
+            not_after: result.not_after,
+            chain_not_after: result.chain_not_after,
+            elapsed: result.elapsed,
+            sans: result.sans.clone(),
+            findings: result.findings.clone(),
+            error: result.error.clone(),
+            trust: result.trust.clone(),
+            fingerprint: result.fingerprint.clone(),
+        };
+        self.entries.insert(result.domain_name.to_string(), entry);
+    }
+
+    /// Drop results older than TTL
+    pub fn prune(&mut self, now: &DateTime<Utc>) {
+        let ttl = self.ttl;
+        self.entries.retain(|_, e| Self::is_fresh(e, ttl, now));
+    }
+
+    /// Whether entry is checked within TTL, entry from the future e.g. due to clock skew is not
+    fn is_fresh(entry: &Entry, ttl: Duration, now: &DateTime<Utc>) -> bool {
+        u64::try_from(now.timestamp() - entry.checked_at)
+            .is_ok_and(|age| Duration::from_secs(age) < ttl)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::cache::Cache;
+    use crate::check_result::{CheckResult, CheckState};
+
+    #[test]
+    fn test_get() {
+        let now = Utc.timestamp(1_622_533_524, 0);
+        let mut cache = Cache::load("/nonexistent/cache.json", Duration::from_secs(3600)).unwrap();
+        let result = CheckResult {
+            state: CheckState::Ok,
+            checked_at: now.timestamp(),
+            domain_name: "example.com",
+            days: 42,
+            sans: vec!["example.com".into()],
+            ..Default::default()
+        };
+        cache.insert(&result, "grace=7");
+
+        let cached = cache.get("example.com", "grace=7", &now).unwrap();
+        assert_eq!(result.id, cached.id);
+        assert_eq!(42, cached.days);
+        assert_eq!(result.sans, cached.sans);
+        assert!(matches!(cached.state, CheckState::Ok));
+
+        assert!(cache.get("example.org", "grace=7", &now).is_none());
+        assert!(cache.get("example.com", "grace=14", &now).is_none());
+        let later = Utc.timestamp(now.timestamp() + 3600, 0);
+        assert!(cache.get("example.com", "grace=7", &later).is_none());
+
+        cache.prune(&later);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let ttl = Duration::from_secs(60);
+        let mut cache = Cache::load(&path, ttl).unwrap();
+        cache.insert(
+            &CheckResult {
+                domain_name: "example.com",
+                checked_at: Utc::now().timestamp(),
+                ..Default::default()
+            },
+            "",
+        );
+        cache.save(&path).unwrap();
+
+        let cache = Cache::load(&path, ttl).unwrap();
+        assert!(cache.get("example.com", "", &Utc::now()).is_some());
+    }
+}
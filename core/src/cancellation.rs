@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Cancellation of a run by signal or deadline, checks not started yet are skipped, checks in
+/// flight are interrupted on cancel and give up by deadline
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    flag: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    sockets: Arc<Mutex<Sockets>>,
+}
+
+/// Sockets of checks in flight, shut down on cancel so blocking reads return at once
+#[derive(Debug, Default)]
+struct Sockets {
+    next: u64,
+    open: BTreeMap<u64, TcpStream>,
+}
+
+/// Socket tracked until check is done
+pub(crate) struct Tracked {
+    sockets: Arc<Mutex<Sockets>>,
+    id: Option<u64>,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.sockets.lock().unwrap().open.remove(&id);
+        }
+    }
+}
+
+impl Cancellation {
+    /// Cancellation on cancel, and by deadline after max runtime from now if given
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use hcc::Cancellation;
+    /// let cancellation = Cancellation::new(Some(Duration::from_secs(300)));
+    /// assert!(!cancellation.is_cancelled());
+    /// cancellation.cancel();
+    /// assert_eq!(Some("run was interrupted"), cancellation.reason());
+    /// ```
+    pub fn new(max_runtime: Option<Duration>) -> Self {
+        Cancellation {
+            deadline: max_runtime.map(|d| Instant::now() + d),
+            ..Default::default()
+        }
+    }
+
+    /// Cancel run e.g. on signal, interrupting checks in flight
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+        for sock in self.sockets.lock().unwrap().open.values() {
+            let _ = sock.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Whether run is cancelled or runs past deadline
+    pub fn is_cancelled(&self) -> bool {
+        self.reason().is_some()
+    }
+
+    /// Why run is cancelled, none when it goes on
+    pub fn reason(&self) -> Option<&'static str> {
+        if self.flag.load(Ordering::SeqCst) {
+            Some("run was interrupted")
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some("run exceeded maximum runtime")
+        } else {
+            None
+        }
+    }
+
+    /// Time left until deadline, none without deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// Track socket of check in flight until returned guard is dropped
+    pub(crate) fn track(&self, sock: &TcpStream) -> Tracked {
+        let mut sockets = self.sockets.lock().unwrap();
+        let id = sock.try_clone().ok().map(|clone| {
+            let id = sockets.next;
+            sockets.next += 1;
+            sockets.open.insert(id, clone);
+            id
+        });
+        // run may be cancelled after check started but before socket is tracked
+        if self.flag.load(Ordering::SeqCst) {
+            let _ = sock.shutdown(Shutdown::Both);
+        }
+        Tracked {
+            sockets: self.sockets.clone(),
+            id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    use crate::cancellation::Cancellation;
+
+    #[test]
+    fn test_deadline() {
+        let cancellation = Cancellation::new(Some(Duration::ZERO));
+        assert_eq!(Some("run exceeded maximum runtime"), cancellation.reason());
+        assert_eq!(Some(Duration::ZERO), cancellation.remaining());
+
+        let cancellation = Cancellation::default();
+        assert!(!cancellation.is_cancelled());
+        assert!(cancellation.remaining().is_none());
+        let copy = cancellation.clone();
+        copy.cancel();
+        assert!(cancellation.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut sock = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let cancellation = Cancellation::default();
+        let tracked = cancellation.track(&sock);
+        let copy = cancellation.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            copy.cancel();
+        });
+        // server never writes, read returns only because socket is shut down
+        let mut buf = [0; 1];
+        assert_eq!(0, sock.read(&mut buf).unwrap_or(0));
+        handle.join().unwrap();
+        drop(tracked);
+        assert!(cancellation.sockets.lock().unwrap().open.is_empty());
+    }
+}
@@ -1,14 +1,24 @@
 use std::fmt;
 
-use chrono::{DateTime, TimeZone, Utc};
-use num_format::{Locale, ToFormattedString};
+use anyhow::Context;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
+use uuid::Uuid;
+
+use crate::check_client::parse_certificates;
+use crate::detail::CertificateDetail;
+use crate::error::CheckError;
+use crate::http::HttpResponse;
+use crate::number_format::NumberFormat;
+use crate::trust_store::TrustStatus;
+use crate::zone::Zone;
 
 /// State of Certificate
-#[derive(Debug)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub enum CheckState {
     /// Default state
+    #[default]
     Unknown,
     /// Certificate is valid
     Ok,
@@ -18,9 +28,20 @@ pub enum CheckState {
     Expired,
 }
 
-impl Default for CheckState {
-    fn default() -> Self {
-        CheckState::Unknown
+impl CheckState {
+    /// Exit code of state following convention of Nagios plugins
+    ///
+    /// ```
+    /// # use hcc::CheckState;
+    /// assert_eq!(0, CheckState::Ok.exit_code());
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CheckState::Ok => 0,
+            CheckState::Warning => 1,
+            CheckState::Expired => 2,
+            CheckState::Unknown => 3,
+        }
     }
 }
 
@@ -36,8 +57,10 @@ impl fmt::Display for CheckState {
 }
 
 /// Check result
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct CheckResult<'a> {
+    /// Unique ID of check to trace result across logs and sinks
+    pub id: Uuid,
     /// State of certificate
     pub state: CheckState,
     /// When is domain name got checked in seconds since Unix epoch
@@ -46,10 +69,40 @@ pub struct CheckResult<'a> {
     pub days: i64,
     /// Domain name that got checked
     pub domain_name: &'a str,
-    /// Exact expiration time in seconds since Unix epoch
+    /// Exact expiration time of leaf certificate in seconds since Unix epoch
     pub not_after: i64,
+    /// The earliest expiration time anywhere in chain including leaf in seconds since Unix
+    /// epoch, none when chain is not seen
+    pub chain_not_after: Option<i64>,
     /// Elapsed time in milliseconds
     pub elapsed: Option<u128>,
+    /// Date until which alerts of domain name are silenced
+    pub snoozed_until: Option<NaiveDate>,
+    /// Subject alternative names i.e. DNS names in certificate
+    pub sans: Vec<String>,
+    /// Problems found besides expiration e.g. missing subject alternative names
+    pub findings: Vec<String>,
+    /// Tags of domain name in configuration file
+    pub tags: Vec<String>,
+    /// Certificates in DER format, leaf first, kept when client includes certificates
+    pub certificates: Vec<Vec<u8>>,
+    /// Full detail of leaf certificate, when client is built with detail
+    pub detail: Option<CertificateDetail>,
+    /// Why check failed e.g. host is unreachable or handshake failed, none when certificate
+    /// is checked
+    pub error: Option<CheckError>,
+    /// Whether chain is trusted by each trust store, when client verifies against trust stores
+    pub trust: Vec<TrustStatus>,
+    /// Status line and headers of response to HTTP request, when client sends one
+    pub http: Option<HttpResponse>,
+    /// SHA-256 fingerprint of leaf certificate in DER format, bytes separated by colon, none
+    /// when certificate is not seen
+    pub fingerprint: Option<String>,
+    /// Other domain names serving the same leaf certificate, when results are grouped by
+    /// certificate
+    pub shared_with: Vec<String>,
+    /// Vantage point domain name is checked from e.g. eu-west, when checked by remote agent
+    pub location: Option<String>,
 }
 
 impl<'a> CheckResult<'a> {
@@ -58,10 +111,12 @@ impl<'a> CheckResult<'a> {
     /// ```
     /// # use hcc::CheckResult;
     /// use chrono::Utc;
-    /// CheckResult::expired("expired.badssl.com", &Utc::now());
+    /// use uuid::Uuid;
+    /// CheckResult::expired(Uuid::new_v4(), "expired.badssl.com", &Utc::now());
     /// ```
-    pub fn expired(domain_name: &'a str, checked_at: &'a DateTime<Utc>) -> Self {
+    pub fn expired(id: Uuid, domain_name: &'a str, checked_at: &'a DateTime<Utc>) -> Self {
         CheckResult {
+            id,
             state: CheckState::Expired,
             checked_at: checked_at.timestamp(),
             domain_name,
@@ -69,6 +124,130 @@ impl<'a> CheckResult<'a> {
         }
     }
 
+    /// Create a result from domain name whose check could not complete e.g. host is
+    /// unreachable, state is unknown since certificate is never seen
+    ///
+    /// ```
+    /// # use hcc::{CheckError, CheckResult, CheckState};
+    /// use chrono::Utc;
+    /// use uuid::Uuid;
+    /// let error = CheckError::ConnectTimeout {
+    ///     target: "example.com".into(),
+    /// };
+    /// let result = CheckResult::failed(Uuid::new_v4(), "example.com", &Utc::now(), error);
+    /// assert!(matches!(result.state, CheckState::Unknown));
+    /// ```
+    pub fn failed(
+        id: Uuid,
+        domain_name: &'a str,
+        checked_at: &DateTime<Utc>,
+        error: CheckError,
+    ) -> Self {
+        CheckResult {
+            id,
+            state: CheckState::Unknown,
+            checked_at: checked_at.timestamp(),
+            domain_name,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+
+    /// Compare subject alternative names of certificate with expected ones, and report missing
+    /// or extra names as findings, certificate missing names is degraded to warning
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, CheckState};
+    /// let mut result = CheckResult {
+    ///     state: CheckState::Ok,
+    ///     sans: vec!["example.com".into()],
+    ///     ..Default::default()
+    /// };
+    /// result.expect_sans(&["example.com".into(), "www.example.com".into()]);
+    /// assert!(matches!(result.state, CheckState::Warning));
+    /// ```
+    pub fn expect_sans(&mut self, expected: &[String]) {
+        let has = |sans: &[String], name: &str| sans.iter().any(|s| s.eq_ignore_ascii_case(name));
+        let missing: Vec<&String> = expected.iter().filter(|e| !has(&self.sans, e)).collect();
+        let extra: Vec<&String> = self.sans.iter().filter(|s| !has(expected, s)).collect();
+        for name in &missing {
+            self.findings.push(format!("missing SAN {0}", name));
+        }
+        for name in &extra {
+            self.findings.push(format!("unexpected SAN {0}", name));
+        }
+        if !missing.is_empty() {
+            self.degrade();
+        }
+    }
+
+    /// Require certificate to cover host names, where a wildcard SAN covers one label, and
+    /// report uncovered names as findings, certificate missing any is degraded to warning
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, CheckState};
+    /// let mut result = CheckResult {
+    ///     state: CheckState::Ok,
+    ///     sans: vec!["*.example.com".into()],
+    ///     ..Default::default()
+    /// };
+    /// result.require_sans(&["api.example.com".into()]);
+    /// assert!(matches!(result.state, CheckState::Ok));
+    /// result.require_sans(&["example.com".into()]);
+    /// assert!(matches!(result.state, CheckState::Warning));
+    /// ```
+    pub fn require_sans(&mut self, names: &[String]) {
+        let uncovered: Vec<&String> = names
+            .iter()
+            .filter(|name| !self.sans.iter().any(|san| san_covers(san, name)))
+            .collect();
+        for name in &uncovered {
+            self.findings.push(format!("SAN {0} is not covered", name));
+        }
+        if !uncovered.is_empty() {
+            self.degrade();
+        }
+    }
+
+    /// Degrade valid certificate to warning e.g. due to findings, other states are kept
+    pub fn degrade(&mut self) {
+        if matches!(self.state, CheckState::Ok) {
+            self.state = CheckState::Warning;
+        }
+    }
+
+    /// Whether result deserves attention i.e. certificate is not valid and alerts are not snoozed
+    ///
+    /// ```
+    /// # use hcc::CheckResult;
+    /// let result = CheckResult::default();
+    /// assert!(result.is_failure());
+    /// ```
+    pub fn is_failure(&self) -> bool {
+        !matches!(self.state, CheckState::Ok) && self.snoozed_until.is_none()
+    }
+
+    /// Whether domain name was left unchecked because run was cancelled
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.error, Some(CheckError::Skipped { .. }))
+    }
+
+    /// Remaining days to the earliest expiration anywhere in chain, none when chain is not seen
+    ///
+    /// ```
+    /// # use hcc::CheckResult;
+    /// let result = CheckResult {
+    ///     checked_at: 0,
+    ///     chain_not_after: Some(3 * 86400),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(Some(3), result.chain_days());
+    /// ```
+    pub fn chain_days(&self) -> Option<i64> {
+        self.chain_not_after
+            .map(|not_after| (not_after - self.checked_at) / 86400)
+    }
+
     /// Expiration date of certficate in RFC3339 format
     ///
     /// ```
@@ -88,25 +267,65 @@ impl<'a> CheckResult<'a> {
     /// result.sentence();
     /// ```
     pub fn sentence(&self) -> String {
-        let days = self.days.to_formatted_string(&Locale::en);
+        self.sentence_styled(&TextStyle::default())
+    }
+
+    /// Human-readable sentence of certificate state, with numbers in format
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, CheckState, NumberFormat};
+    /// let result = CheckResult {
+    ///     state: CheckState::Ok,
+    ///     days: 1000,
+    ///     ..Default::default()
+    /// };
+    /// assert!(result.sentence_with(&NumberFormat::Plain).contains("in 1000 days"));
+    /// ```
+    pub fn sentence_with(&self, numbers: &NumberFormat) -> String {
+        self.sentence_styled(&TextStyle {
+            numbers: *numbers,
+            ..Default::default()
+        })
+    }
+
+    /// Human-readable sentence of certificate state in style
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, CheckState, TextStyle};
+    /// let result = CheckResult {
+    ///     state: CheckState::Ok,
+    ///     days: 1000,
+    ///     ..Default::default()
+    /// };
+    /// let style = TextStyle {
+    ///     human: true,
+    ///     ..Default::default()
+    /// };
+    /// assert!(!result.sentence_styled(&style).contains("days"));
+    /// ```
+    pub fn sentence_styled(&self, style: &TextStyle) -> String {
+        let remaining = if style.human {
+            human_duration(self.checked_at, self.not_after)
+        } else {
+            format!("{0} days", style.numbers.format(self.days))
+        };
+        let not_after = style.zone.format(self.not_after);
         match self.state {
             CheckState::Unknown => format!("certificate state of {} is unknown", self.domain_name),
-            CheckState::Ok => format!(
-                "certificate of {} expires in {} days ({})",
-                self.domain_name,
-                days,
-                self.not_after_timestamp()
+            CheckState::Ok | CheckState::Warning => format!(
+                "certificate of {} expires in {} ({})",
+                self.domain_name, remaining, not_after
             ),
-            CheckState::Warning => format!(
-                "certificate of {} expires in {} days ({})",
+            // expiration is unknown when handshake fails
+            CheckState::Expired if style.human && self.not_after > 0 => format!(
+                "certificate of {} has expired {} ago ({})",
                 self.domain_name,
-                days,
-                self.not_after_timestamp()
+                human_duration(self.not_after, self.checked_at),
+                not_after
             ),
             CheckState::Expired => format!(
                 "certificate of {} has expired ({})",
-                self.domain_name,
-                self.not_after_timestamp()
+                self.domain_name, not_after
             ),
         }
     }
@@ -154,27 +373,182 @@ impl<'a> CheckResult<'a> {
     }
 }
 
-impl<'a> fmt::Display for CheckResult<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl<'a> CheckResult<'a> {
+    /// Line of result in text output, with numbers in format
+    pub fn line(&self, numbers: &NumberFormat) -> String {
+        self.line_styled(&TextStyle {
+            numbers: *numbers,
+            ..Default::default()
+        })
+    }
+
+    /// Line of result in text output in style
+    pub fn line_styled(&self, style: &TextStyle) -> String {
         let mut s = String::with_capacity(100);
 
         s.push_str(&self.state_icon(false));
 
-        s.push_str(&" ");
+        s.push(' ');
 
-        s.push_str(&self.sentence());
+        s.push_str(&self.sentence_styled(style));
+
+        if let Some(ref location) = self.location {
+            s.push_str(&format!(", seen from {0}", location));
+        }
+
+        if !self.shared_with.is_empty() {
+            s.push_str(&format!(", shared with {0}", self.shared_with.join(", ")));
+        }
 
         if let Some(elapsed) = self.elapsed {
             s.push_str(&format!(", {0}ms elapsed", elapsed));
         }
 
-        write!(f, "{}", s)
+        if let Some(ref error) = self.error {
+            s.push_str(&format!("; {0}", error));
+        }
+
+        if !self.findings.is_empty() {
+            s.push_str(&format!("; {0}", self.findings.join(", ")));
+        }
+
+        if let Some(ref http) = self.http {
+            s.push_str(&format!("; {0}", http.status_line));
+        }
+
+        if let Some(until) = self.snoozed_until {
+            s.push_str(&format!(" (snoozed until {0})", until));
+        }
+
+        s
+    }
+}
+
+impl<'a> fmt::Display for CheckResult<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.line(&NumberFormat::default()))
+    }
+}
+
+/// How results are rendered in text output
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextStyle {
+    /// Format of numbers
+    pub numbers: NumberFormat,
+    /// Remaining time in years, months and days instead of days
+    pub human: bool,
+    /// Timezone of timestamps
+    pub zone: Zone,
+}
+
+/// Calendar time between two timestamps in seconds since Unix epoch e.g. "1 year, 2 months, 3
+/// days", counting whole days in UTC
+fn human_duration(from: i64, to: i64) -> String {
+    let (from, to) = (
+        Utc.timestamp(from, 0).naive_utc().date(),
+        Utc.timestamp(to, 0).naive_utc().date(),
+    );
+    let (from, to) = if from <= to { (from, to) } else { (to, from) };
+    let add_months = |months: i32| {
+        let month0 = from.month0() as i32 + months;
+        let (year, month) = (
+            from.year() + month0.div_euclid(12),
+            month0.rem_euclid(12) as u32 + 1,
+        );
+        // clamp to the last day of month e.g. January 31 plus a month is February 28
+        (1..=from.day())
+            .rev()
+            .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+            .unwrap_or(from)
+    };
+    let mut months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    if add_months(months) > to {
+        months -= 1;
+    }
+    let days = (to - add_months(months)).num_days();
+    let (years, months) = (i64::from(months / 12), i64::from(months % 12));
+    let parts: Vec<String> = [(years, "year"), (months, "month"), (days, "day")]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{0} {1}{2}", n, unit, if *n == 1 { "" } else { "s" }))
+        .collect();
+    if parts.is_empty() {
+        "less than a day".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Counts of results by state
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    /// Number of domain names checked
+    pub checked: usize,
+    /// Number of certificates expiring within grace period
+    pub expiring: usize,
+    /// Number of expired certificates
+    pub expired: usize,
+    /// Number of certificates in unknown state
+    pub unknown: usize,
+    /// Grace period in days
+    pub grace_in_days: i64,
+}
+
+impl Summary {
+    /// Summarize results checked with grace period
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, Summary};
+    /// let summary = Summary::new(&[CheckResult::default()], 7);
+    /// assert_eq!(1, summary.checked);
+    /// ```
+    pub fn new(results: &[CheckResult], grace_in_days: i64) -> Self {
+        let mut summary = Summary {
+            checked: results.len(),
+            grace_in_days,
+            ..Default::default()
+        };
+        for result in results {
+            match result.state {
+                CheckState::Ok => {}
+                CheckState::Warning => summary.expiring += 1,
+                CheckState::Expired => summary.expired += 1,
+                CheckState::Unknown => summary.unknown += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{0} checked, {1} expiring within {2} days, {3} expired",
+            self.checked, self.expiring, self.grace_in_days, self.expired
+        )?;
+        if self.unknown > 0 {
+            write!(f, ", {0} unknown", self.unknown)?;
+        }
+        Ok(())
     }
 }
 
+/// Version of JSON output, bumped when fields are removed, renamed or change type, adding
+/// fields keeps it
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON schema of JSON output, see `core/schema/result.schema.json`
+pub const JSON_SCHEMA: &str = include_str!("../schema/result.schema.json");
+
 /// Check result in JSON format
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CheckResultJSON {
+    /// Version of JSON schema, see [`SCHEMA_VERSION`]
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Unique ID of check
+    pub id: String,
     /// State of certificate
     pub state: String,
     /// When is the domain name got checked
@@ -185,8 +559,49 @@ pub struct CheckResultJSON {
     pub domain_name: String,
     /// Expiration time in RFC3389 format
     pub expired_at: String,
+    /// Expiration time of leaf certificate in RFC3339 format, same as expired_at
+    #[serde(default)]
+    pub leaf_not_after: String,
+    /// The earliest expiration time anywhere in chain in RFC3339 format, absent when chain is
+    /// not seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_min_not_after: Option<String>,
     /// Elapsed time in milliseconds
     pub elapsed: u128,
+    /// Problems found besides expiration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<String>,
+    /// Tags of domain name in configuration file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Leaf certificate in PEM format, when certificates are included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
+    /// The rest of certificate chain in PEM format, when chain is included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<String>,
+    /// Full detail of leaf certificate, when detail is requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<CertificateDetail>,
+    /// Why check failed with its kind e.g. `dns_failure` or `handshake_failed`, absent when
+    /// certificate is checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<CheckError>,
+    /// Whether chain is trusted by each trust store, when trust stores are given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trust: Vec<TrustStatus>,
+    /// Status line and headers of response to HTTP request, when a request is sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpResponse>,
+    /// SHA-256 fingerprint of leaf certificate, absent when certificate is not seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Other domain names serving the same leaf certificate, when grouped by certificate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_with: Vec<String>,
+    /// Vantage point domain name is checked from, when checked by remote agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
 }
 
 impl CheckResultJSON {
@@ -204,22 +619,176 @@ impl CheckResultJSON {
     /// ```
     pub fn new(result: &CheckResult) -> CheckResultJSON {
         CheckResultJSON {
+            schema_version: SCHEMA_VERSION,
+            id: result.id.to_string(),
             state: result.state.to_string(),
             days: result.days,
             domain_name: result.domain_name.to_string(),
             checked_at: Utc.timestamp(result.checked_at, 0).to_rfc3339(),
             expired_at: Utc.timestamp(result.not_after, 0).to_rfc3339(),
+            leaf_not_after: Utc.timestamp(result.not_after, 0).to_rfc3339(),
+            chain_min_not_after: result
+                .chain_not_after
+                .map(|t| Utc.timestamp(t, 0).to_rfc3339()),
             elapsed: result.elapsed.unwrap_or(0),
+            findings: result.findings.clone(),
+            tags: result.tags.clone(),
+            certificate: result.certificates.first().map(|der| pem(der)),
+            chain: result
+                .certificates
+                .iter()
+                .skip(1)
+                .map(|der| pem(der))
+                .collect(),
+            detail: result.detail.clone(),
+            error: result.error.clone(),
+            trust: result.trust.clone(),
+            http: result.http.clone(),
+            fingerprint: result.fingerprint.clone(),
+            shared_with: result.shared_with.clone(),
+            location: result.location.clone(),
         }
     }
+
+    /// Convert JSON back to result e.g. replied by remote agent, subject alternative names are
+    /// not kept in JSON and left empty
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, CheckResultJSON, CheckState};
+    /// let json = CheckResultJSON::new(&CheckResult {
+    ///     domain_name: "example.com",
+    ///     state: CheckState::Warning,
+    ///     location: Some("eu-west".into()),
+    ///     ..Default::default()
+    /// });
+    /// let result = json.to_result().unwrap();
+    /// assert!(matches!(result.state, CheckState::Warning));
+    /// assert_eq!(Some("eu-west"), result.location.as_deref());
+    /// ```
+    pub fn to_result(&self) -> anyhow::Result<CheckResult<'_>> {
+        let timestamp = |s: &str| -> anyhow::Result<i64> {
+            Ok(DateTime::parse_from_rfc3339(s)
+                .with_context(|| format!("invalid timestamp {0}", s))?
+                .timestamp())
+        };
+        let state = match self.state.as_str() {
+            "OK" => CheckState::Ok,
+            "WARNING" => CheckState::Warning,
+            // misspelled as it has always been in output
+            "EXPIPRED" | "EXPIRED" => CheckState::Expired,
+            _ => CheckState::Unknown,
+        };
+        let mut certificates = vec![];
+        for pem in self.certificate.iter().chain(self.chain.iter()) {
+            certificates.extend(parse_certificates(pem.as_bytes())?);
+        }
+        Ok(CheckResult {
+            id: self.id.parse().unwrap_or_default(),
+            state,
+            checked_at: timestamp(&self.checked_at)?,
+            days: self.days,
+            domain_name: &self.domain_name,
+            not_after: timestamp(&self.expired_at)?,
+            chain_not_after: self
+                .chain_min_not_after
+                .as_deref()
+                .map(timestamp)
+                .transpose()?,
+            elapsed: Some(self.elapsed).filter(|ms| *ms > 0),
+            findings: self.findings.clone(),
+            tags: self.tags.clone(),
+            certificates,
+            detail: self.detail.clone(),
+            error: self.error.clone(),
+            trust: self.trust.clone(),
+            http: self.http.clone(),
+            fingerprint: self.fingerprint.clone(),
+            shared_with: self.shared_with.clone(),
+            location: self.location.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Report each leaf certificate once, results of domain names sharing a fingerprint e.g.
+/// behind a wildcard certificate are merged into the worst of them, with the other domain
+/// names in [`CheckResult::shared_with`] and their findings kept, results without certificate
+/// are kept as they are, in order of first appearance
+///
+/// ```
+/// # use hcc::{group_by_certificate, CheckResult};
+/// let build = |domain_name| CheckResult {
+///     domain_name,
+///     fingerprint: Some("AB:CD".into()),
+///     ..Default::default()
+/// };
+/// let grouped = group_by_certificate(vec![build("a.example.com"), build("b.example.com")]);
+/// assert_eq!(1, grouped.len());
+/// assert_eq!(vec!["b.example.com"], grouped[0].shared_with);
+/// ```
+pub fn group_by_certificate(results: Vec<CheckResult<'_>>) -> Vec<CheckResult<'_>> {
+    let mut grouped: Vec<CheckResult> = vec![];
+    for result in results {
+        let index = result.fingerprint.as_ref().and_then(|fingerprint| {
+            grouped
+                .iter()
+                .position(|g| g.fingerprint.as_ref() == Some(fingerprint))
+        });
+        let group = match index {
+            Some(index) => &mut grouped[index],
+            None => {
+                grouped.push(result);
+                continue;
+            }
+        };
+        let mut result = result;
+        if result.state.exit_code() > group.state.exit_code() {
+            std::mem::swap(group, &mut result);
+            group.shared_with.append(&mut result.shared_with);
+        }
+        group.shared_with.push(result.domain_name.to_string());
+        for finding in result.findings {
+            if !group.findings.contains(&finding) {
+                group.findings.push(finding);
+            }
+        }
+    }
+    grouped
+}
+
+/// Whether SAN covers host name, a wildcard covers exactly one label
+pub(crate) fn san_covers(san: &str, name: &str) -> bool {
+    let name = name.trim_end_matches('.');
+    match san.strip_prefix("*.") {
+        Some(suffix) => name
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest.eq_ignore_ascii_case(suffix)),
+        None => san.eq_ignore_ascii_case(name),
+    }
+}
+
+/// Encode certificate in DER format as PEM, base64 wrapped at 64 characters
+pub(crate) fn pem(der: &[u8]) -> String {
+    let encoded = base64::encode(der);
+    let mut s = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        s.push_str(&String::from_utf8_lossy(line));
+        s.push('\n');
+    }
+    s.push_str("-----END CERTIFICATE-----\n");
+    s
 }
 
 #[cfg(test)]
 mod test {
     use chrono::{Duration, SubsecRound, TimeZone, Utc};
 
-    use crate::check_result::CheckState;
-    use crate::CheckResult;
+    use uuid::Uuid;
+
+    use crate::check_result::{
+        group_by_certificate, human_duration, CheckState, Summary, JSON_SCHEMA, SCHEMA_VERSION,
+    };
+    use crate::{CheckError, CheckResult, CheckResultJSON, TextStyle};
 
     fn build_result<'a>() -> CheckResult<'a> {
         let days = 512;
@@ -228,7 +797,7 @@ mod test {
         CheckResult {
             checked_at: now.timestamp(),
             days,
-            domain_name: &"example.com",
+            domain_name: "example.com",
             not_after: expired_at.timestamp(),
             ..Default::default()
         }
@@ -270,4 +839,245 @@ mod test {
         );
         assert_eq!(left, right);
     }
+
+    #[test]
+    fn test_display_failed() {
+        let error = CheckError::DnsFailure {
+            host: "example.invalid".into(),
+            reason: "no address found".into(),
+        };
+        let result = CheckResult::failed(Uuid::new_v4(), "example.invalid", &Utc::now(), error);
+        assert_eq!(
+            "[?] certificate state of example.invalid is unknown; failed to resolve example.invalid: no address found",
+            result.to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_human() {
+        let mut result = build_result();
+        result.state = CheckState::Ok;
+        result.checked_at = Utc.ymd(2021, 1, 31).and_hms(12, 0, 0).timestamp();
+        result.not_after = Utc.ymd(2022, 3, 1).and_hms(0, 0, 0).timestamp();
+        let style = TextStyle {
+            human: true,
+            zone: "Asia/Taipei".parse().unwrap(),
+            ..Default::default()
+        };
+        let line = result.line_styled(&style);
+        assert!(
+            line.contains("expires in 1 year, 1 month, 1 day (2022-03-01T08:00:00+08:00)"),
+            "{0}",
+            line
+        );
+
+        result.state = CheckState::Expired;
+        let line = result.line_styled(&style);
+        assert!(
+            line.contains("has expired 1 year, 1 month, 1 day ago"),
+            "{0}",
+            line
+        );
+    }
+
+    #[test]
+    fn test_human_duration() {
+        let ts = |y, m, d| Utc.ymd(y, m, d).and_hms(0, 0, 0).timestamp();
+        assert_eq!("3 days", human_duration(ts(2021, 1, 1), ts(2021, 1, 4)));
+        assert_eq!(
+            "1 year, 2 months, 3 days",
+            human_duration(ts(2021, 1, 1), ts(2022, 3, 4))
+        );
+        assert_eq!(
+            "11 months, 30 days",
+            human_duration(ts(2021, 1, 5), ts(2022, 1, 4))
+        );
+        assert_eq!("2 years", human_duration(ts(2022, 1, 1), ts(2020, 1, 1)));
+        assert_eq!(
+            "less than a day",
+            human_duration(ts(2021, 1, 1), ts(2021, 1, 1))
+        );
+    }
+
+    #[test]
+    fn test_json_id() {
+        let mut result = build_result();
+        result.id = Uuid::new_v4();
+        let json = CheckResultJSON::new(&result);
+        assert_eq!(result.id.to_string(), json.id);
+    }
+
+    #[test]
+    fn test_json_error() {
+        let result = build_result();
+        let json = serde_json::to_value(CheckResultJSON::new(&result)).unwrap();
+        assert!(json.get("error").is_none());
+
+        let error = CheckError::ConnectTimeout {
+            target: "example.com".into(),
+        };
+        let result = CheckResult::failed(Uuid::new_v4(), "example.com", &Utc::now(), error);
+        let json = serde_json::to_value(CheckResultJSON::new(&result)).unwrap();
+        assert_eq!("connect_timeout", json["error"]["kind"]);
+        assert_eq!("Unknown", json["state"]);
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema: serde_json::Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        assert_eq!(SCHEMA_VERSION, schema["x-schema-version"]);
+        let properties = &schema["$defs"]["result"]["properties"];
+        assert_eq!(SCHEMA_VERSION, properties["schema_version"]["const"]);
+
+        // every field of result with everything included is documented
+        let result = CheckResult {
+            certificates: vec![vec![0], vec![1]],
+            detail: Some(Default::default()),
+            error: Some(CheckError::ConnectTimeout {
+                target: "example.com".into(),
+            }),
+            findings: vec!["finding".into()],
+            tags: vec!["tag".into()],
+            trust: vec![Default::default()],
+            chain_not_after: Some(0),
+            http: Some(Default::default()),
+            fingerprint: Some("AB:CD".into()),
+            shared_with: vec!["www.example.com".into()],
+            ..build_result()
+        };
+        let json = serde_json::to_value(CheckResultJSON::new(&result)).unwrap();
+        for field in json.as_object().unwrap().keys() {
+            assert!(
+                properties.get(field).is_some(),
+                "{0} is undocumented",
+                field
+            );
+        }
+        let detail = &schema["$defs"]["detail"]["properties"];
+        for field in json["detail"].as_object().unwrap().keys() {
+            assert!(detail.get(field).is_some(), "{0} is undocumented", field);
+        }
+    }
+
+    #[test]
+    fn test_json_certificates() {
+        let mut result = build_result();
+        let json = CheckResultJSON::new(&result);
+        assert!(json.certificate.is_none());
+
+        result.certificates = vec![vec![0; 100], vec![1; 10]];
+        let json = CheckResultJSON::new(&result);
+        let certificate = json.certificate.unwrap();
+        assert!(certificate.starts_with("-----BEGIN CERTIFICATE-----\nAAAA"));
+        assert!(certificate.ends_with("\n-----END CERTIFICATE-----\n"));
+        assert_eq!(5, certificate.lines().count());
+        assert_eq!(1, json.chain.len());
+    }
+
+    #[test]
+    fn test_summary() {
+        let results: Vec<CheckResult> = [
+            CheckState::Ok,
+            CheckState::Warning,
+            CheckState::Expired,
+            CheckState::Ok,
+        ]
+        .iter()
+        .map(|state| CheckResult {
+            state: state.clone(),
+            ..Default::default()
+        })
+        .collect();
+        let summary = Summary::new(&results, 30);
+        assert_eq!(
+            "4 checked, 1 expiring within 30 days, 1 expired",
+            summary.to_string()
+        );
+
+        let summary = Summary::new(&[CheckResult::default()], 30);
+        assert_eq!(
+            "1 checked, 0 expiring within 30 days, 0 expired, 1 unknown",
+            summary.to_string()
+        );
+    }
+
+    #[test]
+    fn test_expect_sans() {
+        let mut result = build_result();
+        result.state = CheckState::Ok;
+        result.sans = vec!["example.com".into(), "old.example.com".into()];
+        result.expect_sans(&["EXAMPLE.com".into()]);
+        assert!(matches!(result.state, CheckState::Ok));
+        assert_eq!(vec!["unexpected SAN old.example.com"], result.findings);
+
+        let mut result = build_result();
+        result.state = CheckState::Ok;
+        result.sans = vec!["example.com".into()];
+        result.expect_sans(&["example.com".into(), "www.example.com".into()]);
+        assert!(matches!(result.state, CheckState::Warning));
+        let left = format!("{0}", result);
+        assert!(left.ends_with("; missing SAN www.example.com"));
+    }
+
+    #[test]
+    fn test_require_sans() {
+        let mut result = build_result();
+        result.state = CheckState::Ok;
+        result.sans = vec!["example.com".into(), "*.example.com".into()];
+        result.require_sans(&["EXAMPLE.com".into(), "api.example.com".into()]);
+        assert!(matches!(result.state, CheckState::Ok));
+        assert!(result.findings.is_empty());
+
+        result.require_sans(&["a.b.example.com".into(), "example.org".into()]);
+        assert!(matches!(result.state, CheckState::Warning));
+        assert_eq!(
+            vec![
+                "SAN a.b.example.com is not covered",
+                "SAN example.org is not covered"
+            ],
+            result.findings
+        );
+    }
+
+    #[test]
+    fn test_group_by_certificate() {
+        let build = |domain_name, state, fingerprint: Option<&str>, finding: &str| CheckResult {
+            domain_name,
+            state,
+            fingerprint: fingerprint.map(String::from),
+            findings: vec![finding.to_string()],
+            ..build_result()
+        };
+        let results = vec![
+            build("a.example.com", CheckState::Ok, Some("AA"), "x"),
+            build(
+                "b.example.com",
+                CheckState::Warning,
+                Some("AA"),
+                "missing SAN b",
+            ),
+            build("c.example.com", CheckState::Ok, Some("AA"), "x"),
+            build("d.example.com", CheckState::Unknown, None, "y"),
+            build("e.example.com", CheckState::Unknown, None, "y"),
+            build("f.example.com", CheckState::Ok, Some("BB"), "z"),
+        ];
+        let grouped = group_by_certificate(results);
+        let domain_names: Vec<&str> = grouped.iter().map(|r| r.domain_name).collect();
+        assert_eq!(
+            vec![
+                "b.example.com",
+                "d.example.com",
+                "e.example.com",
+                "f.example.com"
+            ],
+            domain_names
+        );
+        assert!(matches!(grouped[0].state, CheckState::Warning));
+        assert_eq!(
+            vec!["a.example.com", "c.example.com"],
+            grouped[0].shared_with
+        );
+        assert_eq!(vec!["missing SAN b", "x"], grouped[0].findings);
+        assert!(grouped[3].shared_with.is_empty());
+    }
 }
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+
+/// Range of IP addresses in CIDR notation e.g. 10.0.0.0/24 or 2001:db8::/120
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Number of addresses in range
+    ///
+    /// ```
+    /// # use hcc::Cidr;
+    /// let cidr: Cidr = "10.0.0.0/24".parse().unwrap();
+    /// assert_eq!(256, cidr.size());
+    /// ```
+    pub fn size(&self) -> u128 {
+        let host_bits = u32::from(Self::bits(&self.network) - self.prefix);
+        1u128.checked_shl(host_bits).unwrap_or(u128::MAX)
+    }
+
+    /// Addresses in range, network and broadcast addresses included
+    pub fn addresses(&self) -> impl Iterator<Item = IpAddr> {
+        let network = self.network;
+        let start = Self::to_u128(&network);
+        (0..self.size()).map(move |i| match network {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from((start + i) as u32)),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(start + i)),
+        })
+    }
+
+    fn bits(addr: &IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    fn to_u128(addr: &IpAddr) -> u128 {
+        match addr {
+            IpAddr::V4(addr) => u128::from(u32::from(*addr)),
+            IpAddr::V6(addr) => u128::from(*addr),
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = anyhow::Error;
+
+    /// Parse address with prefix length e.g. 10.0.0.0/24, or single address, host bits of
+    /// address are cleared
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .with_context(|| format!("invalid address in {0}", s))?;
+        let bits = Self::bits(&addr);
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .with_context(|| format!("invalid prefix length in {0}", s))?,
+            None => bits,
+        };
+        if prefix > bits {
+            bail!("prefix length of {0} exceeds {1}", s, bits);
+        }
+        let host_bits = u32::from(bits - prefix);
+        let mask = u128::MAX.checked_shl(host_bits).unwrap_or(0);
+        let start = Self::to_u128(&addr) & mask;
+        let network = match addr {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(start as u32)),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(start)),
+        };
+        Ok(Cidr { network, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{0}/{1}", self.network, self.prefix)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::IpAddr;
+
+    use crate::cidr::Cidr;
+
+    #[test]
+    fn test_parse() {
+        let cidr: Cidr = "10.0.0.7/30".parse().unwrap();
+        assert_eq!("10.0.0.4/30", cidr.to_string());
+        assert_eq!(4, cidr.size());
+        let addresses: Vec<IpAddr> = cidr.addresses().collect();
+        assert_eq!("10.0.0.4".parse::<IpAddr>().unwrap(), addresses[0]);
+        assert_eq!("10.0.0.7".parse::<IpAddr>().unwrap(), addresses[3]);
+
+        let cidr: Cidr = "192.168.1.1".parse().unwrap();
+        assert_eq!(1, cidr.size());
+
+        let cidr: Cidr = "2001:db8::/126".parse().unwrap();
+        assert_eq!(
+            vec!["2001:db8::", "2001:db8::1", "2001:db8::2", "2001:db8::3"],
+            cidr.addresses().map(|a| a.to_string()).collect::<Vec<_>>()
+        );
+        assert_eq!(u128::MAX, "::/0".parse::<Cidr>().unwrap().size());
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("10.0.0/24".parse::<Cidr>().is_err());
+        assert!("example.com/24".parse::<Cidr>().is_err());
+    }
+}
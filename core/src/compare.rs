@@ -0,0 +1,224 @@
+use std::fmt;
+use std::fs;
+
+use anyhow::Context;
+use chrono::{TimeZone, Utc};
+use ring::digest::{digest, SHA256};
+use serde::Serialize;
+use x509_parser::parse_x509_certificate;
+
+use crate::check_client::{parse_certificates, CheckClient};
+
+/// Leaf certificate presented by an endpoint or read from a file
+#[derive(Debug, Default, Serialize)]
+pub struct CertificateSummary {
+    /// Domain name or path where certificate comes from
+    pub source: String,
+    /// SHA-256 fingerprint of certificate in DER format, bytes separated by colon
+    pub fingerprint: String,
+    /// Distinguished name of subject
+    pub subject: String,
+    /// Distinguished name of issuer
+    pub issuer: String,
+    /// Serial number, bytes separated by colon
+    pub serial: String,
+    /// Subject alternative names i.e. DNS names in certificate
+    pub sans: Vec<String>,
+    /// Start of validity in RFC3339 format
+    pub not_before: String,
+    /// End of validity in RFC3339 format
+    pub not_after: String,
+}
+
+impl CertificateSummary {
+    /// Summarize certificate in DER format
+    pub fn parse(source: &str, der: &[u8]) -> anyhow::Result<Self> {
+        let (_, cert) = parse_x509_certificate(der)
+            .map_err(|e| anyhow::anyhow!("failed to parse certificate of {0}: {1}", source, e))?;
+        let validity = cert.validity();
+        let rfc3339 = |secs| Utc.timestamp(secs, 0).to_rfc3339();
+        Ok(CertificateSummary {
+            source: source.to_string(),
+            fingerprint: hex(digest(&SHA256, der).as_ref()),
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            serial: cert.tbs_certificate.raw_serial_as_string(),
+            sans: CheckClient::sans_of(&cert),
+            not_before: rfc3339(validity.not_before.timestamp()),
+            not_after: rfc3339(validity.not_after.timestamp()),
+        })
+    }
+
+    /// Fields compared and their values
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("fingerprint", self.fingerprint.clone()),
+            ("subject", self.subject.clone()),
+            ("issuer", self.issuer.clone()),
+            ("serial", self.serial.clone()),
+            ("SANs", self.sans.join(", ")),
+            ("not before", self.not_before.clone()),
+            ("not after", self.not_after.clone()),
+        ]
+    }
+}
+
+/// Field of certificates with different values
+#[derive(Debug, Serialize)]
+pub struct Difference {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+/// Comparison of certificates presented by two endpoints, or an endpoint and a file
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub left: CertificateSummary,
+    pub right: CertificateSummary,
+    /// Fields with different values, empty when certificates are identical
+    pub differences: Vec<Difference>,
+}
+
+impl Comparison {
+    /// Compare fields of two certificates
+    pub fn new(left: CertificateSummary, right: CertificateSummary) -> Self {
+        let differences = left
+            .fields()
+            .into_iter()
+            .zip(right.fields())
+            .filter(|((_, l), (_, r))| l != r)
+            .map(|((field, left), (_, right))| Difference { field, left, right })
+            .collect();
+        Comparison {
+            left,
+            right,
+            differences,
+        }
+    }
+
+    /// Whether both sides are the same certificate
+    pub fn is_same(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.is_same() {
+            "same certificate"
+        } else {
+            "different certificates"
+        };
+        write!(
+            f,
+            "{0} vs {1}: {2}",
+            self.left.source, self.right.source, verdict
+        )?;
+        for (field, value) in self.left.fields() {
+            match self.differences.iter().find(|d| d.field == field) {
+                Some(d) => write!(f, "\n  ! {0}: {1} | {2}", field, d.left, d.right)?,
+                None => write!(f, "\n  = {0}: {1}", field, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CheckClient {
+    /// Leaf certificate presented by domain name e.g. example.com:8443 or
+    /// https://example.com, or the first certificate of a PEM or DER file given as file:PATH
+    ///
+    /// Files need the prefix, so a file named after a host never shadows the host.
+    pub fn fetch_certificate(&self, source: &str) -> anyhow::Result<CertificateSummary> {
+        if let Some(path) = source.strip_prefix("file:") {
+            let body = fs::read(path).with_context(|| format!("failed to read {0}", path))?;
+            let certificates = parse_certificates(&body)?;
+            let der = certificates
+                .first()
+                .with_context(|| format!("no certificate found in {0}", path))?;
+            return CertificateSummary::parse(source, der);
+        }
+        let domain_name = source.strip_prefix("https://").unwrap_or(source);
+        let domain_name = domain_name.trim_end_matches('/');
+        let target = self.target_of(domain_name)?;
+        let sni = self.sni_of(&target)?;
+        let mut sock = self.connect(&target)?;
+        let handshake = Self::handshake(&mut sock, &target, sni, &self.config)?;
+        let certificate = handshake
+            .certificates
+            .first()
+            .with_context(|| format!("no certificate found for {0}", source))?;
+        CertificateSummary::parse(source, certificate.as_ref())
+    }
+
+    /// Compare certificates presented by two domain names, or a domain name and a file given as
+    /// file:PATH
+    ///
+    /// ```
+    /// # use hcc::CheckClient;
+    /// let client = CheckClient::new();
+    /// client.compare("sha256.badssl.com", "sha512.badssl.com");
+    /// ```
+    pub fn compare(&self, left: &str, right: &str) -> anyhow::Result<Comparison> {
+        Ok(Comparison::new(
+            self.fetch_certificate(left)?,
+            self.fetch_certificate(right)?,
+        ))
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{0:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::check_client::CheckClient;
+    use crate::compare::Comparison;
+
+    fn fixture(name: &str) -> String {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "fixtures", name]
+            .iter()
+            .collect();
+        format!("file:{0}", path.to_string_lossy())
+    }
+
+    #[test]
+    fn test_fetch_certificate() {
+        let client = CheckClient::new();
+        let summary = client.fetch_certificate(&fixture("localhost.pem")).unwrap();
+        assert_eq!(95, summary.fingerprint.len());
+        assert_eq!("CN=hcc Test CA", summary.issuer);
+        assert_eq!(vec!["localhost", "www.localhost"], summary.sans);
+
+        // without prefix, source is a host even if a file has the same name
+        let path = fixture("localhost.pem");
+        assert!(client.fetch_certificate(&path["file:".len()..]).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let client = CheckClient::new();
+        let comparison = client
+            .compare(&fixture("localhost.pem"), &fixture("localhost.pem"))
+            .unwrap();
+        assert!(comparison.is_same());
+
+        let left = client.fetch_certificate(&fixture("localhost.pem")).unwrap();
+        let right = client.fetch_certificate(&fixture("ca.pem")).unwrap();
+        let comparison = Comparison::new(left, right);
+        assert!(!comparison.is_same());
+        let fields: Vec<&str> = comparison.differences.iter().map(|d| d.field).collect();
+        assert!(fields.contains(&"fingerprint"));
+        assert!(fields.contains(&"subject"));
+        assert!(!fields.contains(&"issuer"));
+        assert!(comparison.to_string().contains("different certificates"));
+    }
+}
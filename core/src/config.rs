@@ -0,0 +1,451 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::hook::Hook;
+use crate::plugin::Plugin;
+use crate::policy::Policy;
+use crate::target::{has_port, Target};
+
+/// Configuration file in TOML format
+///
+/// ```toml
+/// grace_in_days = 14
+/// notifiers = ["teams"]
+///
+/// [schedules]
+/// internal = "1d"
+///
+/// [[domains]]
+/// name = "example.com"
+/// ports = [443, 8443]
+/// expected_sans = ["example.com", "www.example.com"]
+/// required_sans = ["api.example.com"]
+/// tags = ["prod", "eu"]
+/// schedule = "0 0 * * * * *"
+///
+/// [ct]
+/// domains = ["example.com"]
+/// expected_issuers = ["Let's Encrypt"]
+///
+/// [[hooks]]
+/// command = ["certbot", "renew", "--cert-name", "{domain}"]
+/// days = 30
+///
+/// [[plugins]]
+/// name = "teams"
+/// kind = "notifier"
+/// command = ["/usr/local/bin/notify-teams"]
+///
+/// [policy]
+/// allowed_issuers = ["Let's Encrypt"]
+/// max_validity_days = 398
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Grace period in days, overridden by command line option or environment variable
+    pub grace_in_days: Option<i64>,
+    /// Host names certificates of all domain names must cover, checked as required_sans of
+    /// policy
+    #[serde(default)]
+    pub required_sans: Vec<String>,
+    /// Schedules of daemon by tag, cron expression or interval e.g. 1h
+    #[serde(default)]
+    pub schedules: BTreeMap<String, String>,
+    /// Domain names to check
+    #[serde(default)]
+    pub domains: Vec<DomainConfig>,
+    /// Certificate Transparency monitoring of daemon
+    pub ct: Option<CtConfig>,
+    /// Hooks fired when certificate drops below threshold e.g. to renew it
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// External formatters and notifiers, referred to by name
+    #[serde(default)]
+    pub plugins: Vec<Plugin>,
+    /// Names of notifiers results are delivered to after each check
+    #[serde(default)]
+    pub notifiers: Vec<String>,
+    /// Rules every checked certificate must follow
+    pub policy: Option<Policy>,
+}
+
+/// Certificate Transparency monitoring, certificates newly logged for owned domains are
+/// alerted unless issued by an expected issuer
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CtConfig {
+    /// Owned domains, certificates of their subdomains count
+    pub domains: Vec<String>,
+    /// Issuers expected to issue certificates, matched against issuer name e.g. Let's Encrypt,
+    /// certificates of any issuer are alerted if empty
+    #[serde(default)]
+    pub expected_issuers: Vec<String>,
+    /// Schedule of polling, cron expression or interval e.g. 1h, schedule of daemon by default
+    pub schedule: Option<String>,
+}
+
+/// Configuration of one domain name
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DomainConfig {
+    /// Domain name, optionally with port e.g. example.com:8443
+    pub name: String,
+    /// Ports to check domain name on, one result per port, ignored when name carries port
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// Subject alternative names certificate must include exactly
+    pub expected_sans: Option<Vec<String>>,
+    /// Host names certificate must cover, wildcard SANs count, other SANs are allowed
+    #[serde(default)]
+    pub required_sans: Vec<String>,
+    /// Labels to slice domain names by e.g. team or environment
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Schedule of daemon, cron expression or interval e.g. 1h, overrides schedules of tags
+    pub schedule: Option<String>,
+}
+
+impl Config {
+    /// Load configuration from TOML file
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path).with_context(|| format!("failed to read {0:?}", path))?;
+        s.parse()
+    }
+
+    /// Configuration of domain name, domain name with port falls back to configuration of its
+    /// host without port
+    ///
+    /// ```
+    /// # use hcc::Config;
+    /// let config: Config = "[[domains]]\nname = \"example.com\"".parse().unwrap();
+    /// assert!(config.domain("example.com").is_some());
+    /// assert!(config.domain("example.com:8443").is_some());
+    /// ```
+    pub fn domain(&self, name: &str) -> Option<&DomainConfig> {
+        self.domains.iter().find(|d| d.name == name).or_else(|| {
+            let host = name.parse::<Target>().ok()?.host;
+            self.domains.iter().find(|d| {
+                !has_port(&d.name) && d.name.parse::<Target>().is_ok_and(|t| t.host == host)
+            })
+        })
+    }
+
+    /// Domain names in configuration
+    pub fn domain_names(&self) -> Vec<&str> {
+        self.domains.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    /// Domain names in configuration tagged with all given tags, all domain names if no tag is
+    /// given
+    ///
+    /// ```
+    /// # use hcc::Config;
+    /// let config: Config = "[[domains]]\nname = \"example.com\"\ntags = [\"prod\"]"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(vec!["example.com"], config.domain_names_tagged(&["prod".into()]));
+    /// assert!(config.domain_names_tagged(&["staging".into()]).is_empty());
+    /// ```
+    pub fn domain_names_tagged(&self, tags: &[String]) -> Vec<&str> {
+        self.domains
+            .iter()
+            .filter(|d| tags.iter().all(|t| d.tags.contains(t)))
+            .map(|d| d.name.as_str())
+            .collect()
+    }
+
+    /// Domain names given, or in configuration if none is given, tagged with all given tags
+    ///
+    /// ```
+    /// # use hcc::Config;
+    /// let config: Config = "[[domains]]\nname = \"example.com\"\ntags = [\"prod\"]"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(vec!["example.com"], config.select(&[], &["prod".into()]));
+    /// assert!(config.select(&["example.org"], &["prod".into()]).is_empty());
+    /// ```
+    pub fn select<'a>(&'a self, domain_names: &[&'a str], tags: &[String]) -> Vec<&'a str> {
+        if domain_names.is_empty() {
+            return self.domain_names_tagged(tags);
+        }
+        domain_names
+            .iter()
+            .filter(|name| tags.iter().all(|t| self.tags_of(name).contains(t)))
+            .copied()
+            .collect()
+    }
+
+    /// Domain names with one entry per port, ports in configuration of domain name take
+    /// precedence over given ports, domain names carrying port are kept as is
+    ///
+    /// ```
+    /// # use hcc::Config;
+    /// let config = Config::default();
+    /// assert_eq!(
+    ///     vec!["example.com", "example.com:8443", "example.org:9443"],
+    ///     config.expand_ports(&["example.com", "example.org:9443"], &[443, 8443])
+    /// );
+    /// ```
+    pub fn expand_ports(&self, domain_names: &[&str], ports: &[u16]) -> Vec<String> {
+        let mut expanded = vec![];
+        for name in domain_names {
+            let ports = match self.domain(name) {
+                Some(d) if !d.ports.is_empty() => d.ports.as_slice(),
+                _ => ports,
+            };
+            match name.parse::<Target>() {
+                Ok(target) if !ports.is_empty() && !has_port(name) => {
+                    expanded.extend(ports.iter().map(|&port| {
+                        Target {
+                            port,
+                            ..target.clone()
+                        }
+                        .to_string()
+                    }));
+                }
+                // invalid domain names are kept to be reported by check
+                _ => expanded.push(name.to_string()),
+            }
+        }
+        expanded
+    }
+
+    /// Schedule of domain name in daemon, its own schedule, or schedule of its first tag with
+    /// one, none if daemon should check it on default schedule
+    ///
+    /// ```
+    /// # use hcc::Config;
+    /// let config: Config = r#"
+    ///     schedules = { prod = "1h" }
+    ///     [[domains]]
+    ///     name = "example.com"
+    ///     tags = ["prod"]
+    /// "#
+    /// .parse()
+    /// .unwrap();
+    /// assert_eq!(Some("1h"), config.schedule_of("example.com"));
+    /// assert_eq!(None, config.schedule_of("example.org"));
+    /// ```
+    pub fn schedule_of(&self, name: &str) -> Option<&str> {
+        let domain = self.domain(name)?;
+        domain.schedule.as_deref().or_else(|| {
+            domain
+                .tags
+                .iter()
+                .find_map(|t| self.schedules.get(t).map(String::as_str))
+        })
+    }
+
+    /// Tags of domain name, empty if domain name is not configured
+    pub fn tags_of(&self, name: &str) -> &[String] {
+        self.domain(name).map_or(&[], |d| d.tags.as_slice())
+    }
+
+    /// Policy certificates are checked against, with required_sans at top level folded into
+    /// required_sans of policy, none without either
+    ///
+    /// ```
+    /// # use hcc::Config;
+    /// let config: Config = "required_sans = [\"api.example.com\"]".parse().unwrap();
+    /// let policy = config.effective_policy().unwrap();
+    /// assert_eq!(vec!["api.example.com"], policy.required_sans);
+    /// ```
+    pub fn effective_policy(&self) -> Option<Policy> {
+        if self.required_sans.is_empty() {
+            return self.policy.clone();
+        }
+        let mut policy = self.policy.clone().unwrap_or_default();
+        for name in &self.required_sans {
+            if !policy.required_sans.contains(name) {
+                policy.required_sans.push(name.clone());
+            }
+        }
+        Some(policy)
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s).context("failed to parse configuration")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::Config;
+    use crate::plugin::PluginKind;
+
+    #[test]
+    fn test_parse() {
+        let config: Config = r#"
+            grace_in_days = 14
+            notifiers = ["teams"]
+
+            [[domains]]
+            name = "example.com"
+            expected_sans = ["example.com", "www.example.com"]
+            tags = ["prod", "eu"]
+
+            [[domains]]
+            name = "example.org:8443"
+            tags = ["prod"]
+
+            [[domains]]
+            name = "appliance.example.net"
+            ports = [443, 8443]
+
+            [ct]
+            domains = ["example.com"]
+
+            [[hooks]]
+            command = ["certbot", "renew", "--cert-name", "{domain}"]
+            days = 30
+
+            [[plugins]]
+            name = "teams"
+            kind = "notifier"
+            command = ["notify-teams"]
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(Some(14), config.grace_in_days);
+        assert_eq!(vec!["teams"], config.notifiers);
+        assert_eq!(PluginKind::Notifier, config.plugins[0].kind);
+        assert_eq!(Some(30), config.hooks[0].days);
+        assert_eq!(4, config.hooks[0].command.len());
+        let ct = config.ct.as_ref().unwrap();
+        assert_eq!(vec!["example.com"], ct.domains);
+        assert!(ct.expected_issuers.is_empty());
+        assert_eq!(
+            vec!["example.com", "example.org:8443", "appliance.example.net"],
+            config.domain_names()
+        );
+
+        let domain = config.domain("example.com").unwrap();
+        assert_eq!(2, domain.expected_sans.as_ref().unwrap().len());
+        assert!(config
+            .domain("example.org:8443")
+            .unwrap()
+            .expected_sans
+            .is_none());
+        assert!(config.domain("example.net").is_none());
+        assert_eq!(
+            "example.com",
+            config.domain("example.com:8443").unwrap().name
+        );
+        assert!(config.domain("example.org").is_none());
+
+        assert_eq!(vec!["prod", "eu"], config.tags_of("example.com"));
+        assert!(config.tags_of("example.net").is_empty());
+        assert_eq!(
+            vec!["example.com", "example.org:8443"],
+            config.domain_names_tagged(&["prod".into()])
+        );
+        assert_eq!(
+            vec!["example.com"],
+            config.domain_names_tagged(&["prod".into(), "eu".into()])
+        );
+        assert_eq!(
+            vec!["example.org:8443", "example.net"],
+            config.select(&["example.org:8443", "example.net"], &[])
+        );
+        assert_eq!(
+            vec!["example.org:8443"],
+            config.select(&["example.org:8443", "example.net"], &["prod".into()])
+        );
+    }
+
+    #[test]
+    fn test_expand_ports() {
+        let config: Config = "[[domains]]\nname = \"appliance.example.net\"\nports = [443, 8443]"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            vec!["appliance.example.net", "appliance.example.net:8443"],
+            config.expand_ports(&["appliance.example.net"], &[])
+        );
+        assert_eq!(
+            vec!["appliance.example.net", "appliance.example.net:8443"],
+            config.expand_ports(&["appliance.example.net"], &[9443])
+        );
+        assert_eq!(
+            vec!["example.com:9443", "[2001:db8::1]:9443"],
+            config.expand_ports(&["example.com", "2001:db8::1"], &[9443])
+        );
+        assert_eq!(
+            vec!["example.com:8443"],
+            config.expand_ports(&["example.com:8443"], &[9443])
+        );
+        assert_eq!(
+            vec!["example.com"],
+            config.expand_ports(&["example.com"], &[])
+        );
+    }
+
+    #[test]
+    fn test_schedule_of() {
+        let config: Config = r#"
+            [schedules]
+            internal = "1d"
+            prod = "1h"
+
+            [[domains]]
+            name = "edge.example.com"
+            tags = ["prod"]
+            schedule = "0 */10 * * * * *"
+
+            [[domains]]
+            name = "api.example.com"
+            tags = ["eu", "prod"]
+
+            [[domains]]
+            name = "wiki.example.com"
+            tags = ["internal"]
+
+            [[domains]]
+            name = "example.com"
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            Some("0 */10 * * * * *"),
+            config.schedule_of("edge.example.com")
+        );
+        assert_eq!(Some("1h"), config.schedule_of("api.example.com"));
+        assert_eq!(Some("1h"), config.schedule_of("api.example.com:8443"));
+        assert_eq!(Some("1d"), config.schedule_of("wiki.example.com"));
+        assert_eq!(None, config.schedule_of("example.com"));
+    }
+
+    #[test]
+    fn test_effective_policy() {
+        assert_eq!(None, Config::default().effective_policy());
+
+        let config: Config = r#"
+            required_sans = ["example.com", "api.example.com"]
+
+            [policy]
+            required_sans = ["example.com"]
+            max_validity_days = 398
+        "#
+        .parse()
+        .unwrap();
+        let policy = config.effective_policy().unwrap();
+        assert_eq!(vec!["example.com", "api.example.com"], policy.required_sans);
+        assert_eq!(Some(398), policy.max_validity_days);
+    }
+
+    #[test]
+    fn test_parse_unknown_field() {
+        let config = "[[domains]]\nname = \"example.com\"\nexpected_san = []".parse::<Config>();
+        assert!(config.is_err());
+    }
+}
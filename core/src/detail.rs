@@ -0,0 +1,352 @@
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::der_parser::oid::Oid;
+use x509_parser::der_parser::parse_der;
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+use x509_parser::objects::{oid2abbrev, oid2sn};
+use x509_parser::x509::X509Name;
+
+use crate::check_client::CheckClient;
+
+const OID_RSA_ENCRYPTION: &str = "1.2.840.113549.1.1.1";
+const OID_EC_PUBLIC_KEY: &str = "1.2.840.10045.2.1";
+const OID_ED25519: &str = "1.3.101.112";
+const OID_ED448: &str = "1.3.101.113";
+const OID_OCSP: &str = "1.3.6.1.5.5.7.48.1";
+const OID_CA_ISSUERS: &str = "1.3.6.1.5.5.7.48.2";
+const OID_TLS_FEATURE: &str = "1.3.6.1.5.5.7.1.24";
+const OID_SCT_LIST: &str = "1.3.6.1.4.1.11129.2.4.2";
+
+/// Attribute of distinguished name e.g. CN=example.com
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NameComponent {
+    /// Abbreviation or short name of attribute type e.g. CN, O or C
+    pub name: String,
+    pub value: String,
+}
+
+/// Public key of subject
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PublicKey {
+    /// Key type e.g. RSA, EC, Ed25519
+    #[serde(rename = "type")]
+    pub key_type: String,
+    /// Size in bits e.g. 2048 for RSA or 256 for P-256
+    pub size: Option<usize>,
+    /// Named curve of EC key e.g. P-256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<String>,
+}
+
+/// Extensions worth attention when debugging deployment
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Extensions {
+    /// Whether certificate belongs to a CA, from basic constraints
+    pub ca: bool,
+    /// Maximum number of intermediate certificates below CA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_len: Option<u32>,
+    /// Key usage in RFC 5280 names e.g. digitalSignature
+    pub key_usage: Vec<String>,
+    /// Extended key usage in RFC 5280 names e.g. serverAuth, or OID when unknown
+    pub extended_key_usage: Vec<String>,
+    /// URLs of OCSP responders
+    pub ocsp: Vec<String>,
+    /// URLs of certificates of issuer
+    pub ca_issuers: Vec<String>,
+    /// Whether certificate requires stapled OCSP response i.e. TLS feature status_request
+    pub must_staple: bool,
+    /// Whether signed certificate timestamps of Certificate Transparency are embedded
+    pub embedded_scts: bool,
+    /// Short names or OIDs of extensions marked critical
+    pub critical: Vec<String>,
+}
+
+/// Full detail of a certificate
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CertificateDetail {
+    /// X.509 version e.g. 3
+    pub version: u32,
+    /// Serial number, bytes separated by colon
+    pub serial: String,
+    pub subject: Vec<NameComponent>,
+    pub issuer: Vec<NameComponent>,
+    /// Start of validity in RFC3339 format
+    pub not_before: String,
+    /// End of validity in RFC3339 format
+    pub not_after: String,
+    /// Subject alternative names i.e. DNS names in certificate
+    pub sans: Vec<String>,
+    pub public_key: PublicKey,
+    /// Short name of signature algorithm e.g. sha256WithRSAEncryption
+    pub signature_algorithm: String,
+    pub extensions: Extensions,
+}
+
+impl CertificateDetail {
+    /// Detail of parsed certificate
+    pub fn new(cert: &X509Certificate) -> Self {
+        let validity = cert.validity();
+        let rfc3339 = |secs| Utc.timestamp(secs, 0).to_rfc3339();
+        CertificateDetail {
+            version: cert.version().0 + 1,
+            serial: cert.tbs_certificate.raw_serial_as_string(),
+            subject: name_components(cert.subject()),
+            issuer: name_components(cert.issuer()),
+            not_before: rfc3339(validity.not_before.timestamp()),
+            not_after: rfc3339(validity.not_after.timestamp()),
+            sans: CheckClient::sans_of(cert),
+            public_key: public_key(cert),
+            signature_algorithm: short_name(&cert.signature_algorithm.algorithm),
+            extensions: extensions(cert),
+        }
+    }
+}
+
+/// Problems of key usage and extended key usage of certificate served by TLS server, which
+/// clients reject without serverAuth purpose
+pub(crate) fn usage_findings(cert: &X509Certificate) -> Vec<String> {
+    let extensions = extensions(cert);
+    let eku = &extensions.extended_key_usage;
+    let mut findings = vec![];
+    // certificate without extended key usage is valid for any purpose
+    if !eku.is_empty()
+        && !eku
+            .iter()
+            .any(|p| p == "serverAuth" || p == "anyExtendedKeyUsage")
+    {
+        if eku.iter().all(|p| p == "clientAuth") {
+            findings.push("client authentication certificate served, serverAuth is missing in extended key usage".to_string());
+        } else {
+            findings.push(format!(
+                "serverAuth is missing in extended key usage {0}",
+                eku.join(", ")
+            ));
+        }
+    }
+    let ku = &extensions.key_usage;
+    if !ku.is_empty()
+        && !ku
+            .iter()
+            .any(|u| u == "digitalSignature" || u == "keyEncipherment" || u == "keyAgreement")
+    {
+        findings.push(format!(
+            "key usage {0} does not allow TLS handshake",
+            ku.join(", ")
+        ));
+    }
+    findings
+}
+
+fn short_name(oid: &Oid) -> String {
+    oid2sn(oid)
+        .map(String::from)
+        .unwrap_or_else(|_| oid.to_id_string())
+}
+
+fn name_components(name: &X509Name) -> Vec<NameComponent> {
+    name.iter_attributes()
+        .map(|attr| NameComponent {
+            name: oid2abbrev(&attr.attr_type)
+                .map(String::from)
+                .unwrap_or_else(|_| attr.attr_type.to_id_string()),
+            value: attr
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|_| "<binary>".to_string()),
+        })
+        .collect()
+}
+
+fn public_key(cert: &X509Certificate) -> PublicKey {
+    let spki = &cert.tbs_certificate.subject_pki;
+    let key = spki.subject_public_key.data;
+    match spki.algorithm.algorithm.to_id_string().as_str() {
+        OID_RSA_ENCRYPTION => {
+            // RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+            let size = parse_der(key).ok().and_then(|(_, seq)| {
+                let modulus = seq.as_sequence().ok()?.first()?.as_slice().ok()?;
+                let modulus: Vec<u8> = modulus.iter().skip_while(|b| **b == 0).copied().collect();
+                let leading = *modulus.first()?;
+                Some((modulus.len() - 1) * 8 + (8 - leading.leading_zeros() as usize))
+            });
+            PublicKey {
+                key_type: "RSA".to_string(),
+                size,
+                curve: None,
+            }
+        }
+        OID_EC_PUBLIC_KEY => {
+            let curve = spki
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|p| p.as_oid().ok())
+                .map(|oid| match oid.to_id_string().as_str() {
+                    "1.2.840.10045.3.1.7" => "P-256".to_string(),
+                    "1.3.132.0.34" => "P-384".to_string(),
+                    "1.3.132.0.35" => "P-521".to_string(),
+                    other => other.to_string(),
+                });
+            // uncompressed point is 0x04 followed by both coordinates
+            let size = match curve.as_deref() {
+                Some("P-521") => Some(521),
+                _ if key.first() == Some(&4) => Some((key.len() - 1) / 2 * 8),
+                _ => None,
+            };
+            PublicKey {
+                key_type: "EC".to_string(),
+                size,
+                curve,
+            }
+        }
+        OID_ED25519 => PublicKey {
+            key_type: "Ed25519".to_string(),
+            size: Some(256),
+            curve: None,
+        },
+        OID_ED448 => PublicKey {
+            key_type: "Ed448".to_string(),
+            size: Some(456),
+            curve: None,
+        },
+        _ => PublicKey {
+            key_type: short_name(&spki.algorithm.algorithm),
+            size: None,
+            curve: None,
+        },
+    }
+}
+
+pub(crate) fn extensions(cert: &X509Certificate) -> Extensions {
+    let mut extensions = Extensions::default();
+    let mut all: Vec<_> = cert.extensions().values().collect();
+    all.sort_by_key(|ext| ext.oid.to_id_string());
+    for ext in all {
+        let oid = ext.oid.to_id_string();
+        if ext.critical {
+            extensions.critical.push(short_name(&ext.oid));
+        }
+        match oid.as_str() {
+            OID_TLS_FEATURE => extensions.must_staple = true,
+            OID_SCT_LIST => extensions.embedded_scts = true,
+            _ => {}
+        }
+        match ext.parsed_extension() {
+            ParsedExtension::BasicConstraints(bc) => {
+                extensions.ca = bc.ca;
+                extensions.path_len = bc.path_len_constraint;
+            }
+            ParsedExtension::KeyUsage(ku) => {
+                let flags = [
+                    (ku.digital_signature(), "digitalSignature"),
+                    (ku.non_repudiation(), "nonRepudiation"),
+                    (ku.key_encipherment(), "keyEncipherment"),
+                    (ku.data_encipherment(), "dataEncipherment"),
+                    (ku.key_agreement(), "keyAgreement"),
+                    (ku.key_cert_sign(), "keyCertSign"),
+                    (ku.crl_sign(), "cRLSign"),
+                    (ku.encipher_only(), "encipherOnly"),
+                    (ku.decipher_only(), "decipherOnly"),
+                ];
+                extensions.key_usage = flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, name)| name.to_string())
+                    .collect();
+            }
+            ParsedExtension::ExtendedKeyUsage(eku) => {
+                let purposes = [
+                    (eku.any, "anyExtendedKeyUsage"),
+                    (eku.server_auth, "serverAuth"),
+                    (eku.client_auth, "clientAuth"),
+                    (eku.code_signing, "codeSigning"),
+                    (eku.email_protection, "emailProtection"),
+                    (eku.time_stamping, "timeStamping"),
+                    (eku.ocscp_signing, "OCSPSigning"),
+                ];
+                extensions.extended_key_usage = purposes
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, name)| name.to_string())
+                    .chain(eku.other.iter().map(|oid| oid.to_id_string()))
+                    .collect();
+            }
+            ParsedExtension::AuthorityInfoAccess(aia) => {
+                for (method, names) in &aia.accessdescs {
+                    let urls = names.iter().filter_map(|name| match name {
+                        GeneralName::URI(url) => Some(url.to_string()),
+                        _ => None,
+                    });
+                    match method.to_id_string().as_str() {
+                        OID_OCSP => extensions.ocsp.extend(urls),
+                        OID_CA_ISSUERS => extensions.ca_issuers.extend(urls),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    extensions
+}
+
+#[cfg(test)]
+mod test {
+    use x509_parser::parse_x509_certificate;
+
+    use crate::check_client::parse_certificates;
+    use crate::detail::{usage_findings, CertificateDetail};
+
+    fn parse(pem: &[u8]) -> Vec<u8> {
+        parse_certificates(pem).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_new() {
+        let der = parse(include_bytes!("../fixtures/localhost.pem"));
+        let (_, cert) = parse_x509_certificate(&der).unwrap();
+        let detail = CertificateDetail::new(&cert);
+        assert_eq!(3, detail.version);
+        assert_eq!("CN", detail.issuer[0].name);
+        assert_eq!("hcc Test CA", detail.issuer[0].value);
+        assert_eq!(vec!["localhost", "www.localhost"], detail.sans);
+        assert_eq!("EC", detail.public_key.key_type);
+        assert_eq!(Some(256), detail.public_key.size);
+        assert_eq!(Some("P-256".to_string()), detail.public_key.curve);
+        assert_eq!("ecdsa-with-SHA256", detail.signature_algorithm);
+        assert!(!detail.extensions.ca);
+        assert_eq!(vec!["digitalSignature"], detail.extensions.key_usage);
+        assert_eq!(vec!["serverAuth"], detail.extensions.extended_key_usage);
+        assert_eq!(vec!["keyUsage"], detail.extensions.critical);
+
+        let der = parse(include_bytes!("../fixtures/rsa.pem"));
+        let (_, cert) = parse_x509_certificate(&der).unwrap();
+        let detail = CertificateDetail::new(&cert);
+        assert_eq!(3, detail.subject.len());
+        assert_eq!("C", detail.subject[0].name);
+        assert_eq!("RSA", detail.public_key.key_type);
+        assert_eq!(Some(3072), detail.public_key.size);
+        assert_eq!("sha256WithRSAEncryption", detail.signature_algorithm);
+        assert!(detail.extensions.ca);
+    }
+
+    #[test]
+    fn test_usage_findings() {
+        let der = parse(include_bytes!("../fixtures/localhost.pem"));
+        let (_, cert) = parse_x509_certificate(&der).unwrap();
+        assert!(usage_findings(&cert).is_empty());
+
+        // self-signed certificate without extended key usage
+        let der = parse(include_bytes!("../fixtures/rsa.pem"));
+        let (_, cert) = parse_x509_certificate(&der).unwrap();
+        assert!(usage_findings(&cert).is_empty());
+
+        let der = parse(include_bytes!("../fixtures/client.pem"));
+        let (_, cert) = parse_x509_certificate(&der).unwrap();
+        let findings = usage_findings(&cert);
+        assert_eq!(1, findings.len());
+        assert!(findings[0].starts_with("client authentication certificate served"));
+    }
+}
@@ -0,0 +1,127 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Why a check could not complete, serialized with its kind e.g. `{"kind": "dns_failure", ...}`
+#[derive(Clone, Debug, Error, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CheckError {
+    /// Domain name is not a valid target e.g. port is out of range
+    #[error("invalid target {target}: {reason}")]
+    InvalidTarget { target: String, reason: String },
+    /// IP address is checked without server name to send in handshake
+    #[error("SNI is required to check IP address {target}")]
+    SniRequired { target: String },
+    /// Host could not be resolved to any address
+    #[error("failed to resolve {host}: {reason}")]
+    DnsFailure { host: String, reason: String },
+    /// No address of host accepted connection before timeout
+    #[error("timed out connecting to {target}")]
+    ConnectTimeout { target: String },
+    /// Every address of host refused or dropped connection
+    #[error("failed to connect to {target}: {reason}")]
+    ConnectFailed { target: String, reason: String },
+    /// Proxy refused to tunnel connection to target
+    #[error("failed to tunnel to {target} through proxy: {reason}")]
+    ProxyFailed { target: String, reason: String },
+    /// Server refused to upgrade plaintext connection to TLS
+    #[error("failed to start TLS with {target}: {reason}")]
+    StartTlsFailed { target: String, reason: String },
+    /// TLS handshake failed e.g. certificate is untrusted or expired
+    #[error("handshake with {target} failed: {reason}")]
+    HandshakeFailed { target: String, reason: String },
+    /// Server or file presented no certificate
+    #[error("no certificate found for {target}")]
+    NoCertificate { target: String },
+    /// Certificate is not valid PEM, DER or X.509
+    #[error("failed to parse certificate of {target}: {reason}")]
+    ParseError { target: String, reason: String },
+    /// Certificate could not be read from file, standard input or URL
+    #[error("failed to read {path}: {reason}")]
+    ReadFailed { path: String, reason: String },
+    /// Run was cancelled or ran out of time before target was checked
+    #[error("skipped {target}: {reason}")]
+    Skipped { target: String, reason: String },
+}
+
+impl CheckError {
+    /// Error connecting to target, timeouts are told apart from other failures
+    pub(crate) fn connect(target: &str, e: &io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => CheckError::ConnectTimeout {
+                target: target.to_string(),
+            },
+            _ => CheckError::ConnectFailed {
+                target: target.to_string(),
+                reason: e.to_string(),
+            },
+        }
+    }
+
+    /// Whether error is caused by input rather than target e.g. invalid domain name, so it's
+    /// reported to user instead of recorded as failed result
+    pub fn is_usage_error(&self) -> bool {
+        matches!(
+            self,
+            CheckError::InvalidTarget { .. } | CheckError::SniRequired { .. }
+        )
+    }
+
+    /// Short name of kind of error, same as `kind` in JSON
+    ///
+    /// ```
+    /// # use hcc::CheckError;
+    /// let e = CheckError::NoCertificate {
+    ///     target: "example.com".into(),
+    /// };
+    /// assert_eq!("no_certificate", e.kind());
+    /// ```
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CheckError::InvalidTarget { .. } => "invalid_target",
+            CheckError::SniRequired { .. } => "sni_required",
+            CheckError::DnsFailure { .. } => "dns_failure",
+            CheckError::ConnectTimeout { .. } => "connect_timeout",
+            CheckError::ConnectFailed { .. } => "connect_failed",
+            CheckError::ProxyFailed { .. } => "proxy_failed",
+            CheckError::StartTlsFailed { .. } => "start_tls_failed",
+            CheckError::HandshakeFailed { .. } => "handshake_failed",
+            CheckError::NoCertificate { .. } => "no_certificate",
+            CheckError::ParseError { .. } => "parse_error",
+            CheckError::ReadFailed { .. } => "read_failed",
+            CheckError::Skipped { .. } => "skipped",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use crate::error::CheckError;
+
+    #[test]
+    fn test_connect() {
+        let e = io::Error::new(io::ErrorKind::TimedOut, "timed out");
+        assert_eq!(
+            "connect_timeout",
+            CheckError::connect("example.com", &e).kind()
+        );
+        let e = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        let e = CheckError::connect("example.com", &e);
+        assert_eq!("failed to connect to example.com: refused", e.to_string());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let e = CheckError::DnsFailure {
+            host: "example.invalid".into(),
+            reason: "no address found".into(),
+        };
+        let json = serde_json::to_value(&e).unwrap();
+        assert_eq!(e.kind(), json["kind"]);
+        assert_eq!("example.invalid", json["host"]);
+        assert_eq!(e, serde_json::from_value(json).unwrap());
+    }
+}
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+
+use crate::check_result::CheckState;
+
+/// Condition which results in an exit code
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    Ok,
+    Warning,
+    Expired,
+    Unknown,
+    /// Check could not complete e.g. connection failure
+    Error,
+}
+
+/// Override of exit code of a condition e.g. `error=1`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExitCodeMapping {
+    pub condition: Condition,
+    pub code: i32,
+}
+
+impl FromStr for ExitCodeMapping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, code) = s
+            .split_once('=')
+            .with_context(|| format!("expect CONDITION=CODE e.g. error=1, got {0}", s))?;
+        let condition = match condition {
+            "ok" => Condition::Ok,
+            "warning" => Condition::Warning,
+            "expired" => Condition::Expired,
+            "unknown" => Condition::Unknown,
+            "error" => Condition::Error,
+            _ => bail!(
+                "unknown condition {0}, expect ok, warning, expired, unknown or error",
+                condition
+            ),
+        };
+        let code = code
+            .parse()
+            .with_context(|| format!("invalid exit code {0}", code))?;
+        Ok(ExitCodeMapping { condition, code })
+    }
+}
+
+/// Exit codes of conditions, following convention of Nagios plugins by default
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExitCodes {
+    pub ok: i32,
+    pub warning: i32,
+    pub expired: i32,
+    pub unknown: i32,
+    pub error: i32,
+}
+
+impl Default for ExitCodes {
+    fn default() -> Self {
+        ExitCodes {
+            ok: CheckState::Ok.exit_code(),
+            warning: CheckState::Warning.exit_code(),
+            expired: CheckState::Expired.exit_code(),
+            unknown: CheckState::Unknown.exit_code(),
+            error: 1,
+        }
+    }
+}
+
+impl ExitCodes {
+    /// Default exit codes overridden by mappings, latter mapping wins
+    ///
+    /// ```
+    /// # use hcc::{CheckState, ExitCodes};
+    /// let codes = ExitCodes::new(&["error=1".parse().unwrap()]);
+    /// assert_eq!(1, codes.error);
+    /// assert_eq!(2, codes.code_of(&CheckState::Expired));
+    /// ```
+    pub fn new(mappings: &[ExitCodeMapping]) -> Self {
+        let mut codes = ExitCodes::default();
+        for mapping in mappings {
+            let code = match mapping.condition {
+                Condition::Ok => &mut codes.ok,
+                Condition::Warning => &mut codes.warning,
+                Condition::Expired => &mut codes.expired,
+                Condition::Unknown => &mut codes.unknown,
+                Condition::Error => &mut codes.error,
+            };
+            *code = mapping.code;
+        }
+        codes
+    }
+
+    /// Exit code of state
+    pub fn code_of(&self, state: &CheckState) -> i32 {
+        match state {
+            CheckState::Ok => self.ok,
+            CheckState::Warning => self.warning,
+            CheckState::Expired => self.expired,
+            CheckState::Unknown => self.unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::check_result::CheckState;
+    use crate::exit_code::{Condition, ExitCodeMapping, ExitCodes};
+
+    #[test]
+    fn test_parse_mapping() {
+        let mapping: ExitCodeMapping = "error=1".parse().unwrap();
+        assert_eq!(Condition::Error, mapping.condition);
+        assert_eq!(1, mapping.code);
+
+        assert!("error".parse::<ExitCodeMapping>().is_err());
+        assert!("fatal=1".parse::<ExitCodeMapping>().is_err());
+        assert!("error=x".parse::<ExitCodeMapping>().is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let codes = ExitCodes::default();
+        assert_eq!(0, codes.code_of(&CheckState::Ok));
+        assert_eq!(3, codes.code_of(&CheckState::Unknown));
+
+        let mappings = vec![
+            "warning=0".parse().unwrap(),
+            "expired=1".parse().unwrap(),
+            "expired=5".parse().unwrap(),
+        ];
+        let codes = ExitCodes::new(&mappings);
+        assert_eq!(0, codes.code_of(&CheckState::Warning));
+        assert_eq!(5, codes.code_of(&CheckState::Expired));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::target::Target;
+
+/// Glob patterns selecting domain names, `*` matches any characters and `?` matches one
+///
+/// ```
+/// # use hcc::DomainFilter;
+/// let filter = DomainFilter::new(&["*.example.com".into()], &["staging-*".into()]);
+/// assert!(filter.matches("www.example.com"));
+/// assert!(!filter.matches("staging-api.example.com"));
+/// assert!(!filter.matches("example.org"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DomainFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl DomainFilter {
+    /// Filter keeping domain names matching any include pattern, or all if there is none, and
+    /// matching no exclude pattern
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        let lowercase = |patterns: &[String]| patterns.iter().map(|p| p.to_lowercase()).collect();
+        DomainFilter {
+            include: lowercase(include),
+            exclude: lowercase(exclude),
+        }
+    }
+
+    /// Whether domain name is selected, patterns match domain name with or without port
+    /// case-insensitively
+    pub fn matches(&self, domain_name: &str) -> bool {
+        let domain_name = domain_name.to_lowercase();
+        let host = domain_name.parse::<Target>().map(|t| t.host).ok();
+        let matches = |pattern: &String| {
+            glob_match(pattern.as_bytes(), domain_name.as_bytes())
+                || host
+                    .as_ref()
+                    .is_some_and(|h| glob_match(pattern.as_bytes(), h.as_bytes()))
+        };
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+
+    /// Domain names selected by filter, in the same order
+    pub fn apply<'a>(&self, domain_names: &[&'a str]) -> Vec<&'a str> {
+        domain_names
+            .iter()
+            .filter(|name| self.matches(name))
+            .copied()
+            .collect()
+    }
+}
+
+/// Match text against glob pattern, backtracking to the last `*` on mismatch
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::filter::{glob_match, DomainFilter};
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*.example.com", b"www.example.com"));
+        assert!(glob_match(b"*.example.com", b"a.b.example.com"));
+        assert!(!glob_match(b"*.example.com", b"example.com"));
+        assert!(glob_match(b"staging-*", b"staging-api.example.com"));
+        assert!(glob_match(b"api?.example.com", b"api1.example.com"));
+        assert!(!glob_match(b"api?.example.com", b"api.example.com"));
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"a*b*c", b"axxbyyc"));
+        assert!(!glob_match(b"a*b*c", b"axxbyy"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let domain_names = [
+            "www.example.com",
+            "staging-api.example.com",
+            "example.com:8443",
+            "example.org",
+        ];
+        let filter = DomainFilter::default();
+        assert_eq!(domain_names.to_vec(), filter.apply(&domain_names));
+
+        let filter = DomainFilter::new(&["*example.com".into()], &["staging-*".into()]);
+        assert_eq!(
+            vec!["www.example.com", "example.com:8443"],
+            filter.apply(&domain_names)
+        );
+
+        let filter = DomainFilter::new(&[], &["*.ORG".into()]);
+        assert_eq!(3, filter.apply(&domain_names).len());
+    }
+}
@@ -0,0 +1,126 @@
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use tracing::debug;
+
+/// How long to wait for a pending attempt before attempting the next address, RFC 8305
+/// section 5
+pub(crate) const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Order addresses alternating between address families, starting with family of the first
+/// address i.e. the one preferred by resolver, RFC 8305 section 4
+fn interleave(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let preferred = match addrs.first() {
+        Some(addr) => addr.is_ipv6(),
+        None => return vec![],
+    };
+    let (first, second): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addrs.iter().partition(|a| a.is_ipv6() == preferred);
+    let mut first = first.into_iter();
+    let mut second = second.into_iter();
+    let mut ordered = vec![];
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => return ordered,
+            (a, b) => {
+                ordered.extend(a);
+                ordered.extend(b);
+            }
+        }
+    }
+}
+
+/// Connect to addresses in parallel, attempting the next address once the previous attempt
+/// fails or is pending for delay, and return the first established connection, so a broken
+/// IPv6 path doesn't have to time out before IPv4 is attempted, RFC 8305, each attempt gives up
+/// after timeout if given
+pub(crate) fn connect(
+    addrs: &[SocketAddr],
+    delay: Duration,
+    timeout: Option<Duration>,
+) -> io::Result<TcpStream> {
+    let mut addrs = interleave(addrs).into_iter();
+    let (tx, rx) = mpsc::channel();
+    let mut pending = 0;
+    let mut last_error = None;
+    loop {
+        if let Some(addr) = addrs.next() {
+            debug!(%addr, "attempting connection");
+            let tx = tx.clone();
+            // attempts losing the race are closed once they complete
+            thread::spawn(move || {
+                let sock = match timeout {
+                    Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+                    None => TcpStream::connect(addr),
+                };
+                tx.send((addr, sock))
+            });
+            pending += 1;
+        }
+        if pending == 0 {
+            return Err(last_error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to")
+            }));
+        }
+        let received = if addrs.as_slice().is_empty() {
+            rx.recv().ok()
+        } else {
+            rx.recv_timeout(delay).ok()
+        };
+        match received {
+            Some((_, Ok(sock))) => return Ok(sock),
+            Some((addr, Err(e))) => {
+                debug!(%addr, error = %e, "connection attempt failed");
+                pending -= 1;
+                last_error = Some(e);
+            }
+            // attempt is still pending, attempt the next address in parallel
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{SocketAddr, TcpListener};
+    use std::time::Duration;
+
+    use crate::happy_eyeballs::{connect, interleave};
+
+    fn addrs(s: &[&str]) -> Vec<SocketAddr> {
+        s.iter().map(|a| a.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_interleave() {
+        let given = addrs(&["[::1]:443", "[::2]:443", "[::3]:443", "127.0.0.1:443"]);
+        let expected = addrs(&["[::1]:443", "127.0.0.1:443", "[::2]:443", "[::3]:443"]);
+        assert_eq!(expected, interleave(&given));
+
+        let given = addrs(&["127.0.0.1:443", "127.0.0.2:443", "[::1]:443"]);
+        let expected = addrs(&["127.0.0.1:443", "[::1]:443", "127.0.0.2:443"]);
+        assert_eq!(expected, interleave(&given));
+
+        assert!(interleave(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap();
+        // nothing listens on port of dropped listener, so connection is refused
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let sock = connect(&[closed, open], Duration::from_secs(60), None).unwrap();
+        assert_eq!(open, sock.peer_addr().unwrap());
+
+        assert!(connect(&[closed], Duration::from_secs(60), None).is_err());
+        assert!(connect(&[], Duration::from_secs(60), Some(Duration::from_secs(1))).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info_span};
+
+use crate::check_result::{CheckResult, CheckResultJSON};
+use crate::run_state::{bucket, key};
+use crate::sink::write_atomic;
+
+/// Placeholder in arguments of hook command replaced with domain name
+pub const DOMAIN_PLACEHOLDER: &str = "{domain}";
+
+/// How long webhook may take
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long command may run by default before it's killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often running command is polled for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Action fired when certificate drops below threshold e.g. to run `certbot renew` or to
+/// trigger a renewal pipeline, a command, a webhook or both
+///
+/// ```toml
+/// [[hooks]]
+/// command = ["certbot", "renew", "--cert-name", "{domain}"]
+/// days = 30
+/// tags = ["letsencrypt"]
+///
+/// [[hooks]]
+/// url = "https://ci.example.com/hooks/renew"
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    /// Command and its arguments, `{domain}` in arguments is replaced with domain name, which
+    /// is appended when no argument has it
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// URL to POST result in JSON format to
+    pub url: Option<String>,
+    /// Fire when certificate expires within days, grace period by default
+    pub days: Option<i64>,
+    /// Only fire for domain names tagged with all tags in configuration file
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Seconds command may run before it's killed, 300 by default
+    pub timeout: Option<u64>,
+}
+
+impl Hook {
+    /// Hook running command line, split into arguments like POSIX shell does with quotes
+    ///
+    /// ```
+    /// # use hcc::Hook;
+    /// let hook = Hook::command("certbot renew --cert-name '{domain}'").unwrap();
+    /// assert_eq!(4, hook.command.len());
+    /// assert!(Hook::command("sh -c 'unbalanced").is_err());
+    /// ```
+    pub fn command(command_line: &str) -> anyhow::Result<Self> {
+        let command = shlex::split(command_line)
+            .with_context(|| format!("invalid command line {0:?}", command_line))?;
+        if command.is_empty() {
+            bail!("command line is empty");
+        }
+        Ok(Hook {
+            command,
+            ..Default::default()
+        })
+    }
+
+    /// Hook posting result in JSON format to URL
+    pub fn webhook(url: &str) -> Self {
+        Hook {
+            url: Some(url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Whether hook fires for result checked with grace period, certificates never seen e.g.
+    /// of unreachable hosts and snoozed domain names have nothing to renew
+    pub fn matches(&self, result: &CheckResult, grace_in_days: i64) -> bool {
+        if result.not_after == 0 || result.snoozed_until.is_some() {
+            return false;
+        }
+        if !self.tags.iter().all(|tag| result.tags.contains(tag)) {
+            return false;
+        }
+        result.days <= self.days.unwrap_or(grace_in_days)
+    }
+
+    /// Run command and post to webhook of hook for result, command gets HCC_DOMAIN, HCC_DAYS,
+    /// HCC_STATE and HCC_NOT_AFTER in environment
+    pub fn fire(&self, result: &CheckResult) -> anyhow::Result<()> {
+        let _span = info_span!("hook", domain_name = result.domain_name).entered();
+        if let Some((program, args)) = self.command.split_first() {
+            let mut args: Vec<String> = args
+                .iter()
+                .map(|arg| arg.replace(DOMAIN_PLACEHOLDER, result.domain_name))
+                .collect();
+            if !self
+                .command
+                .iter()
+                .any(|arg| arg.contains(DOMAIN_PLACEHOLDER))
+            {
+                args.push(result.domain_name.to_string());
+            }
+            debug!(program = program.as_str(), ?args, "run hook command");
+            let mut child = Command::new(program)
+                .args(&args)
+                .env("HCC_DOMAIN", result.domain_name)
+                .env("HCC_DAYS", result.days.to_string())
+                .env("HCC_STATE", result.state.to_string())
+                .env("HCC_NOT_AFTER", result.not_after.to_string())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .spawn()
+                .with_context(|| format!("failed to run {0}", program))?;
+            // read from another thread, so command never blocks on full standard error
+            let mut stderr = child
+                .stderr
+                .take()
+                .context("no standard error of command")?;
+            let reader = thread::spawn(move || {
+                let mut s = String::new();
+                let _ = stderr.read_to_string(&mut s);
+                s
+            });
+            let timeout = self.timeout.map_or(COMMAND_TIMEOUT, Duration::from_secs);
+            let deadline = Instant::now() + timeout;
+            let status = loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if Instant::now() >= deadline {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    bail!("{0} is killed after running for {1:?}", program, timeout);
+                }
+                thread::sleep(POLL_INTERVAL);
+            };
+            let stderr = reader.join().unwrap_or_default();
+            if !status.success() {
+                bail!("{0} exits with {1}: {2}", program, status, stderr.trim());
+            }
+        }
+        if let Some(ref url) = self.url {
+            debug!(url = url.as_str(), "post to webhook");
+            let body = serde_json::to_string(&CheckResultJSON::new(result))?;
+            reqwest::blocking::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()?
+                .post(url)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+                .and_then(|r| r.error_for_status())
+                .with_context(|| format!("failed to post to {0}", url))?;
+        }
+        Ok(())
+    }
+}
+
+/// Remaining days of certificate of each domain name in the previous run by threshold of
+/// hooks, so hooks fire as certificate crosses their threshold, and again at each threshold of
+/// [`crate::run_state::THRESHOLDS`] below it in case renewal failed, rather than on every run
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HookState {
+    days: BTreeMap<String, BTreeMap<i64, i64>>,
+}
+
+impl HookState {
+    /// Load state of previous run from JSON file, missing file is treated as first run
+    ///
+    /// ```
+    /// # use hcc::HookState;
+    /// let state = HookState::load("/nonexistent/hooks.json").unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let s = match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HookState::default()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {0:?}", path)),
+        };
+        serde_json::from_str(&s).with_context(|| format!("failed to parse {0:?}", path))
+    }
+
+    /// Save state to JSON file atomically
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let s = serde_json::to_string_pretty(self)?;
+        write_atomic(path, s.as_bytes()).with_context(|| format!("failed to write {0:?}", path))
+    }
+
+    /// Whether hook matches result checked with grace period, and certificate crossed a
+    /// threshold since the previous run, domain name never seen by hook of the same threshold
+    /// counts as crossed
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, Hook, HookState};
+    /// let mut state = HookState::default();
+    /// let hook = Hook::command("certbot renew").unwrap();
+    /// let result = CheckResult {
+    ///     domain_name: "example.com",
+    ///     days: 5,
+    ///     not_after: 1,
+    ///     ..Default::default()
+    /// };
+    /// assert!(state.fires(&hook, &result, 7));
+    /// state.record(&hook, &result, 7);
+    /// assert!(!state.fires(&hook, &result, 7));
+    /// ```
+    pub fn fires(&self, hook: &Hook, result: &CheckResult, grace_in_days: i64) -> bool {
+        if !hook.matches(result, grace_in_days) {
+            return false;
+        }
+        let threshold = hook.days.unwrap_or(grace_in_days);
+        match self
+            .days
+            .get(&key(result))
+            .and_then(|days| days.get(&threshold))
+        {
+            Some(&days) => bucket(days, threshold) != bucket(result.days, threshold),
+            None => true,
+        }
+    }
+
+    /// Remember remaining days of result for hook in the next run, domain names without
+    /// certificate e.g. unreachable ones keep theirs
+    pub fn record(&mut self, hook: &Hook, result: &CheckResult, grace_in_days: i64) {
+        if result.not_after == 0 {
+            return;
+        }
+        let threshold = hook.days.unwrap_or(grace_in_days);
+        self.days
+            .entry(key(result))
+            .or_default()
+            .insert(threshold, result.days);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use crate::check_result::CheckResult;
+    use crate::hook::{Hook, HookState};
+
+    #[test]
+    fn test_matches() {
+        let build = |days| CheckResult {
+            domain_name: "example.com",
+            days,
+            not_after: 1,
+            tags: vec!["prod".into()],
+            ..Default::default()
+        };
+        let hook = Hook::command("true").unwrap();
+        assert!(hook.matches(&build(7), 7));
+        assert!(hook.matches(&build(-1), 7));
+        assert!(!hook.matches(&build(8), 7));
+        assert!(!hook.matches(&CheckResult::default(), 7));
+        let snoozed = CheckResult {
+            snoozed_until: NaiveDate::from_ymd_opt(2100, 1, 1),
+            ..build(1)
+        };
+        assert!(!hook.matches(&snoozed, 7));
+
+        let hook = Hook {
+            days: Some(30),
+            tags: vec!["prod".into()],
+            ..Hook::command("true").unwrap()
+        };
+        assert!(hook.matches(&build(30), 7));
+        let hook = Hook {
+            tags: vec!["staging".into()],
+            ..hook
+        };
+        assert!(!hook.matches(&build(30), 7));
+    }
+
+    #[test]
+    fn test_fire() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fired");
+        let result = CheckResult {
+            domain_name: "example.com",
+            days: 3,
+            ..Default::default()
+        };
+        let script = format!(
+            "echo \"$1 $HCC_DOMAIN $HCC_DAYS\" > {0}",
+            path.to_string_lossy()
+        );
+        let hook = Hook {
+            command: vec!["sh".into(), "-c".into(), script, "sh".into()],
+            ..Default::default()
+        };
+        hook.fire(&result).unwrap();
+        assert_eq!(
+            "example.com example.com 3\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        assert!(Hook::command("false").unwrap().fire(&result).is_err());
+        assert!(Hook::command("/nonexistent/renew")
+            .unwrap()
+            .fire(&result)
+            .is_err());
+
+        let hook = Hook {
+            timeout: Some(1),
+            ..Hook::command("sh -c 'sleep 10'").unwrap()
+        };
+        let e = hook.fire(&result).unwrap_err();
+        assert!(e.to_string().contains("is killed"), "{0}", e);
+    }
+
+    #[test]
+    fn test_hook_state() {
+        let build = |days| CheckResult {
+            domain_name: "example.com",
+            days,
+            not_after: 1,
+            ..Default::default()
+        };
+        let hook = Hook {
+            days: Some(20),
+            ..Hook::command("true").unwrap()
+        };
+        let mut state = HookState::default();
+        // runs before crossing threshold fire nothing
+        state.record(&hook, &build(21), 7);
+        assert!(!state.fires(&hook, &build(21), 7));
+        assert!(state.fires(&hook, &build(20), 7));
+        state.record(&hook, &build(20), 7);
+        assert!(!state.fires(&hook, &build(19), 7));
+        // fires again at lower threshold in case renewal failed
+        state.record(&hook, &build(15), 7);
+        assert!(state.fires(&hook, &build(14), 7));
+
+        // unreachable host keeps remaining days of its certificate
+        state.record(&hook, &build(14), 7);
+        let unreachable = CheckResult {
+            domain_name: "example.com",
+            ..Default::default()
+        };
+        state.record(&hook, &unreachable, 7);
+        assert!(!state.fires(&hook, &build(14), 7));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hooks.json");
+        state.save(&path).unwrap();
+        let loaded = HookState::load(&path).unwrap();
+        assert!(!loaded.fires(&hook, &build(14), 7));
+        // hook of another threshold has never seen the certificate
+        let hook = Hook {
+            days: Some(30),
+            ..hook
+        };
+        assert!(loaded.fires(&hook, &build(14), 7));
+    }
+}
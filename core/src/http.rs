@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Longest response head accepted
+const MAX_HEAD: usize = 16384;
+
+/// HTTP request sent over TLS connection once handshake completes, whose response is captured
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpRequest {
+    /// Path of request e.g. /healthz
+    pub path: String,
+    /// Host header, server name of handshake when none is given
+    pub host: Option<String>,
+}
+
+impl Default for HttpRequest {
+    fn default() -> Self {
+        HttpRequest {
+            path: "/".to_string(),
+            host: None,
+        }
+    }
+}
+
+impl HttpRequest {
+    /// Reject path or host with line breaks, which would inject headers into request
+    ///
+    /// ```
+    /// # use hcc::HttpRequest;
+    /// let request = HttpRequest {
+    ///     path: "/\r\nX-Injected: 1".to_string(),
+    ///     host: None,
+    /// };
+    /// assert!(request.validate().is_err());
+    /// assert!(HttpRequest::default().validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let invalid = |s: &str| s.contains(['\r', '\n']);
+        if invalid(&self.path) {
+            bail!("path of request {0:?} contains line break", self.path);
+        }
+        if let Some(ref host) = self.host.as_deref().filter(|h| invalid(h)) {
+            bail!("host of request {0:?} contains line break", host);
+        }
+        Ok(())
+    }
+
+    /// Send GET request over stream and read status line and headers of response, body is
+    /// never read
+    pub(crate) fn send<S: Read + Write>(
+        &self,
+        stream: &mut S,
+        default_host: &str,
+    ) -> anyhow::Result<HttpResponse> {
+        self.validate()?;
+        let host = self.host.as_deref().unwrap_or(default_host);
+        // one write, so request goes out in one record rather than a record per fragment
+        let request = format!(
+            "GET {0} HTTP/1.1\r\nHost: {1}\r\nUser-Agent: hcc/{2}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            self.path,
+            host,
+            env!("CARGO_PKG_VERSION")
+        );
+        stream.write_all(request.as_bytes())?;
+        stream.flush()?;
+
+        let mut head = vec![];
+        let mut buf = [0; 1024];
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).context("failed to read response")?;
+            if n == 0 {
+                bail!("server closed connection before response head");
+            }
+            head.extend_from_slice(&buf[..n]);
+            if head.len() > MAX_HEAD {
+                bail!("response head exceeds {0} bytes", MAX_HEAD);
+            }
+        }
+        let response = HttpResponse::parse(&String::from_utf8_lossy(&head))?;
+        debug!(host, path = %self.path, status = response.status, "HTTP response");
+        Ok(response)
+    }
+}
+
+/// Status line and headers of HTTP response e.g. to audit Strict-Transport-Security
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpResponse {
+    /// Status line e.g. HTTP/1.1 200 OK
+    pub status_line: String,
+    /// Status code e.g. 200
+    pub status: u16,
+    /// Headers by lowercase name, values of repeated header joined with comma
+    pub headers: BTreeMap<String, String>,
+}
+
+impl HttpResponse {
+    /// Parse response head, body after it is ignored
+    ///
+    /// ```
+    /// # use hcc::HttpResponse;
+    /// let head = "HTTP/1.1 200 OK\r\nStrict-Transport-Security: max-age=63072000\r\n\r\n";
+    /// let response = HttpResponse::parse(head).unwrap();
+    /// assert_eq!(200, response.status);
+    /// assert_eq!(Some("max-age=63072000"), response.header("strict-transport-security"));
+    /// ```
+    pub fn parse(head: &str) -> anyhow::Result<Self> {
+        let head = head.split("\r\n\r\n").next().unwrap_or_default();
+        let mut lines = head.lines();
+        let status_line = lines.next().unwrap_or_default().trim().to_string();
+        let status = match status_line
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [version, code, ..] if version.starts_with("HTTP/") => code
+                .parse()
+                .with_context(|| format!("invalid status line {0:?}", status_line))?,
+            _ => bail!("invalid status line {0:?}", status_line),
+        };
+        let mut headers: BTreeMap<String, String> = BTreeMap::new();
+        for line in lines {
+            let (name, value) = match line.split_once(':') {
+                Some((name, value)) => (name.trim().to_ascii_lowercase(), value.trim()),
+                None => continue,
+            };
+            headers
+                .entry(name)
+                .and_modify(|v| {
+                    v.push_str(", ");
+                    v.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+        Ok(HttpResponse {
+            status_line,
+            status,
+            headers,
+        })
+    }
+
+    /// Value of header by case-insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Read, Write};
+
+    use crate::http::{HttpRequest, HttpResponse};
+
+    /// Stream replaying response and recording request
+    struct Replay {
+        request: Vec<u8>,
+        response: Cursor<Vec<u8>>,
+    }
+
+    impl Read for Replay {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.response.read(buf)
+        }
+    }
+
+    impl Write for Replay {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.request.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_send() {
+        let mut stream = Replay {
+            request: vec![],
+            response: Cursor::new(
+                b"HTTP/1.1 301 Moved Permanently\r\nLocation: https://www.example.com/\r\nSet-Cookie: a=1\r\nset-cookie: b=2\r\n\r\nbody"
+                    .to_vec(),
+            ),
+        };
+        let request = HttpRequest {
+            path: "/healthz".into(),
+            host: None,
+        };
+        let response = request.send(&mut stream, "example.com").unwrap();
+        let sent = String::from_utf8(stream.request).unwrap();
+        assert!(sent.starts_with("GET /healthz HTTP/1.1\r\nHost: example.com\r\n"));
+        assert_eq!("HTTP/1.1 301 Moved Permanently", response.status_line);
+        assert_eq!(301, response.status);
+        assert_eq!(
+            Some("https://www.example.com/"),
+            response.header("Location")
+        );
+        assert_eq!(Some("a=1, b=2"), response.header("set-cookie"));
+
+        let mut stream = Replay {
+            request: vec![],
+            response: Cursor::new(b"HTTP/1.1 200 OK\r\n".to_vec()),
+        };
+        assert!(HttpRequest::default()
+            .send(&mut stream, "example.com")
+            .is_err());
+        assert!(HttpResponse::parse("SSH-2.0-OpenSSH_8.9\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_send_line_break() {
+        let mut stream = Replay {
+            request: vec![],
+            response: Cursor::new(b"HTTP/1.1 200 OK\r\n\r\n".to_vec()),
+        };
+        let request = HttpRequest {
+            path: "/".into(),
+            host: Some("example.com\r\nX-Injected: 1".into()),
+        };
+        assert!(request.send(&mut stream, "example.com").is_err());
+        assert!(stream.request.is_empty());
+    }
+}
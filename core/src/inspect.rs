@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use rustls::ProtocolVersion;
+use serde::Serialize;
+use tracing::debug;
+use x509_parser::objects::oid2sn;
+use x509_parser::parse_x509_certificate;
+use x509_parser::x509::AlgorithmIdentifier;
+
+use crate::check_client::{version_name, CheckClient};
+use crate::detail::extensions;
+use crate::ssllabs::{self, EndpointGrade};
+use crate::target::Target;
+
+const OID_ED25519: &str = "1.3.101.112";
+const OID_ED448: &str = "1.3.101.113";
+
+/// Certificate in chain presented by server
+#[derive(Debug, Default, Serialize)]
+pub struct ChainCertificate {
+    /// Distinguished name of subject
+    pub subject: String,
+    /// Distinguished name of issuer
+    pub issuer: String,
+    /// Algorithm with which certificate is signed e.g. sha256WithRSAEncryption
+    pub signature_algorithm: String,
+    /// Algorithm of public key e.g. rsaEncryption or id-ecPublicKey
+    pub key_algorithm: String,
+    /// Key usage in RFC 5280 names e.g. digitalSignature
+    pub key_usage: Vec<String>,
+    /// Extended key usage in RFC 5280 names e.g. serverAuth
+    pub extended_key_usage: Vec<String>,
+}
+
+/// Minimum version of a client able to connect, or none if no version is able to
+#[derive(Debug, Serialize)]
+pub struct ClientSupport {
+    /// Name of client e.g. Android
+    pub client: &'static str,
+    /// Minimum version e.g. 7.1.1
+    pub minimum_version: Option<&'static str>,
+}
+
+/// Detailed report of TLS parameters and certificate chain of a host
+#[derive(Debug, Default, Serialize)]
+pub struct Inspection {
+    /// Domain name that got inspected
+    pub domain_name: String,
+    /// TLS versions completing handshake e.g. TLSv1.2
+    pub tls_versions: Vec<String>,
+    /// Certificate chain presented by server, leaf first
+    pub chain: Vec<ChainCertificate>,
+    /// Estimated minimum versions of common clients
+    pub minimum_clients: Vec<ClientSupport>,
+    /// Grades from SSL Labs, only when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssllabs_grades: Option<Vec<EndpointGrade>>,
+    /// Differences between local findings and external grades
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl Inspection {
+    /// Fetch grades from SSL Labs and note differences from local findings
+    ///
+    /// Host is submitted to public SSL Labs API, and assessment may take minutes.
+    pub fn grade_with_ssllabs(&mut self) -> anyhow::Result<()> {
+        let target: Target = self.domain_name.parse()?;
+        let grades = ssllabs::fetch_grades(&target.host)?;
+        self.notes.extend(ssllabs::reconcile(&grades));
+        self.ssllabs_grades = Some(grades);
+        Ok(())
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{0}", self.domain_name)?;
+        writeln!(f, "  TLS versions: {0}", self.tls_versions.join(", "))?;
+        writeln!(f, "  chain:")?;
+        for (i, cert) in self.chain.iter().enumerate() {
+            writeln!(f, "    #{0} {1}", i, cert.subject)?;
+            writeln!(f, "       issuer: {0}", cert.issuer)?;
+            writeln!(
+                f,
+                "       signature: {0}, key: {1}",
+                cert.signature_algorithm, cert.key_algorithm
+            )?;
+            if !cert.key_usage.is_empty() || !cert.extended_key_usage.is_empty() {
+                writeln!(
+                    f,
+                    "       key usage: {0}, extended key usage: {1}",
+                    none_if_empty(&cert.key_usage),
+                    none_if_empty(&cert.extended_key_usage)
+                )?;
+            }
+        }
+        let clients: Vec<String> = self
+            .minimum_clients
+            .iter()
+            .map(|c| match c.minimum_version {
+                Some("any") => format!("{0} any", c.client),
+                Some(v) => format!("{0} {1}+", c.client, v),
+                None => format!("{0} unsupported", c.client),
+            })
+            .collect();
+        write!(f, "  minimum clients: {0}", clients.join(", "))?;
+        if let Some(ref grades) = self.ssllabs_grades {
+            let grades: Vec<String> = grades
+                .iter()
+                .map(|g| format!("{0} {1}", g.ip_address, g.grade.as_deref().unwrap_or("-")))
+                .collect();
+            write!(f, "\n  SSL Labs grades: {0}", grades.join(", "))?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  note: {0}", note)?;
+        }
+        Ok(())
+    }
+}
+
+fn none_if_empty(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+impl CheckClient {
+    /// Inspect TLS versions and certificate chain of one domain name, and estimate minimum
+    /// versions of common clients able to connect
+    ///
+    /// ```
+    /// # use hcc::CheckClient;
+    /// let client = CheckClient::new();
+    /// client.inspect("sha512.badssl.com");
+    /// ```
+    pub fn inspect(&self, domain_name: &str) -> anyhow::Result<Inspection> {
+        let target = self.target_of(domain_name)?;
+        let sni = self.sni_of(&target)?;
+
+        let mut tls_versions = vec![];
+        let mut certificates = vec![];
+        for version in &[ProtocolVersion::TLSv1_2, ProtocolVersion::TLSv1_3] {
+            let mut config = (*self.config).clone();
+            config.versions = vec![*version];
+            let mut sock = self.connect(&target)?;
+            match Self::handshake(&mut sock, &target, sni, &Arc::new(config)) {
+                Ok(handshake) => {
+                    tls_versions.extend(handshake.protocol_version);
+                    certificates = handshake.certificates;
+                }
+                Err(e) => debug!(target = %target, ?version, error = %e, "version unsupported"),
+            }
+        }
+        if tls_versions.is_empty() {
+            anyhow::bail!("no TLS version completes handshake with {0}", target);
+        }
+
+        let mut chain = vec![];
+        for certificate in &certificates {
+            let (_, cert) = parse_x509_certificate(certificate.as_ref())
+                .map_err(|e| anyhow::anyhow!("failed to parse certificate: {0}", e))?;
+            let extensions = extensions(&cert);
+            chain.push(ChainCertificate {
+                subject: cert.subject().to_string(),
+                issuer: cert.issuer().to_string(),
+                signature_algorithm: algorithm_name(&cert.signature_algorithm),
+                key_algorithm: algorithm_name(&cert.tbs_certificate.subject_pki.algorithm),
+                key_usage: extensions.key_usage,
+                extended_key_usage: extensions.extended_key_usage,
+            });
+        }
+
+        Ok(Inspection {
+            domain_name: domain_name.to_string(),
+            tls_versions: tls_versions
+                .iter()
+                .map(|v| version_name(Some(*v)))
+                .collect(),
+            minimum_clients: minimum_clients(&tls_versions, &chain),
+            chain,
+            ..Default::default()
+        })
+    }
+}
+
+fn algorithm_name(algorithm: &AlgorithmIdentifier) -> String {
+    match oid2sn(&algorithm.algorithm) {
+        Ok(sn) => sn.to_string(),
+        Err(_) => algorithm.algorithm.to_id_string(),
+    }
+}
+
+/// Minimum version of a client required by a property of host
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Requirement {
+    /// Any version is able to connect
+    Any,
+    /// Version as comparable tuple and label
+    Version((u32, u32, u32), &'static str),
+    /// No version is able to connect
+    Unsupported,
+}
+
+impl Requirement {
+    fn max(self, other: Requirement) -> Requirement {
+        use Requirement::*;
+        match (self, other) {
+            (Unsupported, _) | (_, Unsupported) => Unsupported,
+            (Any, r) | (r, Any) => r,
+            (Version(a, _), Version(b, _)) => match a.cmp(&b) {
+                Ordering::Less => other,
+                _ => self,
+            },
+        }
+    }
+}
+
+const CLIENTS: [&str; 7] = [
+    "Android",
+    "iOS",
+    "Chrome",
+    "Firefox",
+    "Safari",
+    "Java",
+    "Internet Explorer",
+];
+
+type Requirements = [Requirement; 7];
+
+use Requirement::{Any, Unsupported, Version as V};
+
+/// First versions enabling TLS 1.2 by default
+const TLS_1_2: Requirements = [
+    V((4, 4, 2), "4.4.2"),
+    V((5, 0, 0), "5"),
+    V((30, 0, 0), "30"),
+    V((27, 0, 0), "27"),
+    V((7, 0, 0), "7"),
+    V((8, 0, 0), "8"),
+    V((11, 0, 0), "11"),
+];
+
+/// First versions supporting TLS 1.3
+const TLS_1_3: Requirements = [
+    V((10, 0, 0), "10"),
+    V((12, 2, 0), "12.2"),
+    V((70, 0, 0), "70"),
+    V((63, 0, 0), "63"),
+    V((12, 1, 0), "12.1"),
+    V((11, 0, 0), "11"),
+    Unsupported,
+];
+
+/// First versions trusting ISRG Root X1 without cross-signature
+const ISRG_ROOT_X1: Requirements = [
+    V((7, 1, 1), "7.1.1"),
+    V((10, 0, 0), "10"),
+    Any,
+    V((50, 0, 0), "50"),
+    V((10, 0, 0), "10"),
+    V((8, 141, 0), "8u141"),
+    Any,
+];
+
+/// First versions accepting EdDSA certificates
+const EDDSA: Requirements = [
+    Unsupported,
+    Unsupported,
+    Unsupported,
+    Unsupported,
+    Unsupported,
+    V((15, 0, 0), "15"),
+    Unsupported,
+];
+
+fn minimum_clients(
+    tls_versions: &[ProtocolVersion],
+    chain: &[ChainCertificate],
+) -> Vec<ClientSupport> {
+    let mut rules: Vec<&Requirements> = vec![];
+
+    if tls_versions.contains(&ProtocolVersion::TLSv1_2) {
+        rules.push(&TLS_1_2);
+    } else if tls_versions.contains(&ProtocolVersion::TLSv1_3) {
+        rules.push(&TLS_1_3);
+    }
+
+    if let Some(top) = chain.last() {
+        if top.issuer.contains("CN=ISRG Root X1") {
+            rules.push(&ISRG_ROOT_X1);
+        }
+    }
+
+    let eddsa = |s: &str| s == OID_ED25519 || s == OID_ED448;
+    if chain
+        .iter()
+        .any(|c| eddsa(&c.signature_algorithm) || eddsa(&c.key_algorithm))
+    {
+        rules.push(&EDDSA);
+    }
+
+    CLIENTS
+        .iter()
+        .enumerate()
+        .map(|(i, client)| {
+            let requirement = rules.iter().fold(Any, |acc, rule| acc.max(rule[i]));
+            ClientSupport {
+                client,
+                minimum_version: match requirement {
+                    Any => Some("any"),
+                    V(_, label) => Some(label),
+                    Unsupported => None,
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rustls::ProtocolVersion;
+
+    use crate::inspect::{minimum_clients, ChainCertificate, OID_ED25519};
+
+    fn minimum_version_of<'a>(
+        clients: &'a [crate::inspect::ClientSupport],
+        client: &str,
+    ) -> Option<&'a str> {
+        clients
+            .iter()
+            .find(|c| c.client == client)
+            .unwrap()
+            .minimum_version
+    }
+
+    #[test]
+    fn test_minimum_clients_tls_1_2() {
+        let versions = [ProtocolVersion::TLSv1_2, ProtocolVersion::TLSv1_3];
+        let clients = minimum_clients(&versions, &[]);
+        assert_eq!(Some("4.4.2"), minimum_version_of(&clients, "Android"));
+        assert_eq!(Some("8"), minimum_version_of(&clients, "Java"));
+    }
+
+    #[test]
+    fn test_minimum_clients_tls_1_3_only() {
+        let clients = minimum_clients(&[ProtocolVersion::TLSv1_3], &[]);
+        assert_eq!(Some("10"), minimum_version_of(&clients, "Android"));
+        assert_eq!(None, minimum_version_of(&clients, "Internet Explorer"));
+    }
+
+    #[test]
+    fn test_minimum_clients_isrg_root_x1() {
+        let chain = vec![ChainCertificate {
+            issuer: "C=US, O=Internet Security Research Group, CN=ISRG Root X1".into(),
+            ..Default::default()
+        }];
+        let clients = minimum_clients(&[ProtocolVersion::TLSv1_2], &chain);
+        assert_eq!(Some("7.1.1"), minimum_version_of(&clients, "Android"));
+        assert_eq!(Some("8u141"), minimum_version_of(&clients, "Java"));
+        assert_eq!(Some("30"), minimum_version_of(&clients, "Chrome"));
+    }
+
+    #[test]
+    fn test_minimum_clients_eddsa() {
+        let chain = vec![ChainCertificate {
+            key_algorithm: OID_ED25519.into(),
+            ..Default::default()
+        }];
+        let clients = minimum_clients(&[ProtocolVersion::TLSv1_2], &chain);
+        assert_eq!(None, minimum_version_of(&clients, "Chrome"));
+        assert_eq!(Some("15"), minimum_version_of(&clients, "Java"));
+    }
+}
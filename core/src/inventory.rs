@@ -0,0 +1,509 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use reqwest::blocking::Client;
+use roxmltree::Document;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::debug;
+
+use crate::sigv4::{sha256_hex, uri_encode, Signer};
+
+/// How long each request to cloud provider may take
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Key types of ACM, certificates of other key types than RSA 2048 are only listed on request
+const ACM_KEY_TYPES: &[&str] = &[
+    "RSA_1024",
+    "RSA_2048",
+    "RSA_3072",
+    "RSA_4096",
+    "EC_prime256v1",
+    "EC_secp384r1",
+    "EC_secp521r1",
+];
+
+/// Certificate in inventory of cloud provider, checked by expiration recorded there since
+/// it may be attached to load balancers behind private endpoints only
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CloudCertificate {
+    /// Name of certificate in results e.g. acm:us-east-1/example.com/1234abcd
+    pub name: String,
+    /// Expiration in seconds since epoch
+    pub not_after: i64,
+    /// Subject alternative names, if provider records them
+    pub sans: Vec<String>,
+    /// Notes on certificate e.g. not attached to any resource
+    pub findings: Vec<String>,
+}
+
+/// Certificates in AWS Certificate Manager of a region and server certificates of IAM,
+/// requests are signed with credentials of environment
+pub struct AwsInventory {
+    signer: Signer,
+    endpoint: Option<String>,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AcmPage {
+    #[serde(default)]
+    certificate_summary_list: Vec<AcmSummary>,
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AcmSummary {
+    certificate_arn: String,
+    domain_name: Option<String>,
+    #[serde(default)]
+    subject_alternative_name_summaries: Vec<String>,
+    not_after: Option<f64>,
+    in_use: Option<bool>,
+}
+
+impl AwsInventory {
+    /// Inventory of region with credentials in AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and
+    /// optionally AWS_SESSION_TOKEN
+    pub fn from_env(region: &str) -> anyhow::Result<Self> {
+        Ok(AwsInventory {
+            signer: Signer::from_env(region, "acm")?,
+            endpoint: None,
+            client: Client::builder().timeout(TIMEOUT).build()?,
+        })
+    }
+
+    /// Send requests to endpoint instead of AWS e.g. LocalStack
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.trim_end_matches('/').to_string());
+        self
+    }
+
+    fn post(
+        &self,
+        signer: &Signer,
+        url: &str,
+        content_type: &str,
+        body: String,
+        extra: &[(&str, &str)],
+    ) -> anyhow::Result<String> {
+        let host = url
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .with_context(|| format!("invalid endpoint {0}", url))?;
+        let now = Utc::now();
+        let payload_hash = sha256_hex(body.as_bytes());
+        let mut signed = vec![("content-type", content_type)];
+        signed.extend_from_slice(extra);
+        let headers = signer.headers(host, &payload_hash, &now, &signed);
+        let authorization = signer.authorization("POST", "/", &headers, &payload_hash, &now);
+        let mut request = self.client.post(url).header("Authorization", authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| name != "host") {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        request
+            .body(body)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .with_context(|| format!("failed to request {0}", url))
+    }
+
+    /// Issued and expired certificates of ACM, certificates pending validation have no
+    /// expiration yet
+    pub fn acm(&self) -> anyhow::Result<Vec<CloudCertificate>> {
+        let region = &self.signer.region;
+        let url = self
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://acm.{0}.amazonaws.com", region));
+        let mut certificates = vec![];
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut body = json!({
+                "CertificateStatuses": ["ISSUED", "EXPIRED"],
+                "Includes": {"keyTypes": ACM_KEY_TYPES},
+                "MaxItems": 1000,
+            });
+            if let Some(ref token) = next_token {
+                body["NextToken"] = json!(token);
+            }
+            let page = self.post(
+                &self.signer,
+                &url,
+                "application/x-amz-json-1.1",
+                body.to_string(),
+                &[("x-amz-target", "CertificateManager.ListCertificates")],
+            )?;
+            let page: AcmPage =
+                serde_json::from_str(&page).context("failed to parse certificates of ACM")?;
+            for summary in page.certificate_summary_list {
+                let not_after = match summary.not_after {
+                    Some(not_after) => not_after as i64,
+                    None => continue,
+                };
+                let id = summary
+                    .certificate_arn
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default();
+                let mut findings = vec![];
+                if summary.in_use == Some(false) {
+                    findings.push("not in use by any resource".to_string());
+                }
+                certificates.push(CloudCertificate {
+                    name: format!(
+                        "acm:{0}/{1}/{2}",
+                        region,
+                        summary.domain_name.unwrap_or_default(),
+                        id
+                    ),
+                    not_after,
+                    sans: summary.subject_alternative_name_summaries,
+                    findings,
+                });
+            }
+            next_token = page.next_token.filter(|t| !t.is_empty());
+            if next_token.is_none() {
+                break;
+            }
+        }
+        debug!(
+            region = region.as_str(),
+            count = certificates.len(),
+            "certificates in ACM"
+        );
+        Ok(certificates)
+    }
+
+    /// Server certificates of IAM, used by load balancers in regions without ACM or
+    /// uploaded before it, IAM is global so they are listed once whatever region
+    pub fn iam(&self) -> anyhow::Result<Vec<CloudCertificate>> {
+        let signer = Signer {
+            region: "us-east-1".to_string(),
+            service: "iam".to_string(),
+            ..self.signer.clone()
+        };
+        let url = self
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| "https://iam.amazonaws.com".to_string());
+        let mut certificates = vec![];
+        let mut marker: Option<String> = None;
+        loop {
+            let mut body = "Action=ListServerCertificates&Version=2010-05-08".to_string();
+            if let Some(ref marker) = marker {
+                body.push_str(&format!("&Marker={0}", uri_encode(marker, true)));
+            }
+            let page = self.post(
+                &signer,
+                &url,
+                "application/x-www-form-urlencoded; charset=utf-8",
+                body,
+                &[],
+            )?;
+            let doc = Document::parse(&page).context("failed to parse certificates of IAM")?;
+            let text = |node: roxmltree::Node, tag: &str| {
+                node.children()
+                    .find(|n| n.has_tag_name(tag))
+                    .and_then(|n| n.text())
+                    .map(str::to_string)
+            };
+            for member in doc.descendants().filter(|n| {
+                n.has_tag_name("member")
+                    && n.parent()
+                        .is_some_and(|p| p.has_tag_name("ServerCertificateMetadataList"))
+            }) {
+                let name = text(member, "ServerCertificateName").unwrap_or_default();
+                let expiration = text(member, "Expiration").unwrap_or_default();
+                let not_after = DateTime::parse_from_rfc3339(&expiration)
+                    .with_context(|| format!("invalid expiration of {0}", name))?
+                    .timestamp();
+                certificates.push(CloudCertificate {
+                    name: format!("iam:{0}", name),
+                    not_after,
+                    ..Default::default()
+                });
+            }
+            let result = doc
+                .descendants()
+                .find(|n| n.has_tag_name("ListServerCertificatesResult"));
+            let truncated = result.and_then(|r| text(r, "IsTruncated")) == Some("true".to_string());
+            marker = result.and_then(|r| text(r, "Marker"));
+            if !truncated || marker.is_none() {
+                break;
+            }
+        }
+        debug!(count = certificates.len(), "server certificates in IAM");
+        Ok(certificates)
+    }
+}
+
+/// Certificates in Google Cloud Certificate Manager of a project, requests are authorized
+/// with OAuth access token e.g. of `gcloud auth print-access-token`
+pub struct GcpInventory {
+    project: String,
+    location: String,
+    token: String,
+    endpoint: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcpPage {
+    #[serde(default)]
+    certificates: Vec<GcpCertificate>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcpCertificate {
+    name: String,
+    expire_time: Option<String>,
+    #[serde(default)]
+    san_dnsnames: Vec<String>,
+}
+
+impl GcpInventory {
+    /// Inventory of project at location, `PROJECT[/LOCATION]` where location is global by
+    /// default
+    ///
+    /// ```
+    /// # use hcc::GcpInventory;
+    /// let inventory = GcpInventory::new("my-project/europe-west1", "token").unwrap();
+    /// assert_eq!(
+    ///     "https://certificatemanager.googleapis.com/v1/projects/my-project/locations/europe-west1/certificates",
+    ///     inventory.url()
+    /// );
+    /// ```
+    pub fn new(project: &str, token: &str) -> anyhow::Result<Self> {
+        let (project, location) = match project.split_once('/') {
+            Some((project, location)) => (project, location),
+            None => (project, "global"),
+        };
+        if project.is_empty() || location.is_empty() {
+            bail!("invalid project {0:?}, expect PROJECT[/LOCATION]", project);
+        }
+        Ok(GcpInventory {
+            project: project.to_string(),
+            location: location.to_string(),
+            token: token.to_string(),
+            endpoint: "https://certificatemanager.googleapis.com".to_string(),
+            client: Client::builder().timeout(TIMEOUT).build()?,
+        })
+    }
+
+    /// Inventory of project with access token in GOOGLE_OAUTH_ACCESS_TOKEN
+    pub fn from_env(project: &str) -> anyhow::Result<Self> {
+        let token = std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN")
+            .context("GOOGLE_OAUTH_ACCESS_TOKEN is not set")?;
+        Self::new(project, &token)
+    }
+
+    /// Send requests to endpoint instead of Google Cloud
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// URL listing certificates
+    pub fn url(&self) -> String {
+        format!(
+            "{0}/v1/projects/{1}/locations/{2}/certificates",
+            self.endpoint, self.project, self.location
+        )
+    }
+
+    /// Certificates with expiration, those still being provisioned have none yet
+    pub fn certificates(&self) -> anyhow::Result<Vec<CloudCertificate>> {
+        let mut certificates = vec![];
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = format!("{0}?pageSize=100", self.url());
+            if let Some(ref token) = page_token {
+                url.push_str(&format!("&pageToken={0}", uri_encode(token, true)));
+            }
+            let page = self
+                .client
+                .get(&url)
+                .bearer_auth(&self.token)
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.text())
+                .with_context(|| format!("failed to request {0}", url))?;
+            let page: GcpPage = serde_json::from_str(&page)
+                .context("failed to parse certificates of Certificate Manager")?;
+            for certificate in page.certificates {
+                let expire_time = match certificate.expire_time {
+                    Some(ref expire_time) => expire_time,
+                    None => continue,
+                };
+                let not_after = DateTime::parse_from_rfc3339(expire_time)
+                    .with_context(|| format!("invalid expiration of {0}", certificate.name))?
+                    .timestamp();
+                let id = certificate.name.rsplit('/').next().unwrap_or_default();
+                certificates.push(CloudCertificate {
+                    name: format!("gcp:{0}/{1}/{2}", self.project, self.location, id),
+                    not_after,
+                    sans: certificate.san_dnsnames,
+                    ..Default::default()
+                });
+            }
+            page_token = page.next_page_token.filter(|t| !t.is_empty());
+            if page_token.is_none() {
+                break;
+            }
+        }
+        debug!(
+            project = self.project.as_str(),
+            count = certificates.len(),
+            "certificates in Certificate Manager"
+        );
+        Ok(certificates)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crate::inventory::{AwsInventory, GcpInventory};
+    use crate::sigv4::Signer;
+
+    /// Fake API answering requests in order with body, sending head and body of each request
+    fn api_server(responses: Vec<String>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for body in responses {
+                let (mut sock, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(sock.try_clone().unwrap());
+                let mut request = String::new();
+                let mut length = 0;
+                let mut line = String::new();
+                while line != "\r\n" {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                }
+                let mut payload = vec![0; length];
+                reader.read_exact(&mut payload).unwrap();
+                request.push_str(&String::from_utf8(payload).unwrap());
+                tx.send(request).unwrap();
+                write!(
+                    sock,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {0}\r\nConnection: close\r\n\r\n{1}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (format!("http://{0}", addr), rx)
+    }
+
+    fn aws(endpoint: &str) -> AwsInventory {
+        AwsInventory {
+            signer: Signer {
+                access_key: "key".into(),
+                secret_key: "secret".into(),
+                session_token: None,
+                region: "eu-west-1".into(),
+                service: "acm".into(),
+            },
+            endpoint: None,
+            client: reqwest::blocking::Client::new(),
+        }
+        .endpoint(endpoint)
+    }
+
+    #[test]
+    fn test_acm() {
+        let (endpoint, requests) = api_server(vec![
+            r#"{"CertificateSummaryList":[{"CertificateArn":"arn:aws:acm:eu-west-1:1:certificate/abcd",
+                "DomainName":"example.com","SubjectAlternativeNameSummaries":["example.com"],
+                "NotAfter":1700000000.0,"InUse":false}],"NextToken":"next"}"#
+                .into(),
+            r#"{"CertificateSummaryList":[{"CertificateArn":"arn:aws:acm:eu-west-1:1:certificate/pending",
+                "DomainName":"example.org"}]}"#
+                .into(),
+        ]);
+        let certificates = aws(&endpoint).acm().unwrap();
+        assert_eq!(1, certificates.len());
+        assert_eq!("acm:eu-west-1/example.com/abcd", certificates[0].name);
+        assert_eq!(1700000000, certificates[0].not_after);
+        assert_eq!(vec!["not in use by any resource"], certificates[0].findings);
+        let first = requests.recv().unwrap().to_ascii_lowercase();
+        assert!(first.contains("x-amz-target: certificatemanager.listcertificates"));
+        assert!(first.contains("credential=key/"));
+        assert!(requests.recv().unwrap().contains(r#""NextToken":"next""#));
+    }
+
+    #[test]
+    fn test_iam() {
+        let (endpoint, requests) = api_server(vec![
+            r#"<ListServerCertificatesResponse xmlns="https://iam.amazonaws.com/doc/2010-05-08/">
+  <ListServerCertificatesResult>
+    <IsTruncated>true</IsTruncated>
+    <Marker>a/b+c</Marker>
+    <ServerCertificateMetadataList>
+      <member>
+        <ServerCertificateName>legacy-elb</ServerCertificateName>
+        <Expiration>2023-11-14T22:13:20Z</Expiration>
+      </member>
+    </ServerCertificateMetadataList>
+  </ListServerCertificatesResult>
+</ListServerCertificatesResponse>"#
+                .into(),
+            r#"<ListServerCertificatesResponse><ListServerCertificatesResult>
+    <IsTruncated>false</IsTruncated><ServerCertificateMetadataList/>
+</ListServerCertificatesResult></ListServerCertificatesResponse>"#
+                .into(),
+        ]);
+        let certificates = aws(&endpoint).iam().unwrap();
+        assert_eq!(1, certificates.len());
+        assert_eq!("iam:legacy-elb", certificates[0].name);
+        assert_eq!(1700000000, certificates[0].not_after);
+        assert!(requests
+            .recv()
+            .unwrap()
+            .contains("/us-east-1/iam/aws4_request"));
+        assert!(requests.recv().unwrap().contains("&Marker=a%2Fb%2Bc"));
+    }
+
+    #[test]
+    fn test_gcp() {
+        let (endpoint, requests) = api_server(vec![r#"{"certificates":[
+            {"name":"projects/p/locations/global/certificates/www","expireTime":"2023-11-14T22:13:20Z",
+             "sanDnsnames":["www.example.com"]},
+            {"name":"projects/p/locations/global/certificates/provisioning"}]}"#
+            .into()]);
+        let inventory = GcpInventory::new("p", "token").unwrap().endpoint(&endpoint);
+        let certificates = inventory.certificates().unwrap();
+        assert_eq!(1, certificates.len());
+        assert_eq!("gcp:p/global/www", certificates[0].name);
+        assert_eq!(1700000000, certificates[0].not_after);
+        assert_eq!(vec!["www.example.com"], certificates[0].sans);
+        let request = requests.recv().unwrap();
+        assert!(
+            request.starts_with("GET /v1/projects/p/locations/global/certificates?pageSize=100 ")
+        );
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("authorization: bearer token"));
+        assert!(GcpInventory::new("/global", "token").is_err());
+    }
+}
@@ -1,7 +1,88 @@
 #![forbid(unsafe_code)]
-pub use check_client::CheckClient;
+pub use agent::{Agent, AgentRequest};
+pub use bench::{Benchmark, Timings};
+pub use cache::Cache;
+pub use cancellation::Cancellation;
+pub use check_client::{CheckClient, CheckClientBuilder, IncludeCertificates};
+pub use check_result::group_by_certificate;
 pub use check_result::CheckResult;
 pub use check_result::CheckResultJSON;
+pub use check_result::CheckState;
+pub use check_result::Summary;
+pub use check_result::TextStyle;
+pub use check_result::JSON_SCHEMA;
+pub use check_result::SCHEMA_VERSION;
+pub use cidr::Cidr;
+pub use compare::{CertificateSummary, Comparison, Difference};
+pub use config::{Config, CtConfig, DomainConfig};
+pub use detail::{CertificateDetail, Extensions, NameComponent, PublicKey};
+pub use error::CheckError;
+pub use exit_code::{Condition, ExitCodeMapping, ExitCodes};
+pub use filter::DomainFilter;
+pub use hook::{Hook, HookState};
+pub use http::{HttpRequest, HttpResponse};
+pub use inspect::{ChainCertificate, ClientSupport, Inspection};
+pub use inventory::{AwsInventory, CloudCertificate, GcpInventory};
+pub use mta_sts::{has_mta_sts_record, Mode as MtaStsMode, MtaStsPolicy};
+pub use mx::{mail_servers, SMTPS_PORT, SMTP_PORTS};
+pub use nmap::nmap_targets;
+pub use number_format::NumberFormat;
+pub use os_store::{OsStore, StoreCertificate, DEFAULT_STORE};
+pub use plugin::{Formatter, Notifier, Plugin, PluginKind, PluginRegistry};
+pub use policy::{Policy, Violation};
+pub use rate_limit::RateLimiter;
+pub use report_signature::{verify_report, ReportSignature, ReportSigner};
+pub use run_state::{RunState, THRESHOLDS};
+pub use s3::{S3Bucket, DEFAULT_ENDPOINT as DEFAULT_S3_ENDPOINT};
+pub use sink::{ColorChoice, Destination, Format, Sink};
+pub use snooze::Snoozes;
+pub use ssh::{SshCertificate, SSH_PORT};
+pub use ssllabs::EndpointGrade;
+pub use starttls::StartTls;
+pub use status_page::StatusPage;
+pub use target::Target;
+pub use trust_store::{TrustStatus, TrustStore};
+pub use vault::{VaultCertificate, VaultPki};
+pub use zone::Zone;
 
+mod agent;
+mod bench;
+mod cache;
+mod cancellation;
 mod check_client;
 mod check_result;
+mod cidr;
+mod compare;
+mod config;
+mod detail;
+mod error;
+mod exit_code;
+mod filter;
+mod happy_eyeballs;
+mod hook;
+mod http;
+mod inspect;
+mod inventory;
+mod mta_sts;
+mod mx;
+mod nmap;
+mod number_format;
+mod os_store;
+mod plugin;
+mod policy;
+mod proxy;
+mod rate_limit;
+mod report_signature;
+mod run_state;
+mod s3;
+mod sigv4;
+mod sink;
+mod snooze;
+mod ssh;
+mod ssllabs;
+mod starttls;
+mod status_page;
+mod target;
+mod trust_store;
+mod vault;
+mod zone;
@@ -0,0 +1,187 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use hickory_resolver::Resolver;
+use serde::Serialize;
+use tracing::debug;
+
+/// Mode of MTA-STS policy, RFC 8461 section 5
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Senders must not deliver to mail servers failing policy
+    Enforce,
+    /// Senders report but still deliver to mail servers failing policy
+    Testing,
+    /// Policy is withdrawn
+    None,
+}
+
+/// MTA-STS policy of a mail domain, RFC 8461 section 3.2
+#[derive(Debug, PartialEq, Serialize)]
+pub struct MtaStsPolicy {
+    pub mode: Mode,
+    /// Patterns of mail servers allowed, e.g. mail.example.com or *.example.net
+    pub mx: Vec<String>,
+    /// Seconds senders cache policy for
+    pub max_age: u64,
+}
+
+impl FromStr for MtaStsPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut version = None;
+        let mut mode = None;
+        let mut mx = vec![];
+        let mut max_age = None;
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (key, value) = line
+                .split_once(':')
+                .with_context(|| format!("invalid line in MTA-STS policy: {0}", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "version" => version = Some(value.to_string()),
+                "mode" => {
+                    mode = Some(match value {
+                        "enforce" => Mode::Enforce,
+                        "testing" => Mode::Testing,
+                        "none" => Mode::None,
+                        _ => bail!("unknown MTA-STS mode {0}", value),
+                    })
+                }
+                "mx" => mx.push(value.to_lowercase()),
+                "max_age" => {
+                    max_age = Some(value.parse().context("invalid max_age of MTA-STS policy")?)
+                }
+                // unknown fields are ignored for extension
+                _ => {}
+            }
+        }
+        if version.as_deref() != Some("STSv1") {
+            bail!("MTA-STS policy must be version STSv1");
+        }
+        let mode = mode.context("MTA-STS policy has no mode")?;
+        if mode != Mode::None && mx.is_empty() {
+            bail!("MTA-STS policy has no mx");
+        }
+        Ok(MtaStsPolicy {
+            mode,
+            mx,
+            max_age: max_age.context("MTA-STS policy has no max_age")?,
+        })
+    }
+}
+
+impl MtaStsPolicy {
+    /// Fetch policy of mail domain from `https://mta-sts.<domain>/.well-known/mta-sts.txt`
+    pub fn fetch(domain_name: &str) -> anyhow::Result<Self> {
+        let url = format!("https://mta-sts.{0}/.well-known/mta-sts.txt", domain_name);
+        // redirects must not be followed, RFC 8461 section 3.3
+        let client = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let response = client
+            .get(&url)
+            .send()
+            .with_context(|| format!("failed to fetch {0}", url))?;
+        if response.status() != reqwest::StatusCode::OK {
+            bail!("failed to fetch {0}: {1}", url, response.status());
+        }
+        let body = response
+            .text()
+            .with_context(|| format!("failed to fetch {0}", url))?;
+        debug!(url, body = %body, "fetched MTA-STS policy");
+        body.parse()
+    }
+
+    /// Whether mail server is allowed by mx patterns, a wildcard matches exactly one label
+    ///
+    /// ```
+    /// # use hcc::MtaStsPolicy;
+    /// let policy: MtaStsPolicy = "version: STSv1\nmode: enforce\nmx: *.example.net\nmax_age: 86400"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert!(policy.allows("mx1.example.net"));
+    /// assert!(!policy.allows("mx1.eu.example.net"));
+    /// ```
+    pub fn allows(&self, mail_server: &str) -> bool {
+        let mail_server = mail_server.trim_end_matches('.').to_lowercase();
+        self.mx
+            .iter()
+            .any(|pattern| match pattern.strip_prefix("*.") {
+                Some(suffix) => mail_server
+                    .split_once('.')
+                    .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+                None => *pattern == mail_server,
+            })
+    }
+
+    /// Problems of mail server under policy, certificate name and trust are already verified by
+    /// handshake
+    pub fn findings(&self, domain_name: &str, mail_server: &str) -> Vec<String> {
+        let mut findings = vec![];
+        if self.mode != Mode::None && !self.allows(mail_server) {
+            findings.push(format!(
+                "{0} is not allowed by MTA-STS policy of {1} in {2:?} mode",
+                mail_server, domain_name, self.mode
+            ));
+        }
+        findings
+    }
+}
+
+/// Whether `_mta-sts` TXT record announces policy, senders never fetch policy without it
+pub fn has_mta_sts_record(domain_name: &str) -> anyhow::Result<bool> {
+    let resolver = Resolver::from_system_conf().context("failed to read DNS configuration")?;
+    let name = format!("_mta-sts.{0}", domain_name);
+    let lookup = match resolver.txt_lookup(name.as_str()) {
+        Ok(lookup) => lookup,
+        Err(e) => {
+            debug!(name, error = %e, "no TXT record");
+            return Ok(false);
+        }
+    };
+    Ok(lookup
+        .iter()
+        .any(|txt| txt.to_string().starts_with("v=STSv1")))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mta_sts::{Mode, MtaStsPolicy};
+
+    #[test]
+    fn test_parse() {
+        let policy: MtaStsPolicy =
+            "version: STSv1\r\nmode: enforce\r\nmx: mail.example.com\r\nmx: *.example.net\r\nmax_age: 604800\r\n"
+                .parse()
+                .unwrap();
+        assert_eq!(Mode::Enforce, policy.mode);
+        assert_eq!(vec!["mail.example.com", "*.example.net"], policy.mx);
+        assert_eq!(604800, policy.max_age);
+
+        assert!("mode: enforce\nmx: a\nmax_age: 1"
+            .parse::<MtaStsPolicy>()
+            .is_err());
+        assert!("version: STSv1\nmode: enforce\nmax_age: 1"
+            .parse::<MtaStsPolicy>()
+            .is_err());
+        assert!("version: STSv1\nmode: none\nmax_age: 1"
+            .parse::<MtaStsPolicy>()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_findings() {
+        let policy: MtaStsPolicy =
+            "version: STSv1\nmode: testing\nmx: mail.example.com\nmx: *.example.net\nmax_age: 1"
+                .parse()
+                .unwrap();
+        assert!(policy.allows("MAIL.example.com."));
+        assert!(policy.allows("mx1.example.net"));
+        assert!(!policy.allows("example.net"));
+        assert!(policy.findings("example.com", "mx1.example.net").is_empty());
+        assert_eq!(1, policy.findings("example.com", "mx.example.org").len());
+    }
+}
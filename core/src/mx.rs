@@ -0,0 +1,71 @@
+use anyhow::{bail, Context};
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::Resolver;
+use tracing::debug;
+
+/// Ports of SMTP, SMTPS i.e. SMTP over implicit TLS, and submission
+pub const SMTP_PORTS: [u16; 3] = [25, 465, 587];
+
+/// Port of SMTP over implicit TLS, other SMTP ports start TLS with STARTTLS command
+pub const SMTPS_PORT: u16 = 465;
+
+/// Mail servers of domain name by MX preference, or domain name itself when it has no MX record
+pub fn mail_servers(domain_name: &str) -> anyhow::Result<Vec<String>> {
+    let resolver = Resolver::from_system_conf().context("failed to read DNS configuration")?;
+    let records = match resolver.mx_lookup(domain_name) {
+        Ok(lookup) => lookup
+            .iter()
+            .map(|mx| (mx.preference(), mx.exchange().to_utf8()))
+            .collect(),
+        Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => vec![],
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to look up MX of {0}", domain_name))
+        }
+    };
+    debug!(domain_name, ?records, "MX records");
+    order_exchanges(domain_name, records)
+}
+
+/// Sort exchanges by preference, fall back to domain name itself without MX record, RFC 5321
+/// section 5.1
+fn order_exchanges(
+    domain_name: &str,
+    mut records: Vec<(u16, String)>,
+) -> anyhow::Result<Vec<String>> {
+    if records.is_empty() {
+        return Ok(vec![domain_name.to_string()]);
+    }
+    records.sort();
+    let exchanges: Vec<String> = records
+        .into_iter()
+        .map(|(_, exchange)| exchange.trim_end_matches('.').to_string())
+        .filter(|exchange| !exchange.is_empty())
+        .collect();
+    // null MX, RFC 7505
+    if exchanges.is_empty() {
+        bail!("{0} accepts no mail", domain_name);
+    }
+    Ok(exchanges)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mx::order_exchanges;
+
+    #[test]
+    fn test_order_exchanges() {
+        let records = vec![
+            (20, "mx2.example.com.".to_string()),
+            (10, "mx1.example.com.".to_string()),
+        ];
+        assert_eq!(
+            vec!["mx1.example.com", "mx2.example.com"],
+            order_exchanges("example.com", records).unwrap()
+        );
+        assert_eq!(
+            vec!["example.com"],
+            order_exchanges("example.com", vec![]).unwrap()
+        );
+        assert!(order_exchanges("example.com", vec![(0, ".".to_string())]).is_err());
+    }
+}
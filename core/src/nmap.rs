@@ -0,0 +1,132 @@
+use anyhow::Context;
+use roxmltree::{Document, Node, ParsingOptions};
+use tracing::debug;
+
+use crate::target::Target;
+
+/// Names of services nmap detects as TLS besides those tunneled through SSL
+const TLS_SERVICES: &[&str] = &[
+    "https", "ssl", "imaps", "pop3s", "smtps", "ldaps", "ftps", "ircs", "xmpps",
+];
+
+/// Open TLS ports in XML output of nmap or masscan, addressed by hostname when scan has one so
+/// certificate could be verified, by address otherwise
+///
+/// Ports with service detected are TLS ports when tunneled through SSL or named as TLS service
+/// e.g. https, ports without service detection e.g. of masscan are all taken.
+///
+/// ```
+/// # use hcc::nmap_targets;
+/// let xml = r#"<nmaprun><host><address addr="10.0.0.1" addrtype="ipv4"/>
+///   <hostnames><hostname name="intranet.example.com" type="user"/></hostnames>
+///   <ports><port protocol="tcp" portid="443"><state state="open"/></port></ports>
+/// </host></nmaprun>"#;
+/// let targets = nmap_targets(xml).unwrap();
+/// assert_eq!("intranet.example.com", targets[0].to_string());
+/// ```
+pub fn nmap_targets(xml: &str) -> anyhow::Result<Vec<Target>> {
+    // nmap declares DOCTYPE in its output
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc = Document::parse_with_options(xml, options).context("failed to parse XML of scan")?;
+    let mut targets = vec![];
+    for host in doc.descendants().filter(|n| n.has_tag_name("host")) {
+        let address = host
+            .children()
+            .filter(|n| n.has_tag_name("address"))
+            .find(|n| matches!(n.attribute("addrtype"), Some("ipv4") | Some("ipv6")))
+            .and_then(|n| n.attribute("addr"));
+        let hostname = host
+            .descendants()
+            .filter(|n| n.has_tag_name("hostname"))
+            // name given by user comes before name by reverse DNS
+            .min_by_key(|n| n.attribute("type") != Some("user"))
+            .and_then(|n| n.attribute("name"));
+        let name = match hostname.or(address) {
+            Some(name) => name,
+            None => continue,
+        };
+        for port in host.descendants().filter(|n| n.has_tag_name("port")) {
+            if !is_open_tls(&port) {
+                continue;
+            }
+            let portid = port.attribute("portid").unwrap_or_default();
+            let port = portid
+                .parse()
+                .with_context(|| format!("invalid port {0} of {1}", portid, name))?;
+            targets.push(Target {
+                host: name.to_string(),
+                port,
+            });
+        }
+    }
+    debug!(count = targets.len(), "targets in scan");
+    Ok(targets)
+}
+
+fn is_open_tls(port: &Node) -> bool {
+    let child = |tag: &str| port.children().find(|n| n.has_tag_name(tag));
+    let open = child("state").and_then(|n| n.attribute("state")) == Some("open");
+    let tcp = port.attribute("protocol").unwrap_or("tcp") == "tcp";
+    let tls = match child("service") {
+        Some(service) => {
+            service.attribute("tunnel") == Some("ssl")
+                || service
+                    .attribute("name")
+                    .is_some_and(|name| TLS_SERVICES.contains(&name))
+        }
+        None => true,
+    };
+    open && tcp && tls
+}
+
+#[cfg(test)]
+mod test {
+    use crate::nmap::nmap_targets;
+
+    #[test]
+    fn test_nmap_targets() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE nmaprun>
+<nmaprun scanner="nmap">
+  <host>
+    <status state="up"/>
+    <address addr="10.0.0.1" addrtype="ipv4"/>
+    <address addr="00:11:22:33:44:55" addrtype="mac"/>
+    <hostnames>
+      <hostname name="printer.lan" type="PTR"/>
+      <hostname name="printer.example.com" type="user"/>
+    </hostnames>
+    <ports>
+      <port protocol="tcp" portid="22"><state state="open"/><service name="ssh"/></port>
+      <port protocol="tcp" portid="443"><state state="open"/><service name="http" tunnel="ssl"/></port>
+      <port protocol="tcp" portid="636"><state state="open"/><service name="ldaps"/></port>
+      <port protocol="tcp" portid="8443"><state state="closed"/><service name="https"/></port>
+      <port protocol="udp" portid="4433"><state state="open"/></port>
+    </ports>
+  </host>
+  <host>
+    <address addr="2001:db8::1" addrtype="ipv6"/>
+    <ports>
+      <port protocol="tcp" portid="8443"><state state="open"/></port>
+    </ports>
+  </host>
+</nmaprun>"#;
+        let targets: Vec<String> = nmap_targets(xml)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            vec![
+                "printer.example.com",
+                "printer.example.com:636",
+                "[2001:db8::1]:8443"
+            ],
+            targets
+        );
+        assert!(nmap_targets("<nmaprun>").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use num_format::{Locale, ToFormattedString};
+
+/// How numbers are formatted in text output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberFormat {
+    /// Digits grouped by separator of locale e.g. 1,000 in English or 1.000 in German
+    Grouped(Locale),
+    /// Digits without separator, friendly to machines
+    Plain,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat::Grouped(Locale::en)
+    }
+}
+
+impl FromStr for NumberFormat {
+    type Err = anyhow::Error;
+
+    /// Parse locale name e.g. `de`, `de-CH` or POSIX locale e.g. `de_CH.UTF-8`, where `C` and
+    /// `POSIX` group no digits, unknown region falls back to language
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('_', "-");
+        if name == "C" || name == "POSIX" {
+            return Ok(NumberFormat::Plain);
+        }
+        let language = name.split('-').next().unwrap_or_default();
+        Locale::from_name(&name)
+            .or_else(|_| Locale::from_name(language))
+            .map(NumberFormat::Grouped)
+            .map_err(|_| anyhow!("unknown locale {0}", s))
+    }
+}
+
+impl NumberFormat {
+    /// Format number
+    ///
+    /// ```
+    /// # use hcc::NumberFormat;
+    /// let german: NumberFormat = "de_DE.UTF-8".parse().unwrap();
+    /// assert_eq!("1.000", german.format(1000));
+    /// assert_eq!("1000", NumberFormat::Plain.format(1000));
+    /// ```
+    pub fn format(&self, n: i64) -> String {
+        match self {
+            NumberFormat::Grouped(locale) => n.to_formatted_string(locale),
+            NumberFormat::Plain => n.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_format::Locale;
+
+    use crate::number_format::NumberFormat;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| s.parse::<NumberFormat>().unwrap();
+        assert_eq!(NumberFormat::Grouped(Locale::en), parse("en_US.UTF-8"));
+        assert_eq!(NumberFormat::Grouped(Locale::de_CH), parse("de-CH"));
+        assert_eq!(NumberFormat::Grouped(Locale::sr), parse("sr_RS@latin"));
+        assert_eq!(NumberFormat::Plain, parse("C.UTF-8"));
+        assert_eq!(NumberFormat::Plain, parse("POSIX"));
+        assert!("xx".parse::<NumberFormat>().is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!("1,234,567", NumberFormat::default().format(1_234_567));
+        assert_eq!("-1,000", NumberFormat::default().format(-1000));
+        assert_eq!("1234567", NumberFormat::Plain.format(1_234_567));
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{bail, Context};
+use tracing::debug;
+use x509_parser::parse_x509_certificate;
+
+use crate::check_client::parse_certificates;
+
+/// Store checked when none is given, personal store of machine where certificates of local
+/// services live
+#[cfg(windows)]
+pub const DEFAULT_STORE: &str = "LocalMachine\\My";
+/// Store checked when none is given, keychains in search list of user
+#[cfg(target_os = "macos")]
+pub const DEFAULT_STORE: &str = "keychain";
+/// Store checked when none is given, the first existing system CA bundle
+#[cfg(not(any(windows, target_os = "macos")))]
+pub const DEFAULT_STORE: &str = "system";
+
+/// Certificate store of operating system, SChannel store on Windows e.g. CurrentUser\My,
+/// keychain on macOS e.g. /Library/Keychains/System.keychain, and PEM bundle elsewhere e.g.
+/// /etc/ssl/certs/ca-certificates.crt
+pub struct OsStore {
+    name: String,
+}
+
+/// Certificate in certificate store of operating system
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoreCertificate {
+    /// Name of certificate in results, store and common name or subject of certificate e.g.
+    /// LocalMachine\My/intranet.example.com
+    pub name: String,
+    /// Certificate in DER format
+    pub der: Vec<u8>,
+}
+
+impl OsStore {
+    /// Store by name, default store of operating system if none is given
+    pub fn new(name: Option<&str>) -> Self {
+        OsStore {
+            name: name.unwrap_or(DEFAULT_STORE).to_string(),
+        }
+    }
+
+    /// Name of store
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Certificates in store, exported with tools shipped with operating system
+    pub fn certificates(&self) -> anyhow::Result<Vec<StoreCertificate>> {
+        let pem = self.export()?;
+        let certificates = parse_store(&self.name, &pem)?;
+        debug!(
+            store = self.name.as_str(),
+            count = certificates.len(),
+            "certificates in store"
+        );
+        Ok(certificates)
+    }
+
+    /// Certificates of SChannel store in PEM format, by certificate provider of PowerShell
+    #[cfg(windows)]
+    fn export(&self) -> anyhow::Result<Vec<u8>> {
+        let script = format!(
+            "Get-ChildItem -Path 'Cert:\\{0}' | ForEach-Object {{ \
+             '-----BEGIN CERTIFICATE-----'; \
+             [Convert]::ToBase64String($_.RawData, 'InsertLineBreaks'); \
+             '-----END CERTIFICATE-----' }}",
+            self.name.replace('\'', "''")
+        );
+        run(
+            "powershell.exe",
+            &["-NoProfile", "-NonInteractive", "-Command", &script],
+        )
+    }
+
+    /// Certificates of keychains in PEM format, by security command
+    #[cfg(target_os = "macos")]
+    fn export(&self) -> anyhow::Result<Vec<u8>> {
+        let mut args = vec!["find-certificate", "-a", "-p"];
+        if self.name != DEFAULT_STORE {
+            args.push(&self.name);
+        }
+        run("/usr/bin/security", &args)
+    }
+
+    /// Certificates of PEM bundle, system CA bundle by default
+    #[cfg(not(any(windows, target_os = "macos")))]
+    fn export(&self) -> anyhow::Result<Vec<u8>> {
+        use crate::trust_store::SYSTEM_BUNDLES;
+
+        let path = if self.name == DEFAULT_STORE {
+            match SYSTEM_BUNDLES
+                .iter()
+                .find(|p| std::path::Path::new(p).exists())
+            {
+                Some(path) => *path,
+                None => bail!("no system CA bundle found"),
+            }
+        } else {
+            &self.name
+        };
+        std::fs::read(path).with_context(|| format!("failed to read {0}", path))
+    }
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn run(program: &str, args: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run {0}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{0} exits with {1}: {2}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Certificates of store exported in PEM format, named by store and common name or subject
+fn parse_store(store: &str, pem: &[u8]) -> anyhow::Result<Vec<StoreCertificate>> {
+    // an empty store exports nothing, which is not a DER certificate either
+    if pem.iter().all(u8::is_ascii_whitespace) {
+        return Ok(vec![]);
+    }
+    let mut certificates = vec![];
+    for der in parse_certificates(pem)? {
+        let subject = match parse_x509_certificate(&der) {
+            Ok((_, cert)) => {
+                let subject = cert.subject();
+                subject
+                    .iter_common_name()
+                    .next()
+                    .and_then(|cn| cn.as_str().ok())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| subject.to_string())
+            }
+            Err(e) => bail!("invalid certificate in {0}: {1}", store, e),
+        };
+        certificates.push(StoreCertificate {
+            name: format!("{0}/{1}", store, subject),
+            der,
+        });
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::os_store::{parse_store, OsStore};
+
+    #[test]
+    fn test_parse_store() {
+        let pem = std::fs::read("fixtures/bundle.pem").unwrap();
+        let certificates = parse_store("LocalMachine\\My", &pem).unwrap();
+        assert!(certificates.len() > 1);
+        assert!(certificates[0].name.starts_with("LocalMachine\\My/"));
+        assert!(parse_store("keychain", b"\n").unwrap().is_empty());
+        assert!(parse_store("keychain", b"not a certificate").is_err());
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    #[test]
+    fn test_certificates() {
+        let store = OsStore::new(Some("fixtures/localhost.pem"));
+        let certificates = store.certificates().unwrap();
+        assert_eq!(1, certificates.len());
+        assert_eq!("fixtures/localhost.pem/localhost", certificates[0].name);
+        assert!(OsStore::new(Some("fixtures/missing.pem"))
+            .certificates()
+            .is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use tracing::{debug, info_span};
+
+use crate::check_result::{CheckResult, CheckResultJSON};
+use crate::sink::{Format, Sink};
+
+/// Formats built in, registered under their names
+const BUILTIN_FORMATS: &[&str] = &["text", "json", "ndjson", "prometheus", "influx", "ics"];
+
+/// Renders results into report e.g. in a format of in-house tooling
+pub trait Formatter: Send + Sync {
+    fn format(&self, results: &[CheckResult]) -> anyhow::Result<String>;
+}
+
+/// Delivers results somewhere e.g. to in-house chat or ticketing system
+pub trait Notifier: Send + Sync {
+    fn notify(&self, results: &[CheckResult]) -> anyhow::Result<()>;
+}
+
+impl Formatter for Sink {
+    fn format(&self, results: &[CheckResult]) -> anyhow::Result<String> {
+        self.render(results)
+    }
+}
+
+/// What external plugin does with results
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginKind {
+    /// Standard output of plugin is the report
+    Formatter,
+    /// Plugin delivers results itself, its standard output is discarded
+    Notifier,
+}
+
+/// External process plugin, which gets results as NDJSON i.e. one JSON object per line in
+/// format of `--schema` on standard input
+///
+/// ```toml
+/// [[plugins]]
+/// name = "csv"
+/// kind = "formatter"
+/// command = ["jq", "-r", "[.domain_name, .days] | @csv"]
+///
+/// [[plugins]]
+/// name = "teams"
+/// kind = "notifier"
+/// command = ["/usr/local/bin/notify-teams", "--channel", "certificates"]
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plugin {
+    /// Name to refer to plugin by e.g. in `--formatter` or `notifiers` of configuration file
+    pub name: String,
+    pub kind: PluginKind,
+    /// Command and its arguments, gets HCC_PLUGIN_NAME and HCC_PLUGIN_KIND in environment
+    pub command: Vec<String>,
+}
+
+impl Plugin {
+    /// Run command with results on standard input and return its standard output
+    fn run(&self, results: &[CheckResult]) -> anyhow::Result<String> {
+        let _span = info_span!("plugin", name = self.name.as_str()).entered();
+        let (program, args) = match self.command.split_first() {
+            Some(command) => command,
+            None => bail!("command of plugin {0} is empty", self.name),
+        };
+        let mut input = String::new();
+        for result in results {
+            input.push_str(&serde_json::to_string(&CheckResultJSON::new(result))?);
+            input.push('\n');
+        }
+        let kind = match self.kind {
+            PluginKind::Formatter => "formatter",
+            PluginKind::Notifier => "notifier",
+        };
+        debug!(program = program.as_str(), ?args, "run plugin");
+        let mut child = Command::new(program)
+            .args(args)
+            .env("HCC_PLUGIN_NAME", &self.name)
+            .env("HCC_PLUGIN_KIND", kind)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run plugin {0}", self.name))?;
+        // write from another thread, so plugin never blocks on full standard output
+        let mut stdin = child.stdin.take().context("no standard input of plugin")?;
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("failed to run plugin {0}", self.name))?;
+        // plugin may exit without reading all results, which is up to plugin
+        let _ = writer.join();
+        if !output.status.success() {
+            bail!(
+                "plugin {0} exits with {1}: {2}",
+                self.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        String::from_utf8(output.stdout)
+            .with_context(|| format!("output of plugin {0} is not UTF-8", self.name))
+    }
+}
+
+impl Formatter for Plugin {
+    fn format(&self, results: &[CheckResult]) -> anyhow::Result<String> {
+        self.run(results)
+    }
+}
+
+impl Notifier for Plugin {
+    fn notify(&self, results: &[CheckResult]) -> anyhow::Result<()> {
+        self.run(results).map(|_| ())
+    }
+}
+
+/// Formatters and notifiers by name, built-in formats and plugins in configuration file are
+/// registered, and library users may register their own
+pub struct PluginRegistry {
+    formatters: BTreeMap<String, Box<dyn Formatter>>,
+    notifiers: BTreeMap<String, Box<dyn Notifier>>,
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        let mut registry = PluginRegistry {
+            formatters: BTreeMap::new(),
+            notifiers: BTreeMap::new(),
+        };
+        for name in BUILTIN_FORMATS {
+            let format: Format = name.parse().expect("built-in format");
+            registry.register_formatter(name, Box::new(Sink::stdout(format)));
+        }
+        registry
+    }
+}
+
+impl PluginRegistry {
+    /// Registry with built-in formats and plugins, names of plugins must be unique and must not
+    /// shadow built-in formats
+    ///
+    /// ```
+    /// # use hcc::{Plugin, PluginKind, PluginRegistry};
+    /// let plugin = Plugin {
+    ///     name: "count".into(),
+    ///     kind: PluginKind::Formatter,
+    ///     command: vec!["wc".into(), "-l".into()],
+    /// };
+    /// let registry = PluginRegistry::new(&[plugin]).unwrap();
+    /// assert!(registry.formatter("count").is_ok());
+    /// assert!(registry.formatter("json").is_ok());
+    /// assert!(registry.notifier("count").is_err());
+    /// ```
+    pub fn new(plugins: &[Plugin]) -> anyhow::Result<Self> {
+        let mut registry = PluginRegistry::default();
+        for plugin in plugins {
+            if registry.formatters.contains_key(&plugin.name)
+                || registry.notifiers.contains_key(&plugin.name)
+            {
+                bail!("plugin {0} is defined more than once", plugin.name);
+            }
+            match plugin.kind {
+                PluginKind::Formatter => {
+                    registry.register_formatter(&plugin.name, Box::new(plugin.clone()))
+                }
+                PluginKind::Notifier => {
+                    registry.register_notifier(&plugin.name, Box::new(plugin.clone()))
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Register formatter under name, replacing formatter registered before
+    pub fn register_formatter(&mut self, name: &str, formatter: Box<dyn Formatter>) {
+        self.formatters.insert(name.to_string(), formatter);
+    }
+
+    /// Register notifier under name, replacing notifier registered before
+    pub fn register_notifier(&mut self, name: &str, notifier: Box<dyn Notifier>) {
+        self.notifiers.insert(name.to_string(), notifier);
+    }
+
+    /// Formatter registered under name
+    pub fn formatter(&self, name: &str) -> anyhow::Result<&dyn Formatter> {
+        match self.formatters.get(name) {
+            Some(formatter) => Ok(formatter.as_ref()),
+            None => bail!(
+                "unknown formatter {0}, expect one of {1}",
+                name,
+                names(&self.formatters)
+            ),
+        }
+    }
+
+    /// Notifier registered under name
+    pub fn notifier(&self, name: &str) -> anyhow::Result<&dyn Notifier> {
+        match self.notifiers.get(name) {
+            Some(notifier) => Ok(notifier.as_ref()),
+            None if self.notifiers.is_empty() => {
+                bail!("unknown notifier {0}, no notifier is defined", name)
+            }
+            None => bail!(
+                "unknown notifier {0}, expect one of {1}",
+                name,
+                names(&self.notifiers)
+            ),
+        }
+    }
+}
+
+fn names<T>(map: &BTreeMap<String, T>) -> String {
+    map.keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::check_result::CheckResult;
+    use crate::plugin::{Plugin, PluginKind, PluginRegistry};
+
+    fn plugin(name: &str, kind: PluginKind, script: &str) -> Plugin {
+        Plugin {
+            name: name.into(),
+            kind,
+            command: vec!["sh".into(), "-c".into(), script.into()],
+        }
+    }
+
+    #[test]
+    fn test_formatter() {
+        let results = [
+            CheckResult {
+                domain_name: "example.com",
+                ..Default::default()
+            },
+            CheckResult {
+                domain_name: "example.org",
+                ..Default::default()
+            },
+        ];
+        let plugins = [plugin(
+            "lines",
+            PluginKind::Formatter,
+            r#"echo "$HCC_PLUGIN_KIND $(wc -l)""#,
+        )];
+        let registry = PluginRegistry::new(&plugins).unwrap();
+        let formatter = registry.formatter("lines").unwrap();
+        assert_eq!("formatter 2\n", formatter.format(&results).unwrap());
+        let json = registry.formatter("json").unwrap();
+        assert!(json.format(&results).unwrap().contains("example.org"));
+        assert!(registry.formatter("yaml").is_err());
+    }
+
+    #[test]
+    fn test_notifier() {
+        let plugins = [
+            plugin("ok", PluginKind::Notifier, "cat > /dev/null"),
+            plugin("fail", PluginKind::Notifier, "echo oops >&2; exit 1"),
+        ];
+        let registry = PluginRegistry::new(&plugins).unwrap();
+        let results = [CheckResult::default()];
+        assert!(registry.notifier("ok").unwrap().notify(&results).is_ok());
+        let e = registry.notifier("fail").unwrap().notify(&results);
+        assert!(format!("{0:#}", e.unwrap_err()).contains("oops"));
+        assert!(registry.notifier("json").is_err());
+
+        // names are unique across formatters and notifiers
+        let plugins = [
+            plugin("json", PluginKind::Notifier, "true"),
+            plugin("json", PluginKind::Formatter, "true"),
+        ];
+        assert!(PluginRegistry::new(&plugins[..1]).is_err());
+        assert!(PluginRegistry::new(&plugins[1..]).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::DateTime;
+use serde::Deserialize;
+
+use crate::check_result::san_covers;
+use crate::detail::CertificateDetail;
+
+/// Rules every checked certificate must follow, each violation is a finding naming its rule
+/// and degrades certificate to warning
+///
+/// ```toml
+/// [policy]
+/// allowed_issuers = ["Let's Encrypt", "DigiCert"]
+/// required_sans = ["example.com"]
+/// max_validity_days = 398
+/// forbidden_signature_algorithms = ["sha1WithRSAEncryption"]
+///
+/// [policy.min_key_sizes]
+/// RSA = 2048
+/// EC = 256
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Issuers allowed to issue certificates, matched against values of issuer name e.g.
+    /// Let's Encrypt, any issuer is allowed if empty
+    #[serde(default)]
+    pub allowed_issuers: Vec<String>,
+    /// Minimum size of public key in bits by key type e.g. RSA or EC
+    #[serde(default)]
+    pub min_key_sizes: BTreeMap<String, usize>,
+    /// Host names certificate must cover, wildcard SANs count
+    #[serde(default)]
+    pub required_sans: Vec<String>,
+    /// Longest validity period allowed in days, from not before to not after
+    pub max_validity_days: Option<i64>,
+    /// Signature algorithms certificate must not be signed with e.g. sha1WithRSAEncryption
+    #[serde(default)]
+    pub forbidden_signature_algorithms: Vec<String>,
+}
+
+/// Violation of rule of policy
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// Name of rule as in configuration file e.g. max_validity_days
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "violates policy {0}: {1}", self.rule, self.reason)
+    }
+}
+
+impl Policy {
+    /// Violations of policy by certificate, in order of rules
+    ///
+    /// ```
+    /// # use hcc::{CertificateDetail, Policy};
+    /// let policy = Policy {
+    ///     max_validity_days: Some(398),
+    ///     ..Default::default()
+    /// };
+    /// let detail = CertificateDetail {
+    ///     not_before: "2020-01-01T00:00:00+00:00".into(),
+    ///     not_after: "2022-01-01T00:00:00+00:00".into(),
+    ///     ..Default::default()
+    /// };
+    /// let violations = policy.violations(&detail);
+    /// assert_eq!("max_validity_days", violations[0].rule);
+    /// ```
+    pub fn violations(&self, detail: &CertificateDetail) -> Vec<Violation> {
+        let mut violations = vec![];
+        let mut violate = |rule, reason| violations.push(Violation { rule, reason });
+
+        if !self.allowed_issuers.is_empty() {
+            let issuer: Vec<&str> = detail.issuer.iter().map(|c| c.value.as_str()).collect();
+            let issuer = issuer.join(", ");
+            let allowed = self
+                .allowed_issuers
+                .iter()
+                .any(|allowed| issuer.to_lowercase().contains(&allowed.to_lowercase()));
+            if !allowed {
+                violate(
+                    "allowed_issuers",
+                    format!("issuer {0} is not allowed", issuer),
+                );
+            }
+        }
+
+        let key = &detail.public_key;
+        let min_size = self
+            .min_key_sizes
+            .iter()
+            .find(|(key_type, _)| key_type.eq_ignore_ascii_case(&key.key_type));
+        if let Some((_, min_size)) = min_size {
+            match key.size {
+                Some(size) if size < *min_size => violate(
+                    "min_key_sizes",
+                    format!(
+                        "{0} key of {1} bits is shorter than {2} bits",
+                        key.key_type, size, min_size
+                    ),
+                ),
+                Some(_) => {}
+                None => violate(
+                    "min_key_sizes",
+                    format!("size of {0} key is unknown", key.key_type),
+                ),
+            }
+        }
+
+        for name in &self.required_sans {
+            if !detail.sans.iter().any(|san| san_covers(san, name)) {
+                violate("required_sans", format!("SAN {0} is not covered", name));
+            }
+        }
+
+        if let Some(max_days) = self.max_validity_days {
+            let not_before = DateTime::parse_from_rfc3339(&detail.not_before);
+            let not_after = DateTime::parse_from_rfc3339(&detail.not_after);
+            if let (Ok(not_before), Ok(not_after)) = (not_before, not_after) {
+                let days = (not_after - not_before).num_days();
+                if days > max_days {
+                    violate(
+                        "max_validity_days",
+                        format!("validity of {0} days exceeds {1} days", days, max_days),
+                    );
+                }
+            }
+        }
+
+        let algorithm = &detail.signature_algorithm;
+        if self
+            .forbidden_signature_algorithms
+            .iter()
+            .any(|forbidden| forbidden.eq_ignore_ascii_case(algorithm))
+        {
+            violate(
+                "forbidden_signature_algorithms",
+                format!("signature algorithm {0} is forbidden", algorithm),
+            );
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::detail::{CertificateDetail, NameComponent, PublicKey};
+    use crate::policy::Policy;
+
+    fn detail() -> CertificateDetail {
+        CertificateDetail {
+            issuer: vec![
+                NameComponent {
+                    name: "CN".into(),
+                    value: "R3".into(),
+                },
+                NameComponent {
+                    name: "O".into(),
+                    value: "Let's Encrypt".into(),
+                },
+            ],
+            not_before: "2021-01-01T00:00:00+00:00".into(),
+            not_after: "2021-04-01T00:00:00+00:00".into(),
+            sans: vec!["*.example.com".into()],
+            public_key: PublicKey {
+                key_type: "RSA".into(),
+                size: Some(2048),
+                curve: None,
+            },
+            signature_algorithm: "sha256WithRSAEncryption".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_violations() {
+        let policy: Policy = toml::from_str(
+            r#"
+            allowed_issuers = ["let's encrypt"]
+            required_sans = ["api.example.com"]
+            max_validity_days = 398
+            forbidden_signature_algorithms = ["sha1WithRSAEncryption"]
+
+            [min_key_sizes]
+            rsa = 2048
+            "#,
+        )
+        .unwrap();
+        assert!(policy.violations(&detail()).is_empty());
+
+        let strict = Policy {
+            allowed_issuers: vec!["DigiCert".into()],
+            required_sans: vec!["example.com".into()],
+            max_validity_days: Some(30),
+            forbidden_signature_algorithms: vec!["sha256WithRSAEncryption".into()],
+            min_key_sizes: vec![("RSA".to_string(), 3072)].into_iter().collect(),
+        };
+        let violations: Vec<String> = strict
+            .violations(&detail())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            vec![
+                "violates policy allowed_issuers: issuer R3, Let's Encrypt is not allowed",
+                "violates policy min_key_sizes: RSA key of 2048 bits is shorter than 3072 bits",
+                "violates policy required_sans: SAN example.com is not covered",
+                "violates policy max_validity_days: validity of 90 days exceeds 30 days",
+                "violates policy forbidden_signature_algorithms: signature algorithm sha256WithRSAEncryption is forbidden",
+            ],
+            violations
+        );
+    }
+}
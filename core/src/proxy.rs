@@ -0,0 +1,80 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+use anyhow::bail;
+use tracing::debug;
+
+use crate::target::Target;
+
+/// Longest response head of proxy accepted
+const MAX_HEAD: usize = 8192;
+
+/// Ask HTTP proxy to tunnel connection to target with CONNECT method, RFC 9110 section 9.3.6,
+/// handshake with target could start once this returns
+pub(crate) fn tunnel(sock: &mut TcpStream, target: &Target) -> anyhow::Result<()> {
+    let authority = if target.host.contains(':') {
+        format!("[{0}]:{1}", target.host, target.port)
+    } else {
+        format!("{0}:{1}", target.host, target.port)
+    };
+    write!(sock, "CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", authority)?;
+
+    // read byte by byte, so no byte of target after response head is consumed
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0];
+        if sock.read(&mut byte)? == 0 {
+            bail!("proxy closed connection");
+        }
+        head.push(byte[0]);
+        if head.len() > MAX_HEAD {
+            bail!("response head of proxy exceeds {0} bytes", MAX_HEAD);
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status = head.lines().next().unwrap_or_default();
+    debug!(status, "proxy response");
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => bail!("proxy rejects CONNECT: {0}", status),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use crate::proxy::tunnel;
+
+    /// Fake HTTP proxy answering CONNECT request with status line
+    fn proxy_server(status: &'static str) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(sock.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!("CONNECT [2001:db8::1]:443 HTTP/1.1\r\n", line);
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            write!(sock, "{0}\r\nProxy-Agent: test\r\n\r\n", status).unwrap();
+        });
+        TcpStream::connect(addr).unwrap()
+    }
+
+    #[test]
+    fn test_tunnel() {
+        let target = "[2001:db8::1]".parse().unwrap();
+        let mut sock = proxy_server("HTTP/1.1 200 Connection established");
+        tunnel(&mut sock, &target).unwrap();
+
+        let mut sock = proxy_server("HTTP/1.1 407 Proxy Authentication Required");
+        let e = tunnel(&mut sock, &target).unwrap_err();
+        assert!(e.to_string().contains("407"), "{0}", e);
+    }
+}
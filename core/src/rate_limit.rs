@@ -0,0 +1,106 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+/// Token bucket limiting how often checks start, shared by all checks of a client so it holds
+/// however many checks run at once
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    /// Allow `rate` checks per second on average, and up to `burst` checks at once
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        RateLimiter {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Allow one check per `delay`, i.e. pause between consecutive checks
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use hcc::RateLimiter;
+    /// let limiter = RateLimiter::delay(Duration::from_millis(500));
+    /// limiter.acquire(); // returns immediately
+    /// limiter.acquire(); // returns after 500 milliseconds
+    /// ```
+    pub fn delay(delay: Duration) -> Self {
+        Self::new(1.0 / delay.as_secs_f64(), 1)
+    }
+
+    /// Block until a check could start
+    pub fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            debug!(wait_ms = wait.as_millis() as u64, "rate limited");
+            thread::sleep(wait);
+        }
+    }
+
+    /// Take one token, possibly ahead of time, and return how long to wait until it's due
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let refill = now
+            .saturating_duration_since(bucket.updated_at)
+            .as_secs_f64()
+            * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst) - 1.0;
+        bucket.updated_at = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::rate_limit::RateLimiter;
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new(10.0, 2);
+        let now = Instant::now();
+        assert_eq!(Duration::ZERO, limiter.reserve(now));
+        assert_eq!(Duration::ZERO, limiter.reserve(now));
+        assert_eq!(Duration::from_millis(100), limiter.reserve(now));
+        assert_eq!(Duration::from_millis(200), limiter.reserve(now));
+
+        // bucket refills no more than burst
+        let later = now + Duration::from_secs(10);
+        assert_eq!(Duration::ZERO, limiter.reserve(later));
+        assert_eq!(Duration::ZERO, limiter.reserve(later));
+        assert_eq!(Duration::from_millis(100), limiter.reserve(later));
+    }
+
+    #[test]
+    fn test_delay() {
+        let limiter = RateLimiter::delay(Duration::from_millis(50));
+        let now = Instant::now();
+        limiter.acquire();
+        limiter.acquire();
+        limiter.acquire();
+        assert!(now.elapsed() >= Duration::from_millis(100));
+    }
+}
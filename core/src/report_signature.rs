@@ -0,0 +1,250 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use ring::digest::{digest, SHA256};
+use ring::signature::{self, Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+
+use crate::sink::Format;
+
+/// Prefix of trailing line carrying signature of reports other than JSON
+pub const SIGNATURE_PREFIX: &str = "# hcc-signature ";
+
+/// Context signed along with report, so signature of report is never valid for anything else
+const CONTEXT: &[u8] = b"hcc-report-v1\n";
+
+/// DER prefix of Ed25519 public key in SubjectPublicKeyInfo, followed by 32 bytes of key
+const SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Signer of reports with Ed25519 key, so reports are evidence of what checks found
+pub struct ReportSigner {
+    key_pair: Ed25519KeyPair,
+}
+
+/// Signature of report, embedded in JSON reports and trailing other reports
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReportSignature {
+    /// Always ed25519
+    pub algorithm: String,
+    /// ID of key i.e. the first 8 bytes of SHA-256 of public key in hex
+    pub key_id: String,
+    /// Signature in base64
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedJson {
+    results: serde_json::Value,
+    signature: ReportSignature,
+}
+
+impl ReportSigner {
+    /// Signer with Ed25519 private key in PKCS#8 PEM e.g. of `openssl genpkey -algorithm
+    /// ed25519`
+    pub fn from_pem(pem: &[u8]) -> anyhow::Result<Self> {
+        let der = pem_contents(pem, "PRIVATE KEY")?;
+        let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+            .map_err(|_| anyhow::anyhow!("not an Ed25519 private key"))?;
+        Ok(ReportSigner { key_pair })
+    }
+
+    /// Signer with Ed25519 private key in PKCS#8 PEM file
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let pem = std::fs::read(path).with_context(|| format!("failed to read {0:?}", path))?;
+        Self::from_pem(&pem).with_context(|| format!("failed to load key {0:?}", path))
+    }
+
+    /// ID of key, see [`ReportSignature::key_id`]
+    pub fn key_id(&self) -> String {
+        key_id(self.key_pair.public_key().as_ref())
+    }
+
+    /// Public key in PEM, to hand out to whoever verifies reports
+    pub fn public_key_pem(&self) -> String {
+        let mut der = SPKI_PREFIX.to_vec();
+        der.extend_from_slice(self.key_pair.public_key().as_ref());
+        format!(
+            "-----BEGIN PUBLIC KEY-----\n{0}\n-----END PUBLIC KEY-----\n",
+            base64::encode(der)
+        )
+    }
+
+    fn signature(&self, canonical: &str) -> ReportSignature {
+        let mut message = CONTEXT.to_vec();
+        message.extend_from_slice(canonical.as_bytes());
+        ReportSignature {
+            algorithm: "ed25519".to_string(),
+            key_id: self.key_id(),
+            value: base64::encode(self.key_pair.sign(&message)),
+        }
+    }
+
+    /// Sign report rendered in format, JSON report is wrapped as `{"results": ..., "signature":
+    /// {...}}`, others are followed by a line of signature
+    ///
+    /// ```
+    /// # use hcc::{verify_report, Format, ReportSigner};
+    /// let signer = ReportSigner::from_file("fixtures/report_key.pem".as_ref()).unwrap();
+    /// let report = signer.sign(Format::Text, "[OK] example.com\n").unwrap();
+    /// let public_key = signer.public_key_pem();
+    /// assert!(verify_report(&report, public_key.as_bytes()).is_ok());
+    /// ```
+    pub fn sign(&self, format: Format, report: &str) -> anyhow::Result<String> {
+        if format == Format::Json {
+            let results: serde_json::Value = serde_json::from_str(report)?;
+            let signature = self.signature(&serde_json::to_string(&results)?);
+            let signed = SignedJson { results, signature };
+            return Ok(format!("{0}\n", serde_json::to_string(&signed)?));
+        }
+        let signature = self.signature(&canonical_text(report));
+        let mut signed = report.to_string();
+        if !signed.is_empty() && !signed.ends_with('\n') {
+            signed.push('\n');
+        }
+        signed.push_str(&format!(
+            "{0}{1} {2} {3}\n",
+            SIGNATURE_PREFIX, signature.algorithm, signature.key_id, signature.value
+        ));
+        Ok(signed)
+    }
+}
+
+/// Verify signed report against Ed25519 public key in PEM e.g. of `openssl pkey -pubout`,
+/// signature of report is returned when it's intact
+pub fn verify_report(report: &str, public_key: &[u8]) -> anyhow::Result<ReportSignature> {
+    let der = pem_contents(public_key, "PUBLIC KEY")?;
+    if der.len() != SPKI_PREFIX.len() + 32 || !der.starts_with(SPKI_PREFIX) {
+        bail!("not an Ed25519 public key");
+    }
+    let public_key = &der[SPKI_PREFIX.len()..];
+
+    let (canonical, signature) = match serde_json::from_str::<SignedJson>(report) {
+        Ok(signed) => (serde_json::to_string(&signed.results)?, signed.signature),
+        Err(_) => {
+            let body = report.trim_end();
+            let (body, line) = match body.rfind('\n') {
+                Some(i) => (&body[..i + 1], &body[i + 1..]),
+                None => ("", body),
+            };
+            let fields: Vec<&str> = match line.strip_prefix(SIGNATURE_PREFIX) {
+                Some(line) => line.split_whitespace().collect(),
+                None => bail!("report is not signed"),
+            };
+            if fields.len() != 3 {
+                bail!("malformed signature {0:?}", line);
+            }
+            let signature = ReportSignature {
+                algorithm: fields[0].to_string(),
+                key_id: fields[1].to_string(),
+                value: fields[2].to_string(),
+            };
+            (canonical_text(body), signature)
+        }
+    };
+    if signature.algorithm != "ed25519" {
+        bail!("unsupported algorithm {0}", signature.algorithm);
+    }
+    if signature.key_id != key_id(public_key) {
+        bail!(
+            "report is signed by key {0}, not key {1}",
+            signature.key_id,
+            key_id(public_key)
+        );
+    }
+    let value = base64::decode(&signature.value).context("malformed signature")?;
+    let mut message = CONTEXT.to_vec();
+    message.extend_from_slice(canonical.as_bytes());
+    signature::UnparsedPublicKey::new(&signature::ED25519, public_key)
+        .verify(&message, &value)
+        .map_err(|_| anyhow::anyhow!("signature does not match report"))?;
+    Ok(signature)
+}
+
+fn key_id(public_key: &[u8]) -> String {
+    digest(&SHA256, public_key).as_ref()[..8]
+        .iter()
+        .map(|b| format!("{0:02x}", b))
+        .collect()
+}
+
+/// Report with line endings normalized and trailing whitespace removed, so signature survives
+/// conversion of line endings e.g. by editors or version control
+fn canonical_text(report: &str) -> String {
+    let lines: Vec<&str> = report.lines().map(str::trim_end).collect();
+    let mut canonical = lines.join("\n").trim_end().to_string();
+    canonical.push('\n');
+    canonical
+}
+
+/// DER contents of PEM block with label
+fn pem_contents(pem: &[u8], label: &str) -> anyhow::Result<Vec<u8>> {
+    let pem = std::str::from_utf8(pem).context("PEM is not UTF-8")?;
+    let begin = format!("-----BEGIN {0}-----", label);
+    let end = format!("-----END {0}-----", label);
+    let start = pem
+        .find(&begin)
+        .with_context(|| format!("no {0} in PEM", label))?;
+    let rest = &pem[start + begin.len()..];
+    let stop = rest
+        .find(&end)
+        .with_context(|| format!("unterminated {0} in PEM", label))?;
+    let body: String = rest[..stop].split_whitespace().collect();
+    base64::decode(body).with_context(|| format!("malformed {0} in PEM", label))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::report_signature::{verify_report, ReportSigner, SIGNATURE_PREFIX};
+    use crate::sink::Format;
+
+    fn signer() -> ReportSigner {
+        ReportSigner::from_file("fixtures/report_key.pem".as_ref()).unwrap()
+    }
+
+    fn public_key() -> Vec<u8> {
+        std::fs::read("fixtures/report_key.pub.pem").unwrap()
+    }
+
+    #[test]
+    fn test_sign_text() {
+        let signer = signer();
+        assert_eq!(signer.public_key_pem().into_bytes(), public_key());
+        let report = "[OK] example.com\n[WARNING] example.org\n";
+        let signed = signer.sign(Format::Text, report).unwrap();
+        assert!(signed.starts_with(report));
+        assert!(signed.lines().last().unwrap().starts_with(SIGNATURE_PREFIX));
+        let signature = verify_report(&signed, &public_key()).unwrap();
+        assert_eq!(signer.key_id(), signature.key_id);
+
+        // line endings do not matter, content does
+        let crlf = signed.replace('\n', "\r\n");
+        assert!(verify_report(&crlf, &public_key()).is_ok());
+        let tampered = signed.replace("WARNING", "OK");
+        assert!(verify_report(&tampered, &public_key()).is_err());
+        assert!(verify_report(report, &public_key()).is_err());
+    }
+
+    #[test]
+    fn test_sign_json() {
+        let signer = signer();
+        let report = r#"[{"domain_name":"example.com","days":30}]"#;
+        let signed = signer.sign(Format::Json, report).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&signed).unwrap();
+        assert_eq!("example.com", json["results"][0]["domain_name"]);
+        assert_eq!("ed25519", json["signature"]["algorithm"]);
+        assert!(verify_report(&signed, &public_key()).is_ok());
+
+        // order of keys does not matter, values do
+        let reordered = signed.replace(
+            r#"{"days":30,"domain_name":"example.com"}"#,
+            r#"{"domain_name":"example.com","days":30}"#,
+        );
+        assert_ne!(signed, reordered);
+        assert!(verify_report(&reordered, &public_key()).is_ok());
+        let tampered = signed.replace("30", "300");
+        assert!(verify_report(&tampered, &public_key()).is_err());
+        assert!(verify_report(&signed, b"not a key").is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::check_result::CheckResult;
+use crate::sink::write_atomic;
+
+/// Remaining days at which certificate enters another bucket besides grace period, so a
+/// certificate counting down is reported again as it crosses each of them
+pub const THRESHOLDS: &[i64] = &[30, 14, 7, 3, 1];
+
+/// Outcome of each domain name in the previous run, to report only what changed since
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunState {
+    #[serde(flatten)]
+    outcomes: BTreeMap<String, Outcome>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Outcome {
+    /// Exit code of state, see [`crate::CheckState::exit_code`]
+    state: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+    bucket: usize,
+}
+
+impl RunState {
+    /// Load state of previous run from JSON file, missing file is treated as first run
+    ///
+    /// ```
+    /// # use hcc::RunState;
+    /// let state = RunState::load("/nonexistent/state.json").unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let s = match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(RunState::default()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {0:?}", path)),
+        };
+        serde_json::from_str(&s).with_context(|| format!("failed to parse {0:?}", path))
+    }
+
+    /// Save state to JSON file atomically
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let s = serde_json::to_string_pretty(self)?;
+        write_atomic(path, s.as_bytes()).with_context(|| format!("failed to write {0:?}", path))
+    }
+
+    /// Whether state, certificate or bucket of remaining days of result changed since the
+    /// previous run, domain name never seen before counts as changed
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, RunState};
+    /// let mut state = RunState::default();
+    /// let result = CheckResult {
+    ///     domain_name: "example.com",
+    ///     days: 60,
+    ///     ..Default::default()
+    /// };
+    /// assert!(state.changed(&result, 7));
+    /// state.record(&result, 7);
+    /// assert!(!state.changed(&result, 7));
+    /// ```
+    pub fn changed(&self, result: &CheckResult, grace_in_days: i64) -> bool {
+        self.outcomes.get(&key(result)) != Some(&outcome(result, grace_in_days))
+    }
+
+    /// Remember outcome of result for the next run, skipped domain names keep their outcome
+    pub fn record(&mut self, result: &CheckResult, grace_in_days: i64) {
+        if result.is_skipped() {
+            return;
+        }
+        self.outcomes
+            .insert(key(result), outcome(result, grace_in_days));
+    }
+}
+
+/// Results of the same domain name seen from different locations are told apart
+pub(crate) fn key(result: &CheckResult) -> String {
+    match result.location {
+        Some(ref location) => format!("{0}@{1}", result.domain_name, location),
+        None => result.domain_name.to_string(),
+    }
+}
+
+fn outcome(result: &CheckResult, grace_in_days: i64) -> Outcome {
+    Outcome {
+        state: result.state.exit_code(),
+        fingerprint: result.fingerprint.clone(),
+        bucket: bucket(result.days, grace_in_days),
+    }
+}
+
+/// Number of thresholds and grace period remaining days are within
+pub(crate) fn bucket(days: i64, grace_in_days: i64) -> usize {
+    THRESHOLDS
+        .iter()
+        .chain(std::iter::once(&grace_in_days))
+        .filter(|threshold| days <= **threshold)
+        .count()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::check_result::{CheckResult, CheckState};
+    use crate::error::CheckError;
+    use crate::run_state::{bucket, RunState};
+
+    #[test]
+    fn test_bucket() {
+        assert_eq!(0, bucket(60, 7));
+        assert_eq!(1, bucket(30, 7));
+        assert_eq!(2, bucket(20, 21));
+        assert_eq!(bucket(10, 7), bucket(8, 7));
+        assert_ne!(bucket(8, 7), bucket(7, 7));
+    }
+
+    #[test]
+    fn test_changed() {
+        let mut state = RunState::default();
+        let result = CheckResult {
+            domain_name: "example.com",
+            state: CheckState::Ok,
+            days: 60,
+            fingerprint: Some("AB".into()),
+            ..Default::default()
+        };
+        state.record(&result, 7);
+        assert!(!state.changed(&result, 7));
+
+        // a day closer within the same bucket is no change
+        let closer = CheckResult {
+            days: 59,
+            ..result.clone()
+        };
+        assert!(!state.changed(&closer, 7));
+        let crossed = CheckResult {
+            days: 30,
+            ..result.clone()
+        };
+        assert!(state.changed(&crossed, 7));
+        let renewed = CheckResult {
+            fingerprint: Some("CD".into()),
+            ..result.clone()
+        };
+        assert!(state.changed(&renewed, 7));
+        let failed = CheckResult {
+            state: CheckState::Unknown,
+            ..result.clone()
+        };
+        assert!(state.changed(&failed, 7));
+        let elsewhere = CheckResult {
+            location: Some("eu-west".into()),
+            ..result.clone()
+        };
+        assert!(state.changed(&elsewhere, 7));
+        // skipped domain names are compared with their last outcome next time
+        let skipped = CheckResult {
+            state: CheckState::Unknown,
+            error: Some(CheckError::Skipped {
+                target: "example.com".into(),
+                reason: "run was interrupted".into(),
+            }),
+            ..result.clone()
+        };
+        state.record(&skipped, 7);
+        assert!(!state.changed(&result, 7));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        state.save(&path).unwrap();
+        let loaded = RunState::load(&path).unwrap();
+        assert!(!loaded.changed(&result, 7));
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{bail, Context};
+use chrono::Utc;
+
+use crate::sigv4::{sha256_hex, uri_encode, Signer};
+
+/// Endpoint of Amazon S3, other S3-compatible services e.g. MinIO or R2 need theirs
+pub const DEFAULT_ENDPOINT: &str = "https://s3.amazonaws.com";
+
+/// Bucket of S3-compatible object storage, objects are addressed in path style i.e.
+/// ENDPOINT/BUCKET/KEY so any endpoint works without DNS of bucket
+#[derive(Clone)]
+pub struct S3Bucket {
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+    signer: Signer,
+}
+
+impl S3Bucket {
+    /// Bucket at URL `s3://BUCKET[/PREFIX]` on endpoint, signed with access key and secret key
+    ///
+    /// ```
+    /// # use hcc::S3Bucket;
+    /// let endpoint = "https://minio.example.com";
+    /// let bucket = S3Bucket::new("s3://status/certs", endpoint, "us-east-1", "key", "secret");
+    /// assert_eq!(
+    ///     "https://minio.example.com/status/certs/index.html",
+    ///     bucket.unwrap().url("index.html")
+    /// );
+    /// ```
+    pub fn new(
+        url: &str,
+        endpoint: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> anyhow::Result<Self> {
+        let path = url
+            .strip_prefix("s3://")
+            .with_context(|| format!("{0} is not an s3://BUCKET[/PREFIX] URL", url))?;
+        let (bucket, prefix) = match path.split_once('/') {
+            Some((bucket, prefix)) => (bucket, prefix.trim_matches('/')),
+            None => (path, ""),
+        };
+        if bucket.is_empty() {
+            bail!("bucket is missing in {0}", url);
+        }
+        let prefix = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{0}/", prefix)
+        };
+        Ok(S3Bucket {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            prefix,
+            signer: Signer {
+                access_key: access_key.to_string(),
+                secret_key: secret_key.to_string(),
+                session_token: None,
+                region: region.to_string(),
+                service: "s3".to_string(),
+            },
+        })
+    }
+
+    /// Bucket at URL with credentials in AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and
+    /// optionally AWS_SESSION_TOKEN
+    pub fn from_env(url: &str, endpoint: &str, region: &str) -> anyhow::Result<Self> {
+        let signer = Signer::from_env(region, "s3")?;
+        let mut bucket = Self::new(
+            url,
+            endpoint,
+            region,
+            &signer.access_key,
+            &signer.secret_key,
+        )?;
+        bucket.signer = signer;
+        Ok(bucket)
+    }
+
+    /// URL of object with name under prefix of bucket
+    pub fn url(&self, name: &str) -> String {
+        format!("{0}{1}", self.endpoint, self.path(name))
+    }
+
+    fn path(&self, name: &str) -> String {
+        let key = format!("{0}{1}", self.prefix, name);
+        format!("/{0}/{1}", self.bucket, uri_encode(&key, false))
+    }
+
+    /// Upload object with name under prefix of bucket, replacing existing one
+    pub fn put(&self, name: &str, content_type: &str, body: &[u8]) -> anyhow::Result<()> {
+        let url = self.url(name);
+        let host = url
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .with_context(|| format!("invalid endpoint {0}", self.endpoint))?
+            .to_string();
+        let now = Utc::now();
+        let payload_hash = sha256_hex(body);
+        let headers = self.signer.headers(
+            &host,
+            &payload_hash,
+            &now,
+            &[("content-type", content_type)],
+        );
+        let authorization =
+            self.signer
+                .authorization("PUT", &self.path(name), &headers, &payload_hash, &now);
+        let mut request = reqwest::blocking::Client::new()
+            .put(&url)
+            .header("Authorization", authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| name != "host") {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        request
+            .body(body.to_vec())
+            .send()
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("failed to upload {0}", url))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::s3::S3Bucket;
+
+    #[test]
+    fn test_new() {
+        let bucket =
+            S3Bucket::new("s3://status", "https://s3.amazonaws.com/", "r", "k", "s").unwrap();
+        assert_eq!(
+            "https://s3.amazonaws.com/status/a.html",
+            bucket.url("a.html")
+        );
+        assert!(
+            S3Bucket::new("https://status", "https://s3.amazonaws.com", "r", "k", "s").is_err()
+        );
+        assert!(S3Bucket::new("s3:///prefix", "https://s3.amazonaws.com", "r", "k", "s").is_err());
+    }
+}
@@ -0,0 +1,760 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::net::UdpSocket;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use chrono::{Duration, TimeZone, Utc};
+
+use crate::check_result::{CheckResult, CheckResultJSON, CheckState, TextStyle};
+
+/// Format of output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// Human-readable lines
+    Text,
+    /// JSON object, or array of objects when there are multiple results
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Prometheus text exposition format
+    Prometheus,
+    /// StatsD gauges with domain name in metric name
+    Statsd,
+    /// StatsD gauges with domain name as DogStatsD tag
+    DogStatsd,
+    /// InfluxDB line protocol
+    Influx,
+    /// iCalendar with an all-day event on expiration date of each certificate
+    Ics,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    /// Parse format written to standard output or file, i.e. text, json, ndjson, prometheus,
+    /// influx or ics
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "prometheus" => Ok(Format::Prometheus),
+            "influx" => Ok(Format::Influx),
+            "ics" => Ok(Format::Ics),
+            _ => bail!("unknown format {0}", s),
+        }
+    }
+}
+
+/// Where output goes
+#[derive(Clone, Debug, PartialEq)]
+pub enum Destination {
+    /// Standard output
+    Stdout,
+    /// File, replaced atomically on each run
+    File(PathBuf),
+    /// Prometheus Pushgateway base URL e.g. http://localhost:9091
+    Pushgateway(String),
+    /// StatsD server as HOST:PORT, metrics are sent over UDP
+    Statsd(String),
+}
+
+/// Whether to colorize text output
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    /// Colorize when standard output is a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => bail!("unknown color choice {0}, expect auto, always or never", s),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether to colorize text printed to standard output
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Output sink, a pair of format and destination
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sink {
+    pub format: Format,
+    pub destination: Destination,
+    /// Colorize text by state of certificate, only for text format
+    pub color: bool,
+    /// How results are rendered, only for text format
+    pub style: TextStyle,
+    /// Days before expiration to remind of each event, only for ics format
+    pub alarms: Vec<u32>,
+}
+
+impl FromStr for Sink {
+    type Err = anyhow::Error;
+
+    /// Parse sink from `FORMAT[:PATH]` e.g. `text`, `json:results.json`, `ndjson`,
+    /// `prometheus:hcc.prom`, `influx`,
+    /// or `pushgateway:URL` e.g. `pushgateway:http://localhost:9091`,
+    /// or `statsd:HOST:PORT` and `dogstatsd:HOST:PORT` e.g. `dogstatsd:localhost:8125`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, destination) = match s.split_once(':') {
+            Some((format, destination)) => (format, Some(destination)),
+            None => (s, None),
+        };
+        match format {
+            "pushgateway" => {
+                let url = destination.context("pushgateway sink requires URL")?;
+                return Ok(Sink {
+                    format: Format::Prometheus,
+                    destination: Destination::Pushgateway(url.trim_end_matches('/').to_string()),
+                    color: false,
+                    style: TextStyle::default(),
+                    alarms: vec![],
+                });
+            }
+            "statsd" | "dogstatsd" => {
+                let addr = destination.context("statsd sink requires HOST:PORT")?;
+                return Ok(Sink::statsd(addr, format == "dogstatsd"));
+            }
+            _ => {}
+        }
+        let format = format
+            .parse()
+            .with_context(|| format!("unknown sink format {0}", format))?;
+        let destination = match destination {
+            None | Some("-") => Destination::Stdout,
+            Some("") => bail!("empty path in sink {0}", s),
+            Some(path) => Destination::File(PathBuf::from(path)),
+        };
+        Ok(Sink {
+            format,
+            destination,
+            color: false,
+            style: TextStyle::default(),
+            alarms: vec![],
+        })
+    }
+}
+
+impl Sink {
+    /// Sink printing format to standard output
+    pub fn stdout(format: Format) -> Self {
+        Sink {
+            format,
+            destination: Destination::Stdout,
+            color: false,
+            style: TextStyle::default(),
+            alarms: vec![],
+        }
+    }
+
+    /// Sink sending gauges to StatsD server at HOST:PORT, with DogStatsD tags if requested
+    pub fn statsd(addr: &str, dogstatsd: bool) -> Self {
+        Sink {
+            format: if dogstatsd {
+                Format::DogStatsd
+            } else {
+                Format::Statsd
+            },
+            destination: Destination::Statsd(addr.to_string()),
+            color: false,
+            style: TextStyle::default(),
+            alarms: vec![],
+        }
+    }
+
+    /// Whether sink could be written batch by batch during a long run
+    pub fn is_streaming(&self) -> bool {
+        self.destination == Destination::Stdout
+            && matches!(self.format, Format::Text | Format::Ndjson | Format::Influx)
+    }
+
+    /// Render results in format of sink
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, Sink};
+    /// let sink: Sink = "json".parse().unwrap();
+    /// sink.render(&[CheckResult::default()]).unwrap();
+    /// ```
+    pub fn render(&self, results: &[CheckResult]) -> anyhow::Result<String> {
+        match self.format {
+            Format::Text => Ok(render_text(results, self.color, &self.style)),
+            Format::Json => render_json(results),
+            Format::Ndjson => render_ndjson(results),
+            Format::Prometheus => Ok(render_prometheus(results)),
+            Format::Statsd => Ok(render_statsd(results, false)),
+            Format::DogStatsd => Ok(render_statsd(results, true)),
+            Format::Influx => Ok(render_influx(results)),
+            Format::Ics => Ok(render_ics(results, &self.alarms)),
+        }
+    }
+
+    /// Render results and write them to destination of sink
+    pub fn emit(&self, results: &[CheckResult]) -> anyhow::Result<()> {
+        self.write(&self.render(results)?)
+    }
+
+    /// Write rendered output to destination of sink
+    pub fn write(&self, s: &str) -> anyhow::Result<()> {
+        self.destination.write(s)
+    }
+}
+
+impl Destination {
+    /// Write rendered output to destination
+    pub fn write(&self, s: &str) -> anyhow::Result<()> {
+        match *self {
+            Destination::Stdout => print!("{0}", s),
+            Destination::File(ref path) => write_atomic(path, s.as_bytes())
+                .with_context(|| format!("failed to write {0:?}", path))?,
+            Destination::Pushgateway(ref url) => {
+                let url = format!("{0}/metrics/job/hcc", url);
+                reqwest::blocking::Client::new()
+                    .put(&url)
+                    .body(s.to_string())
+                    .send()
+                    .and_then(|r| r.error_for_status())
+                    .with_context(|| format!("failed to push metrics to {0}", url))?;
+            }
+            Destination::Statsd(ref addr) => {
+                let sock = UdpSocket::bind("0.0.0.0:0")?;
+                // one datagram per metric keeps each under MTU
+                for line in s.lines() {
+                    sock.send_to(line.as_bytes(), addr.as_str())
+                        .with_context(|| format!("failed to send metrics to {0}", addr))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a temporary file next to path then rename it to path, so readers never see partial
+/// content
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let name = path
+        .file_name()
+        .with_context(|| format!("{0:?} is not a file", path))?;
+    let tmp = path.with_file_name(format!(
+        ".{0}.{1}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let written = fs::File::create(&tmp).and_then(|mut f| {
+        f.write_all(contents)?;
+        f.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn render_text(results: &[CheckResult], color: bool, style: &TextStyle) -> String {
+    let mut s = String::new();
+    for result in results {
+        if color {
+            let code = match result.state {
+                CheckState::Ok => "32",
+                CheckState::Warning => "33",
+                CheckState::Expired | CheckState::Unknown => "31",
+            };
+            let _ = writeln!(s, "\x1b[{0}m{1}\x1b[0m", code, result.line_styled(style));
+        } else {
+            let _ = writeln!(s, "{0}", result.line_styled(style));
+        }
+    }
+    s
+}
+
+fn render_json(results: &[CheckResult]) -> anyhow::Result<String> {
+    let s = if results.len() == 1 {
+        serde_json::to_string(&CheckResultJSON::new(&results[0]))?
+    } else {
+        let json: Vec<CheckResultJSON> = results.iter().map(CheckResultJSON::new).collect();
+        serde_json::to_string(&json)?
+    };
+    Ok(format!("{0}\n", s))
+}
+
+fn render_ndjson(results: &[CheckResult]) -> anyhow::Result<String> {
+    let mut s = String::new();
+    for result in results {
+        s.push_str(&serde_json::to_string(&CheckResultJSON::new(result))?);
+        s.push('\n');
+    }
+    Ok(s)
+}
+
+fn render_prometheus(results: &[CheckResult]) -> String {
+    let mut s = String::new();
+    s.push_str("# HELP hcc_certificate_days Remaining days to the expiration date\n");
+    s.push_str("# TYPE hcc_certificate_days gauge\n");
+    for result in results {
+        let _ = writeln!(
+            s,
+            "hcc_certificate_days{{{0}}} {1}",
+            prometheus_labels(result),
+            result.days
+        );
+    }
+    s.push_str(
+        "# HELP hcc_certificate_chain_days Remaining days to the earliest expiration in chain\n",
+    );
+    s.push_str("# TYPE hcc_certificate_chain_days gauge\n");
+    for result in results {
+        if let Some(days) = result.chain_days() {
+            let _ = writeln!(
+                s,
+                "hcc_certificate_chain_days{{{0}}} {1}",
+                prometheus_labels(result),
+                days
+            );
+        }
+    }
+    s.push_str("# HELP hcc_certificate_ok Whether certificate is valid and not expiring soon\n");
+    s.push_str("# TYPE hcc_certificate_ok gauge\n");
+    for result in results {
+        let ok = matches!(result.state, CheckState::Ok) as u8;
+        let _ = writeln!(
+            s,
+            "hcc_certificate_ok{{{0}}} {1}",
+            prometheus_labels(result),
+            ok
+        );
+    }
+    s
+}
+
+/// Labels of result, tags are joined by comma in one label so series of a domain name keep the
+/// same label set
+fn prometheus_labels(result: &CheckResult) -> String {
+    // label values escape backslashes, double quotes and line feeds
+    let escape = |v: &str| {
+        v.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    };
+    let mut labels = format!("domain_name=\"{0}\"", escape(result.domain_name));
+    if !result.tags.is_empty() {
+        let _ = write!(labels, ",tags=\"{0}\"", escape(&result.tags.join(",")));
+    }
+    if let Some(ref location) = result.location {
+        let _ = write!(labels, ",location=\"{0}\"", escape(location));
+    }
+    labels
+}
+
+fn render_statsd(results: &[CheckResult], dogstatsd: bool) -> String {
+    let mut s = String::new();
+    for result in results {
+        let ok = matches!(result.state, CheckState::Ok) as u8;
+        let mut metrics = vec![("days", result.days), ("ok", ok as i64)];
+        if let Some(days) = result.chain_days() {
+            metrics.push(("chain_days", days));
+        }
+        for (name, value) in &metrics {
+            let _ = if dogstatsd {
+                let mut tags: String = result.tags.iter().map(|t| format!(",tag:{0}", t)).collect();
+                if let Some(ref location) = result.location {
+                    let _ = write!(tags, ",location:{0}", location);
+                }
+                writeln!(
+                    s,
+                    "hcc.certificate.{0}:{1}|g|#domain:{2}{3}",
+                    name, value, result.domain_name, tags
+                )
+            } else {
+                let domain_name = result.domain_name.replace(['.', ':'], "_");
+                writeln!(s, "hcc.certificate.{0}.{1}:{2}|g", name, domain_name, value)
+            };
+        }
+    }
+    s
+}
+
+fn render_influx(results: &[CheckResult]) -> String {
+    let mut s = String::new();
+    for result in results {
+        let ok = matches!(result.state, CheckState::Ok) as u8;
+        // tag values escape commas, equal signs and spaces
+        let escape = |v: &str| {
+            v.replace(',', "\\,")
+                .replace('=', "\\=")
+                .replace(' ', "\\ ")
+        };
+        let mut tags = format!("domain={0}", escape(result.domain_name));
+        if !result.tags.is_empty() {
+            let _ = write!(tags, ",tags={0}", escape(&result.tags.join(",")));
+        }
+        if let Some(ref location) = result.location {
+            let _ = write!(tags, ",location={0}", escape(location));
+        }
+        let chain_days = result
+            .chain_days()
+            .map(|days| format!(",chain_days={0}i", days))
+            .unwrap_or_default();
+        let _ = writeln!(
+            s,
+            "ssl_cert,{0} days={1}i{2},ok={3}i {4}",
+            tags,
+            result.days,
+            chain_days,
+            ok,
+            result.checked_at as i128 * 1_000_000_000
+        );
+    }
+    s
+}
+
+/// Calendar with an all-day event on expiration date of each certificate, results without
+/// certificate are skipped, events of the same certificate keep UID across runs so calendars
+/// update rather than duplicate them, RFC 5545
+fn render_ics(results: &[CheckResult], alarms: &[u32]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//hcc//Certificate expiration//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+    ];
+    for result in results.iter().filter(|r| r.not_after > 0) {
+        let not_after = Utc.timestamp(result.not_after, 0);
+        let summary = format!("Certificate of {0} expires", result.domain_name);
+        let mut description = format!("expires at {0}", not_after.to_rfc3339());
+        if !result.findings.is_empty() {
+            let _ = write!(description, "\n{0}", result.findings.join("\n"));
+        }
+        lines.extend(vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{0}-{1}@hcc", result.domain_name, result.not_after),
+            format!(
+                "DTSTAMP:{0}",
+                Utc.timestamp(result.checked_at, 0).format("%Y%m%dT%H%M%SZ")
+            ),
+            format!("DTSTART;VALUE=DATE:{0}", not_after.format("%Y%m%d")),
+            format!(
+                "DTEND;VALUE=DATE:{0}",
+                (not_after + Duration::days(1)).format("%Y%m%d")
+            ),
+            format!("SUMMARY:{0}", ics_escape(&summary)),
+            format!("DESCRIPTION:{0}", ics_escape(&description)),
+            "TRANSP:TRANSPARENT".to_string(),
+        ]);
+        for days in alarms {
+            lines.extend(vec![
+                "BEGIN:VALARM".to_string(),
+                "ACTION:DISPLAY".to_string(),
+                format!("DESCRIPTION:{0}", ics_escape(&summary)),
+                format!("TRIGGER:-P{0}D", days),
+                "END:VALARM".to_string(),
+            ]);
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    let mut s = String::new();
+    for line in lines {
+        s.push_str(&ics_fold(&line));
+        s.push_str("\r\n");
+    }
+    s
+}
+
+/// Escape backslashes, semicolons, commas and newlines in text value
+fn ics_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold line longer than 75 octets into continuation lines starting with a space, without
+/// splitting UTF-8 characters
+fn ics_fold(line: &str) -> String {
+    let mut s = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            s.push_str("\r\n ");
+            width = 1;
+        }
+        s.push(c);
+        width += c.len_utf8();
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::check_result::{CheckResult, CheckState, TextStyle};
+    use crate::number_format::NumberFormat;
+    use crate::sink::{ColorChoice, Destination, Format, Sink};
+
+    #[test]
+    fn test_parse() {
+        let sink: Sink = "text".parse().unwrap();
+        assert_eq!(Sink::stdout(Format::Text), sink);
+
+        let sink: Sink = "json:results.json".parse().unwrap();
+        assert_eq!(Format::Json, sink.format);
+        assert_eq!(
+            Destination::File(PathBuf::from("results.json")),
+            sink.destination
+        );
+
+        let sink: Sink = "pushgateway:http://localhost:9091/".parse().unwrap();
+        assert_eq!(Format::Prometheus, sink.format);
+        assert_eq!(
+            Destination::Pushgateway("http://localhost:9091".into()),
+            sink.destination
+        );
+
+        let sink: Sink = "dogstatsd:localhost:8125".parse().unwrap();
+        assert_eq!(Sink::statsd("localhost:8125", true), sink);
+
+        assert!("yaml".parse::<Sink>().is_err());
+        assert!("statsd".parse::<Sink>().is_err());
+        assert!("pushgateway".parse::<Sink>().is_err());
+        assert!("json:".parse::<Sink>().is_err());
+    }
+
+    #[test]
+    fn test_render_text_color() {
+        let result = CheckResult {
+            state: CheckState::Warning,
+            domain_name: "example.com",
+            ..Default::default()
+        };
+        let mut sink = Sink::stdout(Format::Text);
+        let s = sink.render(&[result]).unwrap();
+        assert!(!s.contains('\x1b'));
+
+        sink.color = true;
+        let result = CheckResult {
+            state: CheckState::Warning,
+            domain_name: "example.com",
+            ..Default::default()
+        };
+        let s = sink.render(&[result]).unwrap();
+        assert!(s.starts_with("\x1b[33m"));
+        assert!(s.ends_with("\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_render_text_numbers() {
+        let result = CheckResult {
+            state: CheckState::Ok,
+            days: 1234,
+            domain_name: "example.com",
+            ..Default::default()
+        };
+        let mut sink = Sink::stdout(Format::Text);
+        let s = sink.render(std::slice::from_ref(&result)).unwrap();
+        assert!(s.contains("in 1,234 days"), "{0}", s);
+
+        sink.style.numbers = "de".parse().unwrap();
+        let s = sink.render(std::slice::from_ref(&result)).unwrap();
+        assert!(s.contains("in 1.234 days"), "{0}", s);
+
+        sink.style.numbers = NumberFormat::Plain;
+        let s = sink.render(&[result]).unwrap();
+        assert!(s.contains("in 1234 days"), "{0}", s);
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert_eq!(ColorChoice::Never, "never".parse().unwrap());
+        assert!("rainbow".parse::<ColorChoice>().is_err());
+        assert!(ColorChoice::Always.enabled());
+        assert!(!ColorChoice::Never.enabled());
+    }
+
+    #[test]
+    fn test_render_ndjson() {
+        let sink: Sink = "ndjson".parse().unwrap();
+        assert!(sink.is_streaming());
+        let s = sink
+            .render(&[CheckResult::default(), CheckResult::default()])
+            .unwrap();
+        assert_eq!(2, s.lines().count());
+        assert!(s.lines().all(|l| l.starts_with('{')));
+
+        let sink: Sink = "ndjson:results.ndjson".parse().unwrap();
+        assert!(!sink.is_streaming());
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let result = CheckResult {
+            state: CheckState::Ok,
+            domain_name: "example.com",
+            days: 42,
+            chain_not_after: Some(10 * 86400),
+            ..Default::default()
+        };
+        let s = Sink::stdout(Format::Prometheus).render(&[result]).unwrap();
+        assert!(s.contains("hcc_certificate_days{domain_name=\"example.com\"} 42\n"));
+        assert!(s.contains("hcc_certificate_chain_days{domain_name=\"example.com\"} 10\n"));
+        assert!(s.contains("hcc_certificate_ok{domain_name=\"example.com\"} 1\n"));
+
+        let result = CheckResult {
+            domain_name: "example.com",
+            tags: vec!["prod".into(), "eu".into()],
+            ..Default::default()
+        };
+        let s = Sink::stdout(Format::Prometheus).render(&[result]).unwrap();
+        assert!(s.contains("hcc_certificate_ok{domain_name=\"example.com\",tags=\"prod,eu\"} 0\n"));
+
+        let result = CheckResult {
+            domain_name: "example.com",
+            tags: vec!["a\\b".into(), "\"c\"\nd".into()],
+            ..Default::default()
+        };
+        let s = Sink::stdout(Format::Prometheus).render(&[result]).unwrap();
+        assert!(
+            s.contains(r#"hcc_certificate_ok{domain_name="example.com",tags="a\\b,\"c\"\nd"} 0"#)
+        );
+    }
+
+    #[test]
+    fn test_render_statsd() {
+        let result = CheckResult {
+            state: CheckState::Ok,
+            domain_name: "example.com:8443",
+            days: 42,
+            ..Default::default()
+        };
+        let s = Sink::statsd("localhost:8125", false)
+            .render(std::slice::from_ref(&result))
+            .unwrap();
+        assert_eq!(
+            "hcc.certificate.days.example_com_8443:42|g\nhcc.certificate.ok.example_com_8443:1|g\n",
+            s
+        );
+        let s = Sink::statsd("localhost:8125", true)
+            .render(std::slice::from_ref(&result))
+            .unwrap();
+        assert!(s.contains("hcc.certificate.days:42|g|#domain:example.com:8443\n"));
+
+        let result = CheckResult {
+            tags: vec!["prod".into()],
+            ..result
+        };
+        let s = Sink::statsd("localhost:8125", true)
+            .render(&[result])
+            .unwrap();
+        assert!(s.contains("hcc.certificate.days:42|g|#domain:example.com:8443,tag:prod\n"));
+    }
+
+    #[test]
+    fn test_render_influx() {
+        let result = CheckResult {
+            state: CheckState::Ok,
+            domain_name: "example.com",
+            days: 42,
+            checked_at: 1_622_533_524,
+            ..Default::default()
+        };
+        let sink: Sink = "influx".parse().unwrap();
+        assert!(sink.is_streaming());
+        assert_eq!(
+            "ssl_cert,domain=example.com days=42i,ok=1i 1622533524000000000\n",
+            sink.render(std::slice::from_ref(&result)).unwrap()
+        );
+
+        let result = CheckResult {
+            tags: vec!["prod".into(), "eu".into()],
+            ..result
+        };
+        assert!(sink
+            .render(&[result])
+            .unwrap()
+            .starts_with("ssl_cert,domain=example.com,tags=prod\\,eu days=42i"));
+    }
+
+    #[test]
+    fn test_emit_statsd() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        Sink::statsd(&addr, true)
+            .emit(&[CheckResult::default()])
+            .unwrap();
+        let mut buf = [0; 512];
+        let n = server.recv(&mut buf).unwrap();
+        assert!(std::str::from_utf8(&buf[..n])
+            .unwrap()
+            .starts_with("hcc.certificate.days:"));
+    }
+
+    #[test]
+    fn test_emit_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.json");
+        let sink = Sink {
+            format: Format::Json,
+            destination: Destination::File(path.clone()),
+            color: false,
+            style: TextStyle::default(),
+            alarms: vec![],
+        };
+        sink.emit(&[CheckResult::default(), CheckResult::default()])
+            .unwrap();
+        let s = std::fs::read_to_string(&path).unwrap();
+        assert!(s.starts_with('['));
+        // only the file is left, temporary file is renamed over it
+        assert_eq!(1, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn test_render_ics() {
+        let result = CheckResult {
+            domain_name: "example.com",
+            checked_at: 0,
+            not_after: 86400 * 30,
+            findings: vec!["missing SAN www.example.com, really".into()],
+            ..Default::default()
+        };
+        let mut sink = Sink::stdout(Format::Ics);
+        sink.alarms = vec![14, 7];
+        let s = sink.render(&[result, CheckResult::default()]).unwrap();
+        assert!(s.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(s.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(1, s.matches("BEGIN:VEVENT").count());
+        assert!(s.contains("\r\nUID:example.com-2592000@hcc\r\n"));
+        assert!(s.contains("\r\nDTSTART;VALUE=DATE:19700131\r\n"));
+        assert!(s.contains("\r\nDTEND;VALUE=DATE:19700201\r\n"));
+        assert!(s.contains("\r\nTRIGGER:-P14D\r\n"));
+        assert!(s.contains("\r\nTRIGGER:-P7D\r\n"));
+        // long description is folded
+        let unfolded = s.replace("\r\n ", "");
+        assert!(unfolded.contains("\\nmissing SAN www.example.com\\, really\r\n"));
+        assert!(s.split("\r\n").all(|line| line.len() <= 75));
+    }
+}
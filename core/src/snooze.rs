@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Domain names whose alerts are silenced until a given date
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snoozes {
+    #[serde(flatten)]
+    domain_names: BTreeMap<String, NaiveDate>,
+}
+
+impl Snoozes {
+    /// Load snoozes from JSON file, missing file is treated as no snoozes
+    ///
+    /// ```
+    /// # use hcc::Snoozes;
+    /// let snoozes = Snoozes::load("/nonexistent/snooze.json").unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let s = match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Snoozes::default()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {0:?}", path)),
+        };
+        serde_json::from_str(&s).with_context(|| format!("failed to parse {0:?}", path))
+    }
+
+    /// Save snoozes to JSON file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let s = serde_json::to_string_pretty(self)?;
+        fs::write(path, s).with_context(|| format!("failed to write {0:?}", path))
+    }
+
+    /// Silence alerts of domain name until the beginning of given date in UTC
+    pub fn snooze(&mut self, domain_name: &str, until: NaiveDate) {
+        self.domain_names.insert(domain_name.to_string(), until);
+    }
+
+    /// Stop silencing domain name, return false if domain name was not snoozed
+    pub fn remove(&mut self, domain_name: &str) -> bool {
+        self.domain_names.remove(domain_name).is_some()
+    }
+
+    /// Date until which domain name is snoozed, or none if snooze is absent or over
+    ///
+    /// ```
+    /// # use hcc::Snoozes;
+    /// use chrono::{NaiveDate, Utc};
+    /// let mut snoozes = Snoozes::default();
+    /// snoozes.snooze("example.com", NaiveDate::from_ymd_opt(2000, 1, 1).unwrap());
+    /// assert!(snoozes.until("example.com", &Utc::now()).is_none());
+    /// ```
+    pub fn until(&self, domain_name: &str, now: &DateTime<Utc>) -> Option<NaiveDate> {
+        self.domain_names
+            .get(domain_name)
+            .filter(|until| now.date().naive_utc() < **until)
+            .copied()
+    }
+
+    /// Drop snoozes which are over
+    pub fn prune(&mut self, now: &DateTime<Utc>) {
+        let today = now.date().naive_utc();
+        self.domain_names.retain(|_, until| today < *until);
+    }
+
+    /// Iterate over domain names and dates until which they are snoozed
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &NaiveDate)> {
+        self.domain_names.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::snooze::Snoozes;
+
+    #[test]
+    fn test_until() {
+        let mut snoozes = Snoozes::default();
+        let until = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+        snoozes.snooze("example.com", until);
+
+        let before = Utc.ymd(2024, 6, 30).and_hms(23, 59, 59);
+        assert_eq!(Some(until), snoozes.until("example.com", &before));
+        assert_eq!(None, snoozes.until("example.org", &before));
+
+        let after = Utc.ymd(2024, 7, 1).and_hms(0, 0, 0);
+        assert_eq!(None, snoozes.until("example.com", &after));
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut snoozes = Snoozes::default();
+        snoozes.snooze(
+            "a.example.com",
+            NaiveDate::from_ymd_opt(2024, 7, 1).unwrap(),
+        );
+        snoozes.snooze(
+            "b.example.com",
+            NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+        );
+        snoozes.prune(&Utc.ymd(2024, 7, 15).and_hms(0, 0, 0));
+        let domain_names: Vec<&String> = snoozes.iter().map(|(d, _)| d).collect();
+        assert_eq!(vec!["b.example.com"], domain_names);
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snoozes.json");
+        let mut snoozes = Snoozes::load(&path).unwrap();
+        assert_eq!(0, snoozes.iter().count());
+
+        snoozes.snooze("example.com", NaiveDate::from_ymd_opt(2024, 7, 1).unwrap());
+        snoozes.save(&path).unwrap();
+
+        let mut snoozes = Snoozes::load(&path).unwrap();
+        assert!(snoozes.remove("example.com"));
+        assert!(!snoozes.remove("example.com"));
+    }
+}
@@ -0,0 +1,401 @@
+use std::convert::TryInto;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use anyhow::{bail, Context};
+use ring::agreement::{self, EphemeralPrivateKey};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use tracing::debug;
+
+/// Default port of SSH
+pub const SSH_PORT: u16 = 22;
+
+/// The latest time a date can tell i.e. 9999-12-31T23:59:59Z, where validity forever is
+/// clamped to
+pub(crate) const MAX_TIMESTAMP: u64 = 253_402_300_799;
+
+/// Suffix of OpenSSH certificate types
+const CERT_SUFFIX: &str = "-cert-v01@openssh.com";
+
+/// Key exchange methods offered, both need nothing but an ephemeral key pair
+const KEX_ALGORITHMS: &[&str] = &[
+    "curve25519-sha256",
+    "curve25519-sha256@libssh.org",
+    "ecdh-sha2-nistp256",
+];
+
+/// Host key algorithms offered, certificates first so servers present them if they have one
+const HOST_KEY_ALGORITHMS: &str = "ssh-ed25519-cert-v01@openssh.com,\
+ecdsa-sha2-nistp256-cert-v01@openssh.com,ecdsa-sha2-nistp384-cert-v01@openssh.com,\
+ecdsa-sha2-nistp521-cert-v01@openssh.com,rsa-sha2-512-cert-v01@openssh.com,\
+rsa-sha2-256-cert-v01@openssh.com,ssh-rsa-cert-v01@openssh.com,ssh-ed25519,\
+ecdsa-sha2-nistp256,ecdsa-sha2-nistp384,ecdsa-sha2-nistp521,rsa-sha2-512,rsa-sha2-256,ssh-rsa";
+
+const CIPHERS: &str = "chacha20-poly1305@openssh.com,aes128-ctr,aes192-ctr,aes256-ctr,\
+aes128-gcm@openssh.com,aes256-gcm@openssh.com";
+
+const MACS: &str = "hmac-sha2-256-etm@openssh.com,hmac-sha2-512-etm@openssh.com,hmac-sha2-256,\
+hmac-sha2-512,hmac-sha1";
+
+/// Longest packet accepted, host keys and certificates are far smaller
+const MAX_PACKET: usize = 256 * 1024;
+
+const MSG_DISCONNECT: u8 = 1;
+const MSG_KEXINIT: u8 = 20;
+const MSG_KEX_ECDH_INIT: u8 = 30;
+const MSG_KEX_ECDH_REPLY: u8 = 31;
+
+/// OpenSSH certificate of host key, see PROTOCOL.certkeys of OpenSSH
+#[derive(Clone, Debug, PartialEq)]
+pub struct SshCertificate {
+    /// Certificate type e.g. ssh-ed25519-cert-v01@openssh.com
+    pub key_type: String,
+    pub serial: u64,
+    /// Whether certificate is of a host rather than a user
+    pub host: bool,
+    /// Key ID given when signing
+    pub key_id: String,
+    /// Host names certificate is valid for, any host if empty
+    pub principals: Vec<String>,
+    /// Valid after in seconds since epoch
+    pub valid_after: u64,
+    /// Valid before in seconds since epoch, `u64::MAX` if it never expires
+    pub valid_before: u64,
+    /// Fingerprint of signing CA key e.g. SHA256:jlJSj6hN...
+    pub ca_fingerprint: String,
+}
+
+impl SshCertificate {
+    /// Parse certificate from wire format
+    ///
+    /// ```
+    /// # use hcc::SshCertificate;
+    /// let line = std::fs::read_to_string("fixtures/ssh_host_ed25519_key-cert.pub").unwrap();
+    /// let blob = base64::decode(line.split_whitespace().nth(1).unwrap()).unwrap();
+    /// let cert = SshCertificate::parse(&blob).unwrap();
+    /// assert_eq!("web01", cert.key_id);
+    /// assert!(cert.host);
+    /// ```
+    pub fn parse(blob: &[u8]) -> anyhow::Result<Self> {
+        let mut r = Reader(blob);
+        let key_type = r.string_utf8()?;
+        if !key_type.ends_with(CERT_SUFFIX) {
+            bail!("{0} is not a certificate", key_type);
+        }
+        r.string()?; // nonce
+        let fields = match key_type.trim_end_matches(CERT_SUFFIX) {
+            "ssh-ed25519" => 1,
+            t if t.starts_with("ecdsa-sha2-") => 2,
+            "ssh-rsa" => 2,
+            "ssh-dss" => 4,
+            t => bail!("unsupported certificate type {0}", t),
+        };
+        for _ in 0..fields {
+            r.string()?;
+        }
+        let serial = r.u64()?;
+        let host = r.u32()? == 2;
+        let key_id = r.string_utf8()?;
+        let mut principals = vec![];
+        let mut packed = Reader(r.string()?);
+        while !packed.0.is_empty() {
+            principals.push(packed.string_utf8()?);
+        }
+        let valid_after = r.u64()?;
+        let valid_before = r.u64()?;
+        r.string()?; // critical options
+        r.string()?; // extensions
+        r.string()?; // reserved
+        let ca = r.string()?;
+        Ok(SshCertificate {
+            key_type,
+            serial,
+            host,
+            key_id,
+            principals,
+            valid_after,
+            valid_before,
+            ca_fingerprint: fingerprint(ca),
+        })
+    }
+}
+
+/// Fingerprint of key blob as OpenSSH shows it e.g. SHA256:jlJSj6hN...
+pub(crate) fn fingerprint(blob: &[u8]) -> String {
+    let hash = digest(&SHA256, blob);
+    format!(
+        "SHA256:{0}",
+        base64::encode_config(hash.as_ref(), base64::STANDARD_NO_PAD)
+    )
+}
+
+/// Type of key blob e.g. ssh-ed25519 or ssh-ed25519-cert-v01@openssh.com
+pub(crate) fn key_type(blob: &[u8]) -> anyhow::Result<String> {
+    Reader(blob).string_utf8()
+}
+
+/// Whether key type is of OpenSSH certificate
+pub(crate) fn is_certificate(key_type: &str) -> bool {
+    key_type.ends_with(CERT_SUFFIX)
+}
+
+/// Exchange keys with SSH server just far enough to receive host key or certificate, which
+/// server sends in the reply to key exchange before anything is encrypted, and disconnect
+pub(crate) fn fetch_host_key<S: Read + Write>(stream: S) -> anyhow::Result<Vec<u8>> {
+    let mut stream = BufReader::new(stream);
+    let ident = format!("SSH-2.0-hcc_{0}\r\n", env!("CARGO_PKG_VERSION"));
+    stream.get_mut().write_all(ident.as_bytes())?;
+    // server may send other lines before its identification
+    let mut server = String::new();
+    for _ in 0..32 {
+        server.clear();
+        let mut line = vec![];
+        stream
+            .by_ref()
+            .take(256)
+            .read_until(b'\n', &mut line)
+            .context("failed to read identification")?;
+        if line.is_empty() {
+            bail!("server closed connection before identification");
+        }
+        server = String::from_utf8_lossy(&line).trim_end().to_string();
+        if server.starts_with("SSH-") {
+            break;
+        }
+    }
+    if !server.starts_with("SSH-2.0-") && !server.starts_with("SSH-1.99-") {
+        bail!("not an SSH 2 server: {0:?}", server);
+    }
+    debug!(server = server.as_str(), "SSH server");
+
+    let rng = SystemRandom::new();
+    let mut cookie = [0; 16];
+    rng.fill(&mut cookie)
+        .map_err(|_| anyhow::anyhow!("failed to generate cookie"))?;
+    let mut kexinit = vec![MSG_KEXINIT];
+    kexinit.extend_from_slice(&cookie);
+    for list in &[
+        KEX_ALGORITHMS.join(",").as_str(),
+        HOST_KEY_ALGORITHMS,
+        CIPHERS,
+        CIPHERS,
+        MACS,
+        MACS,
+        "none,zlib@openssh.com",
+        "none,zlib@openssh.com",
+        "",
+        "",
+    ] {
+        put_string(&mut kexinit, list.as_bytes());
+    }
+    kexinit.push(0); // first key exchange packet follows
+    kexinit.extend_from_slice(&[0; 4]); // reserved
+    write_packet(stream.get_mut(), &kexinit)?;
+
+    let server_kexinit = read_packet(&mut stream, MSG_KEXINIT)?;
+    // message number and cookie precede name-lists
+    let mut r = Reader(server_kexinit.get(17..).context("truncated KEXINIT")?);
+    let server_kex = r.string_utf8()?;
+    let kex = KEX_ALGORITHMS
+        .iter()
+        .find(|k| server_kex.split(',').any(|s| s == **k))
+        .with_context(|| format!("no common key exchange method in {0}", server_kex))?;
+    debug!(kex, "key exchange method");
+    let algorithm = if kex.starts_with("curve25519") {
+        &agreement::X25519
+    } else {
+        &agreement::ECDH_P256
+    };
+    let private_key = EphemeralPrivateKey::generate(algorithm, &rng)
+        .map_err(|_| anyhow::anyhow!("failed to generate key pair"))?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| anyhow::anyhow!("failed to compute public key"))?;
+    let mut init = vec![MSG_KEX_ECDH_INIT];
+    put_string(&mut init, public_key.as_ref());
+    write_packet(stream.get_mut(), &init)?;
+
+    let reply = read_packet(&mut stream, MSG_KEX_ECDH_REPLY)?;
+    let host_key = Reader(&reply[1..]).string()?.to_vec();
+    Ok(host_key)
+}
+
+fn write_packet<W: Write>(w: &mut W, payload: &[u8]) -> anyhow::Result<()> {
+    // packet is padded to multiple of 8 bytes with at least 4 bytes of padding
+    let mut padding = 8 - (5 + payload.len()) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let mut packet = vec![];
+    packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+    packet.push(padding as u8);
+    packet.extend_from_slice(payload);
+    packet.resize(packet.len() + padding, 0);
+    w.write_all(&packet)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Read packets until one of message type, skipping ignored and debug messages
+fn read_packet<R: Read>(r: &mut R, message: u8) -> anyhow::Result<Vec<u8>> {
+    loop {
+        let mut length = [0; 4];
+        r.read_exact(&mut length)
+            .context("server closed connection during key exchange")?;
+        let length = u32::from_be_bytes(length) as usize;
+        if !(5..=MAX_PACKET).contains(&length) {
+            bail!("invalid packet length {0}", length);
+        }
+        let mut packet = vec![0; length];
+        r.read_exact(&mut packet)?;
+        let padding = packet[0] as usize;
+        if padding + 2 > length {
+            bail!("invalid padding length {0}", padding);
+        }
+        let payload = packet[1..length - padding].to_vec();
+        match payload[0] {
+            m if m == message => return Ok(payload),
+            MSG_DISCONNECT => {
+                let mut r = Reader(&payload[1..]);
+                let _code = r.u32()?;
+                bail!("server disconnected: {0}", r.string_utf8()?);
+            }
+            // ignore, debug and unimplemented
+            2..=4 => continue,
+            m => bail!("unexpected message {0}, expect {1}", m, message),
+        }
+    }
+}
+
+fn put_string(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    buf.extend_from_slice(s);
+}
+
+/// Reader of SSH wire format
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("truncated data");
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> anyhow::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn string(&mut self) -> anyhow::Result<&'a [u8]> {
+        let n = self.u32()? as usize;
+        self.take(n)
+    }
+
+    fn string_utf8(&mut self) -> anyhow::Result<String> {
+        Ok(std::str::from_utf8(self.string()?)?.to_string())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use crate::ssh::{
+        fetch_host_key, put_string, read_packet, write_packet, SshCertificate, MSG_KEXINIT,
+        MSG_KEX_ECDH_INIT, MSG_KEX_ECDH_REPLY,
+    };
+
+    pub(crate) fn host_certificate() -> Vec<u8> {
+        let line = std::fs::read_to_string("fixtures/ssh_host_ed25519_key-cert.pub").unwrap();
+        base64::decode(line.split_whitespace().nth(1).unwrap()).unwrap()
+    }
+
+    /// Fake SSH server presenting host key, nothing is verified nor encrypted
+    pub(crate) fn ssh_server(host_key: Vec<u8>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            sock.write_all(b"banner\r\nSSH-2.0-OpenSSH_9.6\r\n")
+                .unwrap();
+            let mut line = vec![];
+            let mut byte = [0];
+            while byte[0] != b'\n' {
+                sock.read_exact(&mut byte).unwrap();
+                line.push(byte[0]);
+            }
+            assert!(line.starts_with(b"SSH-2.0-hcc_"));
+            let mut kexinit = vec![MSG_KEXINIT];
+            kexinit.extend_from_slice(&[0; 16]);
+            put_string(
+                &mut kexinit,
+                b"sntrup761x25519-sha512@openssh.com,curve25519-sha256",
+            );
+            for _ in 0..9 {
+                put_string(&mut kexinit, b"");
+            }
+            kexinit.extend_from_slice(&[0; 5]);
+            write_packet(&mut sock, &[2, 0, 0, 0, 0]).unwrap();
+            write_packet(&mut sock, &kexinit).unwrap();
+            read_packet(&mut sock, MSG_KEXINIT).unwrap();
+            let init = read_packet(&mut sock, MSG_KEX_ECDH_INIT).unwrap();
+            assert_eq!(1 + 4 + 32, init.len());
+            let mut reply = vec![MSG_KEX_ECDH_REPLY];
+            put_string(&mut reply, &host_key);
+            put_string(&mut reply, &[0; 32]);
+            put_string(&mut reply, b"");
+            write_packet(&mut sock, &reply).unwrap();
+        });
+        port
+    }
+
+    #[test]
+    fn test_parse() {
+        let cert = SshCertificate::parse(&host_certificate()).unwrap();
+        assert_eq!("ssh-ed25519-cert-v01@openssh.com", cert.key_type);
+        assert_eq!(42, cert.serial);
+        assert!(cert.host);
+        assert_eq!("web01", cert.key_id);
+        assert_eq!(vec!["localhost", "web01.example.com"], cert.principals);
+        assert_eq!(1577836800, cert.valid_after);
+        assert_eq!(1893456000, cert.valid_before);
+        assert_eq!(
+            "SHA256:jlJSj6hNgUFT35eM0ALeQxH1MTTlALnWNPL8IkmNRRg",
+            cert.ca_fingerprint
+        );
+        assert!(SshCertificate::parse(&host_certificate()[..100]).is_err());
+    }
+
+    #[test]
+    fn test_fetch_host_key() {
+        let port = ssh_server(host_certificate());
+        let sock = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        assert_eq!(host_certificate(), fetch_host_key(sock).unwrap());
+    }
+
+    #[test]
+    fn test_fetch_host_key_truncated_kexinit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            sock.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").unwrap();
+            // cookie is cut short
+            write_packet(&mut sock, &[MSG_KEXINIT, 0, 0, 0]).unwrap();
+            let mut buf = [0; 1024];
+            while sock.read(&mut buf).is_ok_and(|n| n > 0) {}
+        });
+        let sock = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let e = fetch_host_key(sock).unwrap_err();
+        assert!(e.to_string().contains("truncated KEXINIT"), "{0}", e);
+    }
+}
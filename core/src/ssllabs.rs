@@ -0,0 +1,137 @@
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+const SSLLABS_API: &str = "https://api.ssllabs.com/api/v3/analyze";
+
+/// Interval between polls, SSL Labs asks clients to poll no faster than every 10 seconds
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum number of polls before giving up, about 10 minutes
+const MAX_POLLS: usize = 60;
+
+/// Grade of one endpoint i.e. IP address of host from SSL Labs
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EndpointGrade {
+    #[serde(rename = "ipAddress")]
+    pub ip_address: String,
+    /// Grade e.g. A+, or none if assessment of endpoint failed
+    pub grade: Option<String>,
+    /// Message of assessment e.g. Ready or Unable to connect to the server
+    #[serde(rename = "statusMessage", default)]
+    pub status_message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Report {
+    status: String,
+    #[serde(rename = "statusMessage", default)]
+    status_message: String,
+    #[serde(default)]
+    endpoints: Vec<EndpointGrade>,
+}
+
+/// Fetch grades of host from SSL Labs API, reuse cached assessment no older than a day
+///
+/// Assessment is submitted to public SSL Labs and takes minutes, so it is opt-in and hosts
+/// should be assessed one at a time.
+pub fn fetch_grades(host: &str) -> anyhow::Result<Vec<EndpointGrade>> {
+    let client = reqwest::blocking::Client::new();
+    for i in 0..MAX_POLLS {
+        let mut query = vec![("host", host), ("all", "done")];
+        if i == 0 {
+            query.push(("fromCache", "on"));
+            query.push(("maxAge", "24"));
+        }
+        let body = client
+            .get(SSLLABS_API)
+            .query(&query)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .with_context(|| format!("failed to request SSL Labs for {0}", host))?;
+        let report = parse_report(&body)?;
+        debug!(host, status = %report.status, "polled SSL Labs");
+        match report.status.as_str() {
+            "READY" => return Ok(report.endpoints),
+            "ERROR" => bail!(
+                "SSL Labs failed to assess {0}: {1}",
+                host,
+                report.status_message
+            ),
+            _ => thread::sleep(POLL_INTERVAL),
+        }
+    }
+    bail!("SSL Labs did not finish assessing {0} in time", host)
+}
+
+fn parse_report(body: &str) -> anyhow::Result<Report> {
+    serde_json::from_str(body).context("failed to parse response of SSL Labs")
+}
+
+/// Notes on differences between local findings and grades from SSL Labs, certificate is
+/// trusted locally since inspection only succeeds when handshake with trusted certificate
+/// completes
+pub(crate) fn reconcile(grades: &[EndpointGrade]) -> Vec<String> {
+    let mut notes = vec![];
+    for endpoint in grades {
+        match endpoint.grade.as_deref() {
+            Some("T") => notes.push(format!(
+                "SSL Labs reports trust issues on {0} but certificate is trusted locally",
+                endpoint.ip_address
+            )),
+            None => notes.push(format!(
+                "SSL Labs could not assess {0}: {1}",
+                endpoint.ip_address, endpoint.status_message
+            )),
+            _ => {}
+        }
+    }
+    notes
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ssllabs::{parse_report, reconcile};
+
+    const READY: &str = r#"{
+        "host": "example.com",
+        "status": "READY",
+        "endpoints": [
+            {"ipAddress": "93.184.216.34", "statusMessage": "Ready", "grade": "A+"},
+            {"ipAddress": "2606:2800:220:1::", "statusMessage": "Unable to connect to the server"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_report() {
+        let report = parse_report(READY).unwrap();
+        assert_eq!("READY", report.status);
+        assert_eq!(2, report.endpoints.len());
+        assert_eq!(Some("A+"), report.endpoints[0].grade.as_deref());
+        assert_eq!(None, report.endpoints[1].grade);
+
+        let report = parse_report(r#"{"status": "IN_PROGRESS"}"#).unwrap();
+        assert!(report.endpoints.is_empty());
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let report = parse_report(READY).unwrap();
+        let notes = reconcile(&report.endpoints);
+        assert_eq!(1, notes.len());
+        assert!(notes[0].contains("could not assess"));
+
+        let report = parse_report(
+            r#"{"status": "READY", "endpoints": [
+                {"ipAddress": "93.184.216.34", "statusMessage": "Ready", "grade": "T"}
+            ]}"#,
+        )
+        .unwrap();
+        let notes = reconcile(&report.endpoints);
+        assert!(notes[0].contains("trusted locally"), "{0:?}", notes);
+    }
+}
@@ -0,0 +1,337 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpStream};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use tracing::debug;
+
+/// How long to wait for each reply of server before TLS handshake
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Plaintext protocol upgraded to TLS by a command before handshake
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartTls {
+    /// SMTP with STARTTLS command, RFC 3207
+    Smtp,
+    /// XMPP client-to-server stream negotiation, RFC 6120 section 5
+    Xmpp,
+    /// XMPP server-to-server stream negotiation, RFC 6120 section 5
+    XmppServer,
+    /// LDAP StartTLS extended operation, RFC 4511 section 4.14
+    Ldap,
+}
+
+impl FromStr for StartTls {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smtp" => Ok(StartTls::Smtp),
+            "xmpp" => Ok(StartTls::Xmpp),
+            "xmpp-server" => Ok(StartTls::XmppServer),
+            "ldap" => Ok(StartTls::Ldap),
+            _ => bail!(
+                "unknown STARTTLS protocol {0}, expect smtp, xmpp, xmpp-server or ldap",
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for StartTls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartTls::Smtp => write!(f, "smtp"),
+            StartTls::Xmpp => write!(f, "xmpp"),
+            StartTls::XmppServer => write!(f, "xmpp-server"),
+            StartTls::Ldap => write!(f, "ldap"),
+        }
+    }
+}
+
+impl StartTls {
+    /// Well-known port of protocol before upgrade to TLS
+    ///
+    /// ```
+    /// # use hcc::StartTls;
+    /// assert_eq!(5222, StartTls::Xmpp.default_port());
+    /// ```
+    pub fn default_port(&self) -> u16 {
+        match self {
+            StartTls::Smtp => 25,
+            StartTls::Xmpp => 5222,
+            StartTls::XmppServer => 5269,
+            StartTls::Ldap => 389,
+        }
+    }
+
+    /// Ask server to upgrade connection to TLS, handshake could start once this returns, XMPP
+    /// streams are addressed to domain name
+    pub(crate) fn negotiate(&self, sock: &mut TcpStream, domain_name: &str) -> anyhow::Result<()> {
+        let timeout = sock.read_timeout()?;
+        sock.set_read_timeout(Some(timeout.map_or(TIMEOUT, |t| t.min(TIMEOUT))))?;
+        match self {
+            StartTls::Smtp => smtp(sock)?,
+            StartTls::Xmpp => xmpp(sock, domain_name, "jabber:client")?,
+            StartTls::XmppServer => xmpp(sock, domain_name, "jabber:server")?,
+            StartTls::Ldap => ldap(sock)?,
+        }
+        sock.set_read_timeout(timeout)?;
+        Ok(())
+    }
+}
+
+fn smtp(sock: &mut TcpStream) -> anyhow::Result<()> {
+    // address literal since client has no domain name of its own, RFC 5321 section 4.1.3
+    let client = match sock.local_addr()?.ip() {
+        IpAddr::V4(ip) => format!("[{0}]", ip),
+        IpAddr::V6(ip) => format!("[IPv6:{0}]", ip),
+    };
+    let mut reader = BufReader::new(sock.try_clone()?);
+    smtp_reply(&mut reader, "220").context("unexpected SMTP greeting")?;
+    write!(sock, "EHLO {0}\r\n", client)?;
+    let capabilities = smtp_reply(&mut reader, "250").context("SMTP server rejects EHLO")?;
+    if !capabilities
+        .iter()
+        .any(|c| c.eq_ignore_ascii_case("STARTTLS"))
+    {
+        bail!("SMTP server does not offer STARTTLS");
+    }
+    write!(sock, "STARTTLS\r\n")?;
+    smtp_reply(&mut reader, "220").context("SMTP server rejects STARTTLS")?;
+    Ok(())
+}
+
+/// Read possibly multiline reply with expected code, and return text of each line
+fn smtp_reply<R: BufRead>(reader: &mut R, code: &str) -> anyhow::Result<Vec<String>> {
+    let mut lines = vec![];
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("connection closed");
+        }
+        let line = line.trim_end();
+        debug!(line, "SMTP reply");
+        if !line.starts_with(code) {
+            bail!("expect {0}, got {1}", code, line);
+        }
+        lines.push(line.get(4..).unwrap_or_default().to_string());
+        // hyphen after code continues reply
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(lines);
+        }
+    }
+}
+
+fn xmpp(sock: &mut TcpStream, domain_name: &str, namespace: &str) -> anyhow::Result<()> {
+    write!(
+        sock,
+        "<?xml version='1.0'?><stream:stream xmlns='{0}' \
+         xmlns:stream='http://etherx.jabber.org/streams' to='{1}' version='1.0'>",
+        namespace, domain_name
+    )?;
+    let features = read_until(sock, &["</stream:features>", "<stream:features/>"])
+        .context("unexpected XMPP stream")?;
+    if !features.contains("urn:ietf:params:xml:ns:xmpp-tls") {
+        bail!("XMPP server does not offer STARTTLS");
+    }
+    sock.write_all(b"<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>")?;
+    let reply = read_until(sock, &["<proceed", "<failure"]).context("unexpected XMPP reply")?;
+    if !reply.contains("<proceed") {
+        bail!("XMPP server rejects STARTTLS");
+    }
+    Ok(())
+}
+
+/// Read from server until text contains any of markers, server sends nothing after the last
+/// marker before handshake, so no TLS byte is consumed
+fn read_until<R: Read>(reader: &mut R, markers: &[&str]) -> anyhow::Result<String> {
+    let mut text = String::new();
+    let mut buf = [0; 4096];
+    while !markers.iter().any(|m| text.contains(m)) {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            bail!("connection closed");
+        }
+        text.push_str(&String::from_utf8_lossy(&buf[..n]));
+    }
+    debug!(text = %text, "XMPP reply");
+    Ok(text)
+}
+
+/// Object identifier of StartTLS extended operation, RFC 4511 section 4.14.1
+const LDAP_START_TLS: &[u8] = b"1.3.6.1.4.1.1466.20037";
+
+fn ldap(sock: &mut TcpStream) -> anyhow::Result<()> {
+    // LDAPMessage { messageID 1, extendedReq [APPLICATION 23] { requestName [0] OID } }
+    let mut request = vec![0x30, 0x1d, 0x02, 0x01, 0x01, 0x77, 0x18, 0x80, 0x16];
+    request.extend_from_slice(LDAP_START_TLS);
+    sock.write_all(&request)?;
+
+    let (tag, message) = ber(sock).context("unexpected LDAP response")?;
+    if tag != 0x30 {
+        bail!("expect LDAP message, got tag {0:#04x}", tag);
+    }
+    let mut message = message.as_slice();
+    ber(&mut message).context("LDAP response has no message ID")?;
+    let (tag, response) = ber(&mut message).context("LDAP response has no operation")?;
+    // extendedResp [APPLICATION 24]
+    if tag != 0x78 {
+        bail!("expect LDAP extended response, got tag {0:#04x}", tag);
+    }
+    let (tag, code) = ber(&mut response.as_slice()).context("LDAP response has no result")?;
+    if tag != 0x0a {
+        bail!("expect LDAP result code, got tag {0:#04x}", tag);
+    }
+    debug!(?code, "LDAP result code");
+    let code = code.iter().fold(0u64, |code, b| code << 8 | u64::from(*b));
+    if code != 0 {
+        bail!("LDAP server rejects StartTLS with result code {0}", code);
+    }
+    Ok(())
+}
+
+/// Read tag and content of one BER element with definite length
+fn ber<R: Read>(reader: &mut R) -> anyhow::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let [tag, length] = header;
+    let length = if length < 0x80 {
+        usize::from(length)
+    } else {
+        let n = usize::from(length & 0x7f);
+        if n == 0 || n > 4 {
+            bail!("unsupported BER length of {0} bytes", n);
+        }
+        let mut bytes = [0; 4];
+        reader.read_exact(&mut bytes[4 - n..])?;
+        u32::from_be_bytes(bytes) as usize
+    };
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+    Ok((tag, content))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use crate::starttls::StartTls;
+
+    /// Fake SMTP server answering EHLO with capabilities
+    fn smtp_server(capabilities: &'static str) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(sock.try_clone().unwrap());
+            sock.write_all(b"220-mx.example.com ESMTP\r\n220 ready\r\n")
+                .unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("EHLO [127.0.0.1]"));
+            write!(
+                sock,
+                "250-mx.example.com\r\n{0}250 8BITMIME\r\n",
+                capabilities
+            )
+            .unwrap();
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!("STARTTLS\r\n", line);
+            sock.write_all(b"220 go ahead\r\n").unwrap();
+        });
+        TcpStream::connect(addr).unwrap()
+    }
+
+    /// Fake XMPP server announcing features and answering STARTTLS
+    fn xmpp_server(features: &'static str) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let n = sock.read(&mut buf).unwrap();
+            let header = String::from_utf8_lossy(&buf[..n]).to_string();
+            assert!(header.contains("to='chat.example.com'"), "{0}", header);
+            assert!(header.contains("xmlns='jabber:client'"), "{0}", header);
+            write!(
+                sock,
+                "<?xml version='1.0'?><stream:stream from='chat.example.com' id='1' \
+                 version='1.0'><stream:features>{0}</stream:features>",
+                features
+            )
+            .unwrap();
+            let n = sock.read(&mut buf).unwrap();
+            assert!(String::from_utf8_lossy(&buf[..n]).starts_with("<starttls"));
+            sock.write_all(b"<proceed xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>")
+                .unwrap();
+        });
+        TcpStream::connect(addr).unwrap()
+    }
+
+    /// Fake LDAP server answering StartTLS extended request with result code
+    fn ldap_server(code: u8) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut request = [0; 31];
+            sock.read_exact(&mut request).unwrap();
+            assert_eq!(b"1.3.6.1.4.1.1466.20037", &request[9..]);
+            sock.write_all(&[
+                0x30, 0x0c, 0x02, 0x01, 0x01, 0x78, 0x07, 0x0a, 0x01, code, 0x04, 0x00, 0x04, 0x00,
+            ])
+            .unwrap();
+        });
+        TcpStream::connect(addr).unwrap()
+    }
+
+    #[test]
+    fn test_smtp() {
+        let mut sock = smtp_server("250-STARTTLS\r\n");
+        StartTls::Smtp.negotiate(&mut sock, "").unwrap();
+
+        let mut sock = smtp_server("");
+        let e = StartTls::Smtp.negotiate(&mut sock, "").unwrap_err();
+        assert!(e.to_string().contains("does not offer STARTTLS"));
+    }
+
+    #[test]
+    fn test_xmpp() {
+        let mut sock =
+            xmpp_server("<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'><required/></starttls>");
+        StartTls::Xmpp
+            .negotiate(&mut sock, "chat.example.com")
+            .unwrap();
+
+        let mut sock = xmpp_server("<bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'/>");
+        let e = StartTls::Xmpp
+            .negotiate(&mut sock, "chat.example.com")
+            .unwrap_err();
+        assert!(e.to_string().contains("does not offer STARTTLS"));
+    }
+
+    #[test]
+    fn test_ldap() {
+        let mut sock = ldap_server(0);
+        StartTls::Ldap.negotiate(&mut sock, "").unwrap();
+
+        // protocolError
+        let mut sock = ldap_server(2);
+        let e = StartTls::Ldap.negotiate(&mut sock, "").unwrap_err();
+        assert!(e.to_string().contains("result code 2"));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(StartTls::Smtp, "smtp".parse().unwrap());
+        assert_eq!(StartTls::XmppServer, "xmpp-server".parse().unwrap());
+        assert_eq!("ldap", StartTls::Ldap.to_string());
+        assert!("pop3".parse::<StartTls>().is_err());
+    }
+}
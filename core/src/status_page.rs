@@ -0,0 +1,192 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+
+use crate::check_result::{CheckResult, CheckResultJSON, CheckState, Summary, TextStyle};
+use crate::sink::write_atomic;
+
+/// Page of status site
+pub const INDEX_HTML: &str = "index.html";
+/// Results of status site in JSON format, for scripts and widgets
+pub const STATUS_JSON: &str = "status.json";
+
+/// Static status site of certificates, an HTML page and the same results in JSON format
+pub struct StatusPage<'a> {
+    title: String,
+    results: &'a [CheckResult<'a>],
+    summary: Summary,
+    style: TextStyle,
+    generated_at: i64,
+}
+
+#[derive(Serialize)]
+struct StatusJSON<'a> {
+    title: &'a str,
+    generated_at: String,
+    summary: String,
+    results: Vec<CheckResultJSON>,
+}
+
+impl<'a> StatusPage<'a> {
+    /// Status site of results checked with grace period, timestamps on page are rendered in
+    /// timezone of style
+    ///
+    /// ```
+    /// # use hcc::{CheckResult, StatusPage, TextStyle};
+    /// let results = [CheckResult::default()];
+    /// let page = StatusPage::new("Certificates", &results, 7, TextStyle::default());
+    /// assert!(page.html().contains("<title>Certificates</title>"));
+    /// ```
+    pub fn new(
+        title: &str,
+        results: &'a [CheckResult<'a>],
+        grace_in_days: i64,
+        style: TextStyle,
+    ) -> Self {
+        StatusPage {
+            title: title.to_string(),
+            results,
+            summary: Summary::new(results, grace_in_days),
+            style,
+            generated_at: Utc::now().timestamp(),
+        }
+    }
+
+    /// HTML page with a row per result, self-contained so it could be served from anywhere
+    pub fn html(&self) -> String {
+        let mut s = String::new();
+        let title = escape_html(&self.title);
+        let _ = write!(
+            s,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{0}</title>\n<style>{1}</style>\n</head>\n<body>\n<h1>{0}</h1>\n\
+             <p class=\"summary\">{2}</p>\n<table>\n<thead><tr><th>State</th><th>Domain name</th>\
+             <th>Days</th><th>Expires at</th><th>Notes</th></tr></thead>\n<tbody>\n",
+            title,
+            STYLE,
+            escape_html(&self.summary.to_string())
+        );
+        for result in self.results {
+            let (class, state) = match result.state {
+                CheckState::Ok => ("ok", "OK"),
+                CheckState::Warning => ("warning", "Expiring"),
+                CheckState::Expired => ("expired", "Expired"),
+                CheckState::Unknown => ("unknown", "Unknown"),
+            };
+            let (days, expires_at) = if result.not_after > 0 {
+                (
+                    result.days.to_string(),
+                    self.style.zone.format(result.not_after),
+                )
+            } else {
+                (String::new(), String::new())
+            };
+            let mut notes: Vec<String> = result.error.iter().map(ToString::to_string).collect();
+            notes.extend(result.findings.iter().cloned());
+            let _ = writeln!(
+                s,
+                "<tr class=\"{0}\"><td>{1}</td><td>{2}</td><td>{3}</td><td>{4}</td><td>{5}</td></tr>",
+                class,
+                state,
+                escape_html(result.domain_name),
+                days,
+                escape_html(&expires_at),
+                escape_html(&notes.join("; "))
+            );
+        }
+        let _ = write!(
+            s,
+            "</tbody>\n</table>\n<footer>Generated at {0} by hcc</footer>\n</body>\n</html>\n",
+            escape_html(&self.style.zone.format(self.generated_at))
+        );
+        s
+    }
+
+    /// Results in JSON format with title, summary and when site is generated
+    pub fn json(&self) -> anyhow::Result<String> {
+        let json = StatusJSON {
+            title: &self.title,
+            generated_at: Utc.timestamp(self.generated_at, 0).to_rfc3339(),
+            summary: self.summary.to_string(),
+            results: self.results.iter().map(CheckResultJSON::new).collect(),
+        };
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    /// Files of site as name, content type and body
+    pub fn files(&self) -> anyhow::Result<Vec<(&'static str, &'static str, String)>> {
+        Ok(vec![
+            (INDEX_HTML, "text/html; charset=utf-8", self.html()),
+            (STATUS_JSON, "application/json", self.json()?),
+        ])
+    }
+
+    /// Write files of site to directory, created if missing, each file is replaced atomically
+    /// so visitors never see partial content
+    pub fn write_dir<P: AsRef<Path>>(&self, dir: P) -> anyhow::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {0:?}", dir))?;
+        for (name, _, body) in self.files()? {
+            let path = dir.join(name);
+            write_atomic(&path, body.as_bytes())
+                .with_context(|| format!("failed to write {0:?}", path))?;
+        }
+        Ok(())
+    }
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em auto;max-width:60em;\
+padding:0 1em;color:#222}table{border-collapse:collapse;width:100%}th,td{padding:.4em .6em;\
+text-align:left;border-bottom:1px solid #ddd}tr.ok td:first-child{color:#1a7f37}\
+tr.warning td:first-child{color:#9a6700}tr.expired td:first-child,\
+tr.unknown td:first-child{color:#cf222e}footer{margin-top:2em;color:#666;font-size:.9em}";
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::check_result::{CheckResult, CheckState, TextStyle};
+    use crate::status_page::{StatusPage, INDEX_HTML, STATUS_JSON};
+
+    #[test]
+    fn test_render() {
+        let results = [
+            CheckResult {
+                domain_name: "example.com",
+                state: CheckState::Warning,
+                days: 3,
+                not_after: 86400 * 3,
+                findings: vec!["missing SAN <www.example.com>".into()],
+                ..Default::default()
+            },
+            CheckResult::default(),
+        ];
+        let page = StatusPage::new("Status & health", &results, 7, TextStyle::default());
+        let html = page.html();
+        assert!(html.contains("<h1>Status &amp; health</h1>"));
+        assert!(html.contains(
+            "<tr class=\"warning\"><td>Expiring</td><td>example.com</td><td>3</td>\
+             <td>1970-01-04T00:00:00+00:00</td><td>missing SAN &lt;www.example.com&gt;</td></tr>"
+        ));
+        assert!(html.contains("<tr class=\"unknown\"><td>Unknown</td><td></td><td></td>"));
+
+        let json: serde_json::Value = serde_json::from_str(&page.json().unwrap()).unwrap();
+        assert_eq!("Status & health", json["title"]);
+        assert_eq!("example.com", json["results"][0]["domain_name"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        page.write_dir(dir.path()).unwrap();
+        assert!(dir.path().join(INDEX_HTML).exists());
+        assert!(dir.path().join(STATUS_JSON).exists());
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// Default port of HTTPS
+pub const DEFAULT_PORT: u16 = 443;
+
+/// Host and port to be checked
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    /// Domain name or IP address, without brackets
+    pub host: String,
+    /// Port to connect to
+    pub port: u16,
+}
+
+impl Target {
+    /// IP address of target if host is an IP literal
+    ///
+    /// ```
+    /// # use hcc::Target;
+    /// let target: Target = "[2001:db8::1]:8443".parse().unwrap();
+    /// assert!(target.ip().is_some());
+    /// ```
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.host.parse().ok()
+    }
+}
+
+impl FromStr for Target {
+    type Err = anyhow::Error;
+
+    /// Parse target from `host`, `host:port`, `[ipv6]`, `[ipv6]:port` or bare IPv6 literal
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            bail!("empty target");
+        }
+
+        if let Some(rest) = s.strip_prefix('[') {
+            let end = match rest.find(']') {
+                Some(end) => end,
+                None => bail!("missing closing bracket in {0}", s),
+            };
+            let host = &rest[..end];
+            if host.parse::<std::net::Ipv6Addr>().is_err() {
+                bail!("invalid IPv6 address in {0}", s);
+            }
+            let port = match &rest[end + 1..] {
+                "" => DEFAULT_PORT,
+                p => match p.strip_prefix(':') {
+                    Some(p) => parse_port(p, s)?,
+                    None => bail!("unexpected characters after bracket in {0}", s),
+                },
+            };
+            return Ok(Target {
+                host: host.to_string(),
+                port,
+            });
+        }
+
+        // bare IPv6 literal e.g. 2001:db8::1 carries no port
+        if s.parse::<std::net::Ipv6Addr>().is_ok() {
+            return Ok(Target {
+                host: s.to_string(),
+                port: DEFAULT_PORT,
+            });
+        }
+
+        match s.rsplit_once(':') {
+            Some((host, port)) => {
+                if host.contains(':') {
+                    bail!("IPv6 address with port must be bracketed e.g. [::1]:443");
+                }
+                Ok(Target {
+                    host: host.to_string(),
+                    port: parse_port(port, s)?,
+                })
+            }
+            None => Ok(Target {
+                host: s.to_string(),
+                port: DEFAULT_PORT,
+            }),
+        }
+    }
+}
+
+/// Whether target string carries port e.g. example.com:8443 or [2001:db8::1]:443
+pub(crate) fn has_port(s: &str) -> bool {
+    let s = s.trim();
+    match s.strip_prefix('[') {
+        Some(rest) => rest.contains("]:"),
+        None => s.parse::<std::net::Ipv6Addr>().is_err() && s.contains(':'),
+    }
+}
+
+fn parse_port(port: &str, s: &str) -> anyhow::Result<u16> {
+    match port.parse::<u16>() {
+        Ok(0) | Err(_) => bail!("invalid port in {0}", s),
+        Ok(port) => Ok(port),
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let host = if self.host.contains(':') {
+            format!("[{0}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == DEFAULT_PORT {
+            write!(f, "{0}", host)
+        } else {
+            write!(f, "{0}:{1}", host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::target::{has_port, Target};
+
+    fn parse(s: &str) -> Target {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_domain_name() {
+        let target = parse("example.com");
+        assert_eq!("example.com", target.host);
+        assert_eq!(443, target.port);
+        assert!(target.ip().is_none());
+
+        let target = parse("example.com:8443");
+        assert_eq!("example.com", target.host);
+        assert_eq!(8443, target.port);
+    }
+
+    #[test]
+    fn test_parse_ipv6() {
+        let target = parse("[2001:db8::1]:8443");
+        assert_eq!("2001:db8::1", target.host);
+        assert_eq!(8443, target.port);
+        assert!(target.ip().is_some());
+
+        let target = parse("[2001:db8::1]");
+        assert_eq!(443, target.port);
+
+        let target = parse("2001:db8::1");
+        assert_eq!("2001:db8::1", target.host);
+        assert_eq!(443, target.port);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("".parse::<Target>().is_err());
+        assert!("[2001:db8::1".parse::<Target>().is_err());
+        assert!("[example.com]:443".parse::<Target>().is_err());
+        assert!("[2001:db8::1]8443".parse::<Target>().is_err());
+        assert!("example.com:0".parse::<Target>().is_err());
+        assert!("example.com:https".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn test_has_port() {
+        assert!(has_port("example.com:443"));
+        assert!(has_port("[2001:db8::1]:8443"));
+        assert!(!has_port("example.com"));
+        assert!(!has_port("[2001:db8::1]"));
+        assert!(!has_port("2001:db8::1"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("example.com", parse("example.com:443").to_string());
+        assert_eq!("example.com:8443", parse("example.com:8443").to_string());
+        assert_eq!(
+            "[2001:db8::1]:8443",
+            parse("[2001:db8::1]:8443").to_string()
+        );
+        assert_eq!("[2001:db8::1]", parse("2001:db8::1").to_string());
+    }
+}
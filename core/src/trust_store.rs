@@ -0,0 +1,230 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use rustls::{Certificate, RootCertStore};
+use serde::{Deserialize, Serialize};
+
+use crate::check_client::parse_certificates;
+
+/// Well-known locations of system CA bundle, the first existing one is used
+pub(crate) const SYSTEM_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/pki/tls/cacert.pem",
+    "/etc/ssl/cert.pem",
+];
+
+/// Signature algorithms accepted in chain, same as rustls
+static SIGNATURE_ALGORITHMS: &[&webpki::SignatureAlgorithm] = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::ED25519,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+/// Named set of root certificates to verify chains against e.g. Mozilla root certificates or
+/// CA bundle of an appliance
+#[derive(Clone)]
+pub struct TrustStore {
+    name: String,
+    roots: RootCertStore,
+}
+
+impl fmt::Debug for TrustStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrustStore")
+            .field("name", &self.name)
+            .field("roots", &self.roots.len())
+            .finish()
+    }
+}
+
+impl TrustStore {
+    /// Mozilla root certificates bundled with webpki-roots
+    pub fn mozilla() -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        TrustStore {
+            name: "mozilla".to_string(),
+            roots,
+        }
+    }
+
+    /// CA bundle of operating system, at SSL_CERT_FILE or the first well-known location
+    pub fn system() -> anyhow::Result<Self> {
+        let path = std::env::var("SSL_CERT_FILE")
+            .ok()
+            .or_else(|| {
+                SYSTEM_BUNDLES
+                    .iter()
+                    .find(|p| Path::new(p).exists())
+                    .map(|p| p.to_string())
+            })
+            .context("no system CA bundle found, set SSL_CERT_FILE")?;
+        Self::from_file("system", path)
+    }
+
+    /// Root certificate(s) in PEM or DER file
+    pub fn from_file<P: AsRef<Path>>(name: &str, path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let body = fs::read(path).with_context(|| format!("failed to read {0:?}", path))?;
+        Self::from_bytes(name, &body).with_context(|| format!("invalid trust store {0:?}", path))
+    }
+
+    /// Root certificate(s) in PEM or DER format, certificates webpki could not parse are
+    /// skipped like system bundles do
+    pub fn from_bytes(name: &str, body: &[u8]) -> anyhow::Result<Self> {
+        let mut roots = RootCertStore::empty();
+        for der in parse_certificates(body)? {
+            let _ = roots.add(&Certificate(der));
+        }
+        if roots.is_empty() {
+            anyhow::bail!("no root certificate found");
+        }
+        Ok(TrustStore {
+            name: name.to_string(),
+            roots,
+        })
+    }
+
+    /// Open store by specification, `mozilla`, `system`, `NAME=PATH` or `PATH` named after
+    /// file
+    ///
+    /// ```
+    /// # use hcc::TrustStore;
+    /// let store = TrustStore::open("mozilla").unwrap();
+    /// assert_eq!("mozilla", store.name());
+    /// let store = TrustStore::open("appliance=fixtures/bundle.pem").unwrap();
+    /// assert_eq!("appliance", store.name());
+    /// ```
+    pub fn open(spec: &str) -> anyhow::Result<Self> {
+        match spec.split_once('=') {
+            Some((name, path)) => Self::from_file(name, path),
+            None if spec == "mozilla" => Ok(Self::mozilla()),
+            None if spec == "system" => Self::system(),
+            None => {
+                let name = Path::new(spec)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| spec.to_string());
+                Self::from_file(&name, spec)
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Verify chain, leaf first, issued for DNS name by root certificates of store at time
+    pub fn verify(
+        &self,
+        chain: &[Vec<u8>],
+        dns_name: &str,
+        time: &DateTime<Utc>,
+    ) -> Result<(), webpki::Error> {
+        let leaf = chain.first().ok_or(webpki::Error::BadDER)?;
+        let cert = webpki::EndEntityCert::from(leaf)?;
+        let anchors: Vec<webpki::TrustAnchor> = self
+            .roots
+            .roots
+            .iter()
+            .map(|r| r.to_trust_anchor())
+            .collect();
+        let intermediates: Vec<&[u8]> = chain.iter().skip(1).map(|c| c.as_slice()).collect();
+        let time = webpki::Time::from_seconds_since_unix_epoch(time.timestamp().max(0) as u64);
+        cert.verify_is_valid_tls_server_cert(
+            SIGNATURE_ALGORITHMS,
+            &webpki::TLSServerTrustAnchors(&anchors),
+            &intermediates,
+            time,
+        )?;
+        let dns_name =
+            webpki::DNSNameRef::try_from_ascii_str(dns_name).map_err(|_| webpki::Error::BadDER)?;
+        cert.verify_is_valid_for_dns_name(dns_name)
+    }
+
+    /// Trust status of chain in store
+    pub fn status(&self, chain: &[Vec<u8>], dns_name: &str, time: &DateTime<Utc>) -> TrustStatus {
+        let reason = self
+            .verify(chain, dns_name, time)
+            .err()
+            .map(|e| format!("{0:?}", e));
+        TrustStatus {
+            store: self.name.clone(),
+            trusted: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+/// Whether chain is trusted by a trust store
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrustStatus {
+    /// Name of trust store e.g. mozilla
+    pub store: String,
+    /// Whether chain verifies against root certificates of store
+    pub trusted: bool,
+    /// Why chain is not trusted e.g. UnknownIssuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for TrustStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            None => write!(f, "trusted by {0}", self.store),
+            Some(reason) => write!(f, "not trusted by {0}: {1}", self.store, reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use crate::trust_store::{TrustStatus, TrustStore};
+
+    #[test]
+    fn test_open() {
+        assert_eq!("mozilla", TrustStore::open("mozilla").unwrap().name());
+        assert_eq!(
+            "bundle",
+            TrustStore::open("fixtures/bundle.pem").unwrap().name()
+        );
+        assert_eq!(
+            "appliance",
+            TrustStore::open("appliance=fixtures/bundle.pem")
+                .unwrap()
+                .name()
+        );
+        assert!(TrustStore::open("missing=fixtures/missing.pem").is_err());
+        assert!(TrustStore::from_bytes("empty", b"").is_err());
+    }
+
+    #[test]
+    fn test_status() {
+        let store = TrustStore::mozilla();
+        let status = store.status(&[b"not a certificate".to_vec()], "example.com", &Utc::now());
+        assert!(!status.trusted);
+        assert_eq!("not trusted by mozilla: BadDER", status.to_string());
+        let status = TrustStatus {
+            store: "system".into(),
+            trusted: true,
+            reason: None,
+        };
+        assert_eq!("trusted by system", status.to_string());
+    }
+}
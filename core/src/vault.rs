@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::debug;
+
+/// Address of Vault when VAULT_ADDR is not set, as Vault CLI does
+pub const DEFAULT_ADDR: &str = "https://127.0.0.1:8200";
+
+/// How long each request to Vault may take
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// PKI secrets engine of HashiCorp Vault, certificates it issued are checked as they are
+/// stored, so internal certificates never served on a public endpoint are caught too
+pub struct VaultPki {
+    addr: String,
+    mount: String,
+    token: String,
+    namespace: Option<String>,
+    client: Client,
+}
+
+/// Certificate issued by PKI secrets engine of Vault
+#[derive(Clone, Debug, PartialEq)]
+pub struct VaultCertificate {
+    /// Name of certificate in results e.g. vault:pki/17-67-16-b0
+    pub name: String,
+    /// Serial number of certificate, hyphen-separated as Vault lists them
+    pub serial: String,
+    /// Certificate in PEM format
+    pub pem: String,
+}
+
+#[derive(Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct Keys {
+    keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Certificate {
+    certificate: String,
+    #[serde(default)]
+    revocation_time: i64,
+}
+
+impl VaultPki {
+    /// PKI secrets engine mounted at path e.g. pki of Vault at address, authenticated with
+    /// token
+    ///
+    /// ```
+    /// # use hcc::VaultPki;
+    /// let vault = VaultPki::new("https://vault.example.com:8200/", "/pki_int/", "token").unwrap();
+    /// assert_eq!("https://vault.example.com:8200/v1/pki_int/certs", vault.url("certs"));
+    /// ```
+    pub fn new(addr: &str, mount: &str, token: &str) -> anyhow::Result<Self> {
+        let mount = mount.trim_matches('/');
+        if mount.is_empty() {
+            bail!("mount of PKI secrets engine is missing");
+        }
+        Ok(VaultPki {
+            addr: addr.trim_end_matches('/').to_string(),
+            mount: mount.to_string(),
+            token: token.to_string(),
+            namespace: None,
+            client: Client::builder().timeout(TIMEOUT).build()?,
+        })
+    }
+
+    /// PKI secrets engine mounted at path of Vault at VAULT_ADDR, authenticated with
+    /// VAULT_TOKEN, in VAULT_NAMESPACE if any
+    pub fn from_env(mount: &str) -> anyhow::Result<Self> {
+        let addr = std::env::var("VAULT_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+        let token = std::env::var("VAULT_TOKEN").context("VAULT_TOKEN is not set")?;
+        let mut vault = Self::new(&addr, mount, &token)?;
+        vault.namespace = std::env::var("VAULT_NAMESPACE").ok();
+        Ok(vault)
+    }
+
+    /// URL of path under mount
+    pub fn url(&self, path: &str) -> String {
+        format!("{0}/v1/{1}/{2}", self.addr, self.mount, path)
+    }
+
+    fn get(&self, url: &str) -> anyhow::Result<Response> {
+        let mut request = self.client.get(url).header("X-Vault-Token", &self.token);
+        if let Some(ref namespace) = self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        request
+            .send()
+            .with_context(|| format!("failed to request {0}", url))
+    }
+
+    /// Serial numbers of certificates issued, Vault answers not found when there is none
+    pub fn serials(&self) -> anyhow::Result<Vec<String>> {
+        let url = format!("{0}?list=true", self.url("certs"));
+        let response = self.get(&url)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+        let body = response
+            .error_for_status()
+            .and_then(|r| r.text())
+            .with_context(|| format!("failed to list certificates of {0}", self.mount))?;
+        let keys: Data<Keys> = serde_json::from_str(&body)
+            .with_context(|| format!("failed to parse certificates of {0}", self.mount))?;
+        Ok(keys.data.keys)
+    }
+
+    /// Certificates issued and not revoked, the CA certificate of mount is listed too
+    pub fn certificates(&self) -> anyhow::Result<Vec<VaultCertificate>> {
+        let mut certificates = vec![];
+        for serial in self.serials()? {
+            let url = self.url(&format!("cert/{0}", serial));
+            let body = self
+                .get(&url)?
+                .error_for_status()
+                .and_then(|r| r.text())
+                .with_context(|| format!("failed to read certificate {0}", serial))?;
+            let cert: Data<Certificate> = serde_json::from_str(&body)
+                .with_context(|| format!("failed to parse certificate {0}", serial))?;
+            if cert.data.revocation_time > 0 {
+                debug!(serial = serial.as_str(), "skip revoked certificate");
+                continue;
+            }
+            certificates.push(VaultCertificate {
+                name: format!("vault:{0}/{1}", self.mount, serial),
+                serial,
+                pem: cert.data.certificate,
+            });
+        }
+        debug!(
+            mount = self.mount.as_str(),
+            count = certificates.len(),
+            "certificates in Vault"
+        );
+        Ok(certificates)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::vault::VaultPki;
+
+    /// Fake Vault answering requests in order with status and body
+    fn vault_server(responses: Vec<(&'static str, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut sock, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(sock.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut header = String::new();
+                while header != "\r\n" {
+                    header.clear();
+                    reader.read_line(&mut header).unwrap();
+                    if header.to_ascii_lowercase().starts_with("x-vault-token:") {
+                        assert_eq!("x-vault-token: token\r\n", header.to_ascii_lowercase());
+                    }
+                }
+                write!(
+                    sock,
+                    "HTTP/1.1 {0}\r\nContent-Type: application/json\r\nContent-Length: {1}\r\nConnection: close\r\n\r\n{2}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        format!("http://{0}", addr)
+    }
+
+    #[test]
+    fn test_certificates() {
+        let pem = std::fs::read_to_string("fixtures/bundle.pem").unwrap();
+        let cert = |revocation_time: i64| {
+            serde_json::json!({
+                "data": {"certificate": pem, "revocation_time": revocation_time}
+            })
+            .to_string()
+        };
+        let addr = vault_server(vec![
+            ("200 OK", r#"{"data":{"keys":["01-02","03-04"]}}"#.into()),
+            ("200 OK", cert(0)),
+            ("200 OK", cert(1700000000)),
+        ]);
+        let vault = VaultPki::new(&addr, "pki", "token").unwrap();
+        let certificates = vault.certificates().unwrap();
+        assert_eq!(1, certificates.len());
+        assert_eq!("vault:pki/01-02", certificates[0].name);
+        assert_eq!("01-02", certificates[0].serial);
+        assert_eq!(pem, certificates[0].pem);
+
+        let addr = vault_server(vec![("404 Not Found", r#"{"errors":[]}"#.into())]);
+        let vault = VaultPki::new(&addr, "pki", "token").unwrap();
+        assert!(vault.certificates().unwrap().is_empty());
+
+        let addr = vault_server(vec![(
+            "403 Forbidden",
+            r#"{"errors":["permission denied"]}"#.into(),
+        )]);
+        let vault = VaultPki::new(&addr, "pki", "token").unwrap();
+        assert!(vault.certificates().is_err());
+        assert!(VaultPki::new(&addr, "/", "token").is_err());
+    }
+}
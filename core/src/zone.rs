@@ -0,0 +1,88 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use chrono::{Local, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Timezone timestamps are shown in
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Zone {
+    /// Coordinated Universal Time
+    #[default]
+    Utc,
+    /// Timezone of local machine
+    Local,
+    /// Timezone in IANA database e.g. Europe/Berlin
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "UTC" | "utc" => Ok(Zone::Utc),
+            "local" => Ok(Zone::Local),
+            _ => s.parse().map(Zone::Named).map_err(|_| {
+                anyhow!(
+                    "unknown timezone {0}, expect IANA name e.g. Europe/Berlin, UTC or local",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Utc => write!(f, "UTC"),
+            Zone::Local => write!(f, "local"),
+            Zone::Named(tz) => write!(f, "{0}", tz.name()),
+        }
+    }
+}
+
+impl Zone {
+    /// Seconds since Unix epoch in RFC3339 format with offset of timezone
+    ///
+    /// ```
+    /// # use hcc::Zone;
+    /// let zone: Zone = "Europe/Berlin".parse().unwrap();
+    /// assert_eq!("1970-01-01T01:00:00+01:00", zone.format(0));
+    /// ```
+    pub fn format(&self, secs: i64) -> String {
+        match self {
+            Zone::Utc => Utc.timestamp(secs, 0).to_rfc3339(),
+            Zone::Local => Local.timestamp(secs, 0).to_rfc3339(),
+            Zone::Named(tz) => tz.timestamp(secs, 0).to_rfc3339(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::zone::Zone;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Zone::Utc, "UTC".parse().unwrap());
+        assert_eq!(Zone::Local, "local".parse().unwrap());
+        assert_eq!(
+            "Asia/Taipei",
+            "Asia/Taipei".parse::<Zone>().unwrap().to_string()
+        );
+        assert!("Mars/Olympus_Mons".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!("1970-01-01T00:00:00+00:00", Zone::Utc.format(0));
+        let zone: Zone = "Asia/Taipei".parse().unwrap();
+        assert_eq!("2021-01-01T08:00:00+08:00", zone.format(1_609_459_200));
+        // daylight saving time
+        let zone: Zone = "Europe/Berlin".parse().unwrap();
+        assert_eq!("2021-07-01T02:00:00+02:00", zone.format(1_625_097_600));
+    }
+}
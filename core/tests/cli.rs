@@ -0,0 +1,977 @@
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::process::Stdio;
+
+use common::{fixture, hcc, start_server, start_server_with, trusted_ca};
+
+fn domain_name() -> String {
+    format!("localhost:{0}", start_server().port())
+}
+
+#[test]
+fn test_check() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["check", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("[v] certificate of localhost:"),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_check_untrusted() {
+    let output = hcc().args(["check", &domain_name()]).output().unwrap();
+    assert_eq!(Some(2), output.status.code());
+
+    let output = hcc()
+        .args(["--exit-code", "expired=5", "check", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(5), output.status.code());
+}
+
+#[test]
+fn test_check_unreachable() {
+    // nothing listens on port of dropped listener, so connection is refused
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let unreachable = format!("127.0.0.1:{0}", port);
+    let output = hcc()
+        .args(["--json", "check", "--sni", "localhost", &unreachable])
+        .output()
+        .unwrap();
+    assert_eq!(Some(3), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("Unknown", json["state"]);
+    assert_eq!("connect_failed", json["error"]["kind"]);
+}
+
+#[test]
+fn test_check_json() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "check", &domain_name()])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("OK", json["state"]);
+    assert_eq!(1, json["schema_version"]);
+    assert!(json["days"].as_i64().unwrap() > 0);
+    assert!(json.get("certificate").is_none());
+
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "--include-cert", "check", &domain_name()])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let certificate = json["certificate"].as_str().unwrap();
+    assert_eq!(
+        fs::read_to_string(fixture("localhost.pem")).unwrap(),
+        certificate
+    );
+}
+
+#[test]
+fn test_schema() {
+    let output = hcc().arg("--schema").output().unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(1, schema["x-schema-version"]);
+}
+
+#[test]
+fn test_check_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("results.json");
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .arg("--json")
+        .arg("--out")
+        .arg(&out)
+        .args(["check", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    assert!(output.stdout.is_empty());
+    let json: serde_json::Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+    assert_eq!("OK", json["state"]);
+}
+
+#[test]
+fn test_check_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = dir.path().join("cache.json");
+    let domain_name = domain_name();
+    let check = || {
+        let output = hcc()
+            .arg("--trust-store")
+            .arg(trusted_ca())
+            .arg("--cache")
+            .arg("--cache-file")
+            .arg(&cache)
+            .args(["--json", "check", &domain_name])
+            .output()
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let first = check();
+    let second = check();
+    assert_eq!("OK", second["state"]);
+    assert_eq!(first["id"], second["id"]);
+}
+
+#[test]
+fn test_check_client_certificate() {
+    let addr = start_server_with("client.pem", "client.key");
+    let output = hcc()
+        .args(["check", &format!("localhost:{0}", addr.port())])
+        .output()
+        .unwrap();
+    assert_eq!(Some(2), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("client authentication certificate served"),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_check_grace() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["check", "--grace", "99999", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn test_check_env() {
+    let output = hcc()
+        .env("HCC_TRUST_STORES", trusted_ca())
+        .env(
+            "HCC_DOMAINS",
+            format!("{0},{1}", domain_name(), domain_name()),
+        )
+        .env("HCC_SINK", "ndjson")
+        .arg("check")
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(2, stdout.lines().count());
+}
+
+#[test]
+fn test_command_env() {
+    let port = start_server().port().to_string();
+    let output = hcc()
+        .env("HCC_SCAN_PORTS", &port)
+        .env("HCC_SCAN_TIMEOUT", "5")
+        .env("HCC_SCAN_CONCURRENCY", "1")
+        .args(["--json", "scan", "127.0.0.1/32"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(format!("127.0.0.1:{0}", port), json["domain_name"]);
+
+    let output = hcc()
+        .env("HCC_SCAN_PORTS", &port)
+        .args(["--json", "scan-ports", "localhost"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(format!("localhost:{0}", port), json["domain_name"]);
+
+    let output = hcc()
+        .env("HCC_TRUST_STORES", trusted_ca())
+        .env("HCC_BENCH_COUNT", "2")
+        .args(["bench", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("2 of 2 completed"), "{0}", stdout);
+
+    let output = hcc()
+        .env("HCC_MX_PORTS", "26")
+        .args(["check-mx", "example.com"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("26 is not an SMTP port"), "{0}", stderr);
+
+    let output = hcc()
+        .env("HCC_INTERVAL", "0")
+        .args(["check", "--watch", "example.com"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("interval must be positive"), "{0}", stderr);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nmap.xml");
+    fs::write(
+        &path,
+        format!(
+            r#"<nmaprun><host><address addr="127.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="localhost" type="user"/></hostnames>
+<ports><port protocol="tcp" portid="{0}"><state state="open"/></port></ports>
+</host></nmaprun>"#,
+            port
+        ),
+    )
+    .unwrap();
+    let output = hcc()
+        .env("HCC_TRUST_STORES", trusted_ca())
+        .env("HCC_FROM_NMAP", &path)
+        .args(["--json", "check"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(format!("localhost:{0}", port), json["domain_name"]);
+}
+
+#[test]
+fn test_check_expected_sans() {
+    let domain_name = domain_name();
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("hcc.toml");
+    fs::write(
+        &config,
+        format!(
+            "[[domains]]\nname = \"{0}\"\nexpected_sans = [\"localhost\", \"api.localhost\"]\n",
+            domain_name
+        ),
+    )
+    .unwrap();
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .arg("--config")
+        .arg(&config)
+        .arg("check")
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("missing SAN api.localhost"), "{0}", stdout);
+    assert!(
+        stdout.contains("unexpected SAN www.localhost"),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_check_expect_san() {
+    let domain_name = domain_name();
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["check", &domain_name, "--expect-san", "www.localhost"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["check", &domain_name, "--expect-san", "api.localhost"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("violates policy required_sans: SAN api.localhost is not covered"),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_inspect() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["inspect", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("TLS versions: TLSv1.2, TLSv1.3"),
+        "{0}",
+        stdout
+    );
+    assert!(stdout.contains("issuer: CN=hcc Test CA"), "{0}", stdout);
+    assert!(
+        stdout.contains("key usage: digitalSignature, extended key usage: serverAuth"),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_compare() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["compare", &format!("https://{0}/", domain_name())])
+        .arg(format!("file:{0}", fixture("localhost.pem").display()))
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(": same certificate"), "{0}", stdout);
+
+    let output = hcc()
+        .args(["compare"])
+        .arg(format!("file:{0}", fixture("localhost.pem").display()))
+        .arg(format!("file:{0}", fixture("ca.pem").display()))
+        .output()
+        .unwrap();
+    assert_eq!(Some(4), output.status.code());
+}
+
+#[test]
+fn test_check_file_stdin() {
+    let mut child = hcc()
+        .args(["--json", "check-file", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&fs::read(fixture("localhost.pem")).unwrap())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("(stdin)", json["domain_name"]);
+    assert_eq!("OK", json["state"]);
+}
+
+#[test]
+fn test_scan() {
+    let port = start_server().port();
+    // nothing listens on port of dropped listener, so it is skipped
+    let closed = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let output = hcc()
+        .args([
+            "--json",
+            "scan",
+            "-p",
+            &port.to_string(),
+            "-p",
+            &closed.to_string(),
+        ])
+        .arg("127.0.0.1/32")
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(format!("127.0.0.1:{0}", port), json["domain_name"]);
+    assert_eq!("subject CN=localhost", json["findings"][0]);
+
+    // count of targets overflows
+    let output = hcc()
+        .args(["scan", "-p", "443", "-p", "8443", "::/1"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("targets to scan exceed"), "{0}", stderr);
+}
+
+#[test]
+fn test_check_from_nmap() {
+    let port = start_server().port();
+    let xml = format!(
+        r#"<nmaprun><host><address addr="127.0.0.1" addrtype="ipv4"/>
+<hostnames><hostname name="localhost" type="user"/></hostnames>
+<ports><port protocol="tcp" portid="{0}"><state state="open"/><service name="https"/></port></ports>
+</host></nmaprun>"#,
+        port
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nmap.xml");
+    fs::write(&path, xml).unwrap();
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "check", "--from-nmap"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(format!("localhost:{0}", port), json["domain_name"]);
+}
+
+#[test]
+fn test_scan_ports() {
+    let port = start_server().port();
+    // nothing listens on port of dropped listener, so it is skipped
+    let closed = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let ports = format!("{0},{1}-{1}", port, closed);
+    let output = hcc()
+        .args(["--json", "scan-ports", "--ports", &ports, "localhost"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(format!("localhost:{0}", port), json["domain_name"]);
+}
+
+#[test]
+fn test_check_verify_hostname() {
+    let target = format!("127.0.0.1:{0}", start_server().port());
+    let check = |args: &[&str]| {
+        hcc()
+            .arg("--trust-store")
+            .arg(trusted_ca())
+            .args(args)
+            .args(["check", "--sni", "tenant.example.com", &target])
+            .output()
+            .unwrap()
+    };
+    // certificate of localhost does not cover name requested
+    let output = check(&[]);
+    assert_eq!(Some(1), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("not trusted by ca"), "{0}", stdout);
+    let output = check(&["--verify-hostname", "localhost"]);
+    assert_eq!(Some(0), output.status.code());
+}
+
+#[test]
+fn test_check_request() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "--request", "/healthz", "check", &domain_name()])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(204, json["http"]["status"]);
+    assert_eq!("HTTP/1.1 204 No Content", json["http"]["status_line"]);
+    assert_eq!("close", json["http"]["headers"]["connection"]);
+
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "--request", "/", "--no-request", "check"])
+        .arg(domain_name())
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(json.get("http").is_none());
+
+    // host alone overrides the default request
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "--request-host", "localhost", "check"])
+        .arg(domain_name())
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(204, json["http"]["status"]);
+
+    let output = hcc()
+        .args(["--request", "/\r\nX-Injected: 1", "check"])
+        .arg(domain_name())
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("contains line break"), "{0}", stderr);
+}
+
+#[test]
+fn test_check_group_by_cert() {
+    // both servers present the same certificate of localhost
+    let first = domain_name();
+    let second = domain_name();
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "--sort", "input", "--group-by-cert", "check"])
+        .args([&first, &second])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(first, json["domain_name"]);
+    assert_eq!(second, json["shared_with"][0]);
+    assert_eq!(95, json["fingerprint"].as_str().unwrap().len());
+}
+
+#[test]
+fn test_publish() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["publish", "--title", "Status", "--dir"])
+        .arg(dir.path())
+        .arg(domain_name())
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let html = fs::read_to_string(dir.path().join("index.html")).unwrap();
+    assert!(html.contains("<title>Status</title>"), "{0}", html);
+    assert!(html.contains("<td>localhost:"), "{0}", html);
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("status.json")).unwrap()).unwrap();
+    assert_eq!("OK", json["results"][0]["state"]);
+
+    let output = hcc().args(["publish", "example.com"]).output().unwrap();
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn test_check_hook() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hook");
+    let hook = format!("sh -c 'echo $0 >> {0}'", path.to_string_lossy());
+    let domain_name = domain_name();
+    let check = |days: &str| {
+        hcc()
+            .arg("--trust-store")
+            .arg(trusted_ca())
+            .arg("--hook-state-file")
+            .arg(dir.path().join("hooks.json"))
+            .args(["check", "--hook", &hook, "--hook-days", days])
+            .arg(&domain_name)
+            .output()
+            .unwrap()
+    };
+    let output = check("0");
+    assert_eq!(Some(0), output.status.code());
+    assert!(!path.exists());
+    let output = check("36500");
+    assert_eq!(Some(0), output.status.code());
+    let fired = fs::read_to_string(&path).unwrap();
+    assert!(fired.starts_with("localhost:"), "{0}", fired);
+    // no threshold is crossed since
+    let output = check("36500");
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(fired, fs::read_to_string(&path).unwrap());
+}
+
+#[test]
+fn test_check_vault_pki() {
+    use std::io::{BufRead, BufReader};
+
+    let pem = fs::read_to_string(fixture("localhost.pem")).unwrap();
+    let responses = vec![
+        r#"{"data":{"keys":["01-02"]}}"#.to_string(),
+        serde_json::json!({"data": {"certificate": pem, "revocation_time": 0}}).to_string(),
+    ];
+    // fake Vault answering listing then certificate
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for body in responses {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(sock.try_clone().unwrap());
+            let mut line = String::new();
+            while line != "\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            write!(
+                sock,
+                "HTTP/1.1 200 OK\r\nContent-Length: {0}\r\nConnection: close\r\n\r\n{1}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    let output = hcc()
+        .env("VAULT_ADDR", format!("http://{0}", addr))
+        .env("VAULT_TOKEN", "token")
+        .args(["--json", "check", "--vault-pki", "pki"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("vault:pki/01-02", json["domain_name"]);
+    assert!(json["days"].as_i64().unwrap() > 0);
+
+    let output = hcc()
+        .env_remove("VAULT_TOKEN")
+        .args(["check", "--vault-pki", "pki"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn test_check_store() {
+    let output = hcc()
+        .args(["--json", "check-store", "--store"])
+        .arg(fixture("localhost.pem"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(json["domain_name"]
+        .as_str()
+        .unwrap()
+        .ends_with("localhost.pem/localhost"));
+
+    let output = hcc()
+        .args(["check-store", "--store", "missing.pem"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+}
+
+#[test]
+fn test_ssh() {
+    // nothing listens on port once listener is dropped
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let output = hcc()
+        .args(["--json", "ssh", &format!("127.0.0.1:{0}", port)])
+        .output()
+        .unwrap();
+    assert_eq!(Some(3), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("connect_failed", json["error"]["kind"]);
+}
+
+#[test]
+fn test_sign_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("signed.json");
+    let output = hcc()
+        .arg("--sign-key")
+        .arg(fixture("report_key.pem"))
+        .arg("--out")
+        .arg(&path)
+        .args(["--output", "json", "check-file"])
+        .arg(fixture("localhost.pem"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!("ed25519", json["signature"]["algorithm"]);
+    let verify = |path: &std::path::Path| {
+        hcc()
+            .arg("verify-report")
+            .arg("--public-key")
+            .arg(fixture("report_key.pub.pem"))
+            .arg(path)
+            .output()
+            .unwrap()
+    };
+    assert_eq!(Some(0), verify(&path).status.code());
+
+    let report = fs::read_to_string(&path).unwrap();
+    fs::write(&path, report.replace("\"OK\"", "\"WARNING\"")).unwrap();
+    let output = verify(&path);
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("signature does not match"));
+}
+
+#[test]
+fn test_check_agent() {
+    // nothing listens on port of dropped listener, so agent is unreachable
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["--json", "check", "--agent"])
+        .arg(format!("eu-west=http://127.0.0.1:{0}", port))
+        .arg(domain_name())
+        .output()
+        .unwrap();
+    assert_eq!(Some(3), output.status.code());
+    let json: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let at = |location: &str| json.iter().find(|r| r["location"] == location).unwrap();
+    assert_eq!("OK", at("local")["state"]);
+    assert_eq!("connect_failed", at("eu-west")["error"]["kind"]);
+}
+
+#[test]
+fn test_changed_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = dir.path().join("state.json");
+    let check = || {
+        hcc()
+            .arg("--changed-only")
+            .arg("--state-file")
+            .arg(&state)
+            .arg("check-file")
+            .arg(fixture("localhost.pem"))
+            .output()
+            .unwrap()
+    };
+    let output = check();
+    assert_eq!(Some(0), output.status.code());
+    assert!(!output.stdout.is_empty());
+    let output = check();
+    assert_eq!(Some(0), output.status.code());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_changed_only_undelivered() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = dir.path().join("state.json");
+    let config = dir.path().join("hcc.toml");
+    fs::write(
+        &config,
+        r#"
+        [[plugins]]
+        name = "broken"
+        kind = "notifier"
+        command = ["false"]
+        "#,
+    )
+    .unwrap();
+    let check = |notifier: Option<&str>| {
+        let mut cmd = hcc();
+        cmd.arg("--config")
+            .arg(&config)
+            .arg("--changed-only")
+            .arg("--state-file")
+            .arg(&state);
+        if let Some(notifier) = notifier {
+            cmd.arg("--notifier").arg(notifier);
+        }
+        cmd.arg("check-file")
+            .arg(fixture("localhost.pem"))
+            .output()
+            .unwrap()
+    };
+    let output = check(Some("broken"));
+    assert_eq!(Some(0), output.status.code());
+    assert!(!output.stdout.is_empty());
+    // results failed to be delivered are reported again
+    let output = check(None);
+    assert_eq!(Some(0), output.status.code());
+    assert!(!output.stdout.is_empty());
+}
+
+#[test]
+fn test_plugins() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("hcc.toml");
+    let delivered = dir.path().join("notified.ndjson");
+    fs::write(
+        &config,
+        format!(
+            r#"
+            notifiers = ["archive"]
+
+            [[plugins]]
+            name = "count"
+            kind = "formatter"
+            command = ["sh", "-c", "echo $(wc -l) results"]
+
+            [[plugins]]
+            name = "archive"
+            kind = "notifier"
+            command = ["sh", "-c", "cat > {0}"]
+            "#,
+            delivered.display()
+        ),
+    )
+    .unwrap();
+    let output = hcc()
+        .arg("--config")
+        .arg(&config)
+        .arg("--formatter")
+        .arg("count")
+        .arg("check-file")
+        .arg(fixture("localhost.pem"))
+        .output()
+        .unwrap();
+    let notified = fs::read_to_string(&delivered).unwrap();
+    assert_eq!(Some(0), output.status.code());
+    // formatter replaces default text output
+    assert_eq!("1 results\n", String::from_utf8(output.stdout).unwrap());
+    let result: serde_json::Value = serde_json::from_str(notified.trim()).unwrap();
+    assert_eq!("OK", result["state"]);
+
+    let output = hcc()
+        .arg("--config")
+        .arg(&config)
+        .arg("--notifier")
+        .arg("pager")
+        .arg("check-file")
+        .arg(fixture("localhost.pem"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown notifier pager"), "{0}", stderr);
+}
+
+#[test]
+fn test_builtin_formatter_style() {
+    let check = |formatter: Option<&str>| {
+        let mut cmd = hcc();
+        cmd.arg("--human");
+        if let Some(formatter) = formatter {
+            cmd.arg("--formatter").arg(formatter);
+        }
+        cmd.arg("check-file")
+            .arg(fixture("localhost.pem"))
+            .output()
+            .unwrap()
+    };
+    let sink = check(None);
+    let formatter = check(Some("text"));
+    assert_eq!(Some(0), formatter.status.code());
+    assert_eq!(
+        String::from_utf8(sink.stdout).unwrap(),
+        String::from_utf8(formatter.stdout).unwrap()
+    );
+}
+
+#[test]
+fn test_cancellation() {
+    // server accepts connections but never says a word, so checks hang until cancelled
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let silent = format!("localhost:{0}", listener.local_addr().unwrap().port());
+
+    let output = hcc()
+        .args(["--max-runtime", "1s", "--json", "check", &silent])
+        .output()
+        .unwrap();
+    assert_eq!(Some(3), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("skipped", json["error"]["kind"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("run exceeded maximum runtime"),
+        "{0}",
+        stderr
+    );
+
+    let child = hcc()
+        .args(["check", &silent, "example.com"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let killed = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(Some(3), output.status.code());
+    // partial report still lists every domain name
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        2,
+        stdout.matches("run was interrupted").count(),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("hcc.toml");
+    fs::write(
+        &config,
+        "[policy]\nmax_validity_days = 398\nforbidden_signature_algorithms = [\"sha1WithRSAEncryption\"]\n",
+    )
+    .unwrap();
+    let output = hcc()
+        .arg("--config")
+        .arg(&config)
+        .arg("check-file")
+        .arg(fixture("localhost.pem"))
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("violates policy max_validity_days"),
+        "{0}",
+        stdout
+    );
+    assert!(
+        !stdout.contains("forbidden_signature_algorithms"),
+        "{0}",
+        stdout
+    );
+}
+
+#[test]
+fn test_bench() {
+    let output = hcc()
+        .arg("--trust-store")
+        .arg(trusted_ca())
+        .args(["bench", &domain_name(), "--count", "6"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(0), output.status.code());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("handshakes: 6 of 6 completed, 3 of 3 resumed"),
+        "{0}",
+        stdout
+    );
+    assert!(stdout.contains("full handshake: min "), "{0}", stdout);
+    assert!(stdout.contains("resumed handshake: min "), "{0}", stdout);
+
+    let output = hcc()
+        .args(["--json", "bench", &domain_name(), "--count", "0"])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), output.status.code());
+}
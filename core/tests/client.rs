@@ -0,0 +1,46 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use hcc::{CheckClient, CheckState};
+
+use common::{fixture, start_server};
+
+#[tokio::test]
+async fn test_builder() {
+    let port = start_server().port();
+    let ca = fs::read(fixture("ca.pem")).unwrap();
+    let client = CheckClient::builder()
+        .root_certificates(&ca)
+        .unwrap()
+        .only_root_certificates(true)
+        .port(port)
+        .timeout(Some(Duration::from_secs(5)))
+        .build();
+    let result = client.check_certificate("localhost").await.unwrap();
+    assert!(matches!(result.state, CheckState::Ok), "{0}", result);
+    assert_eq!("localhost", result.domain_name);
+}
+
+#[tokio::test]
+async fn test_builder_untrusted() {
+    let port = start_server().port();
+    let client = CheckClient::builder().port(port).build();
+    let result = client.check_certificate("localhost").await.unwrap();
+    assert!(matches!(result.state, CheckState::Expired));
+
+    let client = CheckClient::builder().port(port).insecure(true).build();
+    let result = client.check_certificate("localhost").await.unwrap();
+    assert!(matches!(result.state, CheckState::Ok), "{0}", result);
+}
+
+#[test]
+fn test_builder_proxy() {
+    let mut builder = CheckClient::builder();
+    assert!(builder
+        .proxy(Some("http://proxy.example.com:3128/"))
+        .is_ok());
+    assert!(builder.proxy(Some("proxy.example.com")).is_err());
+    assert!(builder.proxy(None).is_ok());
+}
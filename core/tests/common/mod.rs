@@ -0,0 +1,79 @@
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+
+use rustls::internal::pemfile::{certs, pkcs8_private_keys};
+use rustls::{NoClientAuth, ServerConfig, ServerSession, Session};
+
+/// Path of file in fixtures directory
+pub fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(name)
+}
+
+/// Trust store of test CA only, so chains of test servers are trusted
+pub fn trusted_ca() -> String {
+    format!("ca={0}", fixture("ca.pem").display())
+}
+
+/// Start TLS server on a random local port with certificate of localhost signed by test CA,
+/// serving connections in background until test process exits
+pub fn start_server() -> SocketAddr {
+    start_server_with("localhost.pem", "localhost.key")
+}
+
+/// Start TLS server on a random local port with certificate and key in fixtures directory
+pub fn start_server_with(cert: &str, key: &str) -> SocketAddr {
+    let chain = certs(&mut BufReader::new(File::open(fixture(cert)).unwrap())).unwrap();
+    let key = pkcs8_private_keys(&mut BufReader::new(File::open(fixture(key)).unwrap()))
+        .unwrap()
+        .remove(0);
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.set_single_cert(chain, key).unwrap();
+    let config = Arc::new(config);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for sock in listener.incoming() {
+            let config = config.clone();
+            if let Ok(sock) = sock {
+                thread::spawn(move || serve(sock, &config));
+            }
+        }
+    });
+    addr
+}
+
+fn serve(mut sock: TcpStream, config: &Arc<ServerConfig>) {
+    let mut sess = ServerSession::new(config);
+    let mut tls = rustls::Stream::new(&mut sess, &mut sock);
+    let mut buf = [0; 1024];
+    // clients may hang up right after handshake, so errors are ignored
+    if let Ok(n) = tls.read(&mut buf) {
+        if n > 0 {
+            let _ = tls.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+        }
+    }
+    sess.send_close_notify();
+    let _ = sess.write_tls(&mut sock);
+}
+
+/// Command running hcc binary without environment variables of caller
+pub fn hcc() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hcc"));
+    let snooze_file =
+        std::env::temp_dir().join(format!("hcc-integration-{0}.json", std::process::id()));
+    command
+        .env_clear()
+        .env("HCC_SNOOZE_FILE", snooze_file)
+        .args(["--color", "never"]);
+    command
+}
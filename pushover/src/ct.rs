@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use hcc::CtConfig;
+
+/// Search of crt.sh, which aggregates Certificate Transparency logs
+const CRT_SH: &str = "https://crt.sh/";
+
+/// Certificate logged in Certificate Transparency logs, as listed by crt.sh
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Issuance {
+    /// ID of certificate on crt.sh
+    pub id: u64,
+    pub issuer_name: String,
+    /// Names in certificate separated by newline
+    pub name_value: String,
+    pub not_before: String,
+    pub not_after: String,
+}
+
+impl Issuance {
+    /// Names in certificate e.g. example.com or *.example.com
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.name_value
+            .lines()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Whether certificate is issued by one of expected issuers, any issuer is unexpected if
+    /// none is given
+    pub fn is_expected(&self, expected_issuers: &[String]) -> bool {
+        expected_issuers
+            .iter()
+            .any(|issuer| self.issuer_name.contains(issuer.as_str()))
+    }
+
+    /// Link to certificate on crt.sh
+    pub fn url(&self) -> String {
+        format!("{0}?id={1}", CRT_SH, self.id)
+    }
+}
+
+/// Whether name is domain or its subdomain, wildcards included
+fn covers(domain: &str, name: &str) -> bool {
+    let name = name.trim_start_matches("*.");
+    name.eq_ignore_ascii_case(domain)
+        || name
+            .to_ascii_lowercase()
+            .ends_with(&format!(".{0}", domain.to_ascii_lowercase()))
+}
+
+/// Certificates of domain and its subdomains which have not expired
+pub async fn fetch(client: &reqwest::Client, domain: &str) -> anyhow::Result<Vec<Issuance>> {
+    let issuances = client
+        .get(CRT_SH)
+        .query(&[("q", domain), ("output", "json"), ("exclude", "expired")])
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("failed to search crt.sh for {0}", domain))?
+        .json::<Vec<Issuance>>()
+        .await
+        .with_context(|| format!("failed to parse crt.sh results of {0}", domain))?;
+    Ok(issuances)
+}
+
+/// IDs of certificates seen on crt.sh, kept in JSON file so restarts don't alert again
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Seen {
+    ids: BTreeSet<u64>,
+}
+
+/// Monitor of Certificate Transparency logs for owned domains
+pub struct Monitor {
+    config: CtConfig,
+    state_file: PathBuf,
+    /// None until the first poll, whose certificates are a baseline rather than alerts
+    seen: Option<Seen>,
+}
+
+impl Monitor {
+    /// Monitor with IDs of seen certificates in state file, missing file starts with baseline
+    pub fn load<P: AsRef<Path>>(config: CtConfig, state_file: P) -> anyhow::Result<Self> {
+        let state_file = state_file.as_ref().to_path_buf();
+        let seen = match fs::read_to_string(&state_file) {
+            Ok(s) => Some(
+                serde_json::from_str(&s)
+                    .with_context(|| format!("failed to parse {0:?}", state_file))?,
+            ),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("failed to read {0:?}", state_file)),
+        };
+        Ok(Monitor {
+            config,
+            state_file,
+            seen,
+        })
+    }
+
+    pub fn domains(&self) -> &[String] {
+        &self.config.domains
+    }
+
+    /// Certificates newly seen for owned domains and not issued by expected issuers, all
+    /// certificates are recorded as seen, nothing is unexpected in the first poll
+    pub fn unexpected(&mut self, issuances: Vec<Issuance>) -> Vec<Issuance> {
+        let baseline = self.seen.is_none();
+        let seen = self.seen.get_or_insert_with(Seen::default);
+        let domains = &self.config.domains;
+        let mut unexpected = vec![];
+        for issuance in issuances {
+            if !issuance
+                .names()
+                .any(|name| domains.iter().any(|d| covers(d, name)))
+            {
+                continue;
+            }
+            if !seen.ids.insert(issuance.id) || baseline {
+                continue;
+            }
+            if !issuance.is_expected(&self.config.expected_issuers) {
+                unexpected.push(issuance);
+            }
+        }
+        unexpected
+    }
+
+    /// Save IDs of seen certificates to state file
+    pub fn save(&self) -> anyhow::Result<()> {
+        let seen = match self.seen {
+            Some(ref seen) => seen,
+            None => return Ok(()),
+        };
+        fs::write(&self.state_file, serde_json::to_string(seen)?)
+            .with_context(|| format!("failed to write {0:?}", self.state_file))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hcc::CtConfig;
+
+    use crate::ct::{covers, Issuance, Monitor};
+
+    fn build(id: u64, issuer_name: &str, name_value: &str) -> Issuance {
+        Issuance {
+            id,
+            issuer_name: issuer_name.into(),
+            name_value: name_value.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers("example.com", "example.com"));
+        assert!(covers("example.com", "*.example.com"));
+        assert!(covers("example.com", "API.example.com"));
+        assert!(!covers("example.com", "badexample.com"));
+        assert!(!covers("example.com", "example.com.evil.net"));
+    }
+
+    #[test]
+    fn test_unexpected() {
+        let config = CtConfig {
+            domains: vec!["example.com".into()],
+            expected_issuers: vec!["Let's Encrypt".into()],
+            schedule: None,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ct.json");
+        let mut monitor = Monitor::load(config.clone(), &path).unwrap();
+        // the first poll is baseline
+        let baseline = vec![build(1, "C=US, O=Evil CA", "example.com")];
+        assert!(monitor.unexpected(baseline).is_empty());
+        monitor.save().unwrap();
+
+        let mut monitor = Monitor::load(config, &path).unwrap();
+        let issuances = vec![
+            build(1, "C=US, O=Evil CA", "example.com"),
+            build(2, "C=US, O=Let's Encrypt, CN=R3", "www.example.com"),
+            build(3, "C=US, O=Evil CA", "login.example.com\nexample.com"),
+            build(4, "C=US, O=Evil CA", "example.org"),
+        ];
+        let unexpected = monitor.unexpected(issuances);
+        assert_eq!(vec![3], unexpected.iter().map(|i| i.id).collect::<Vec<_>>());
+        assert_eq!("https://crt.sh/?id=3", unexpected[0].url());
+    }
+}
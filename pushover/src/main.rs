@@ -1,23 +1,40 @@
 #![forbid(unsafe_code)]
+use std::collections::BTreeMap;
 use std::env;
-use std::str::FromStr;
+use std::path::PathBuf;
 use std::time::Duration;
 use std::time::Instant;
 
-use chrono::Utc;
-use cron::Schedule;
-use log::info;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
 use structopt::StructOpt;
 
-use hcc::CheckClient;
+use hcc::{CheckClient, Config, HookState, RunState, Snoozes};
+
+use crate::ct::Monitor;
+use crate::schedule::Plan;
+
+mod ct;
+mod schedule;
 
 #[derive(Debug, StructOpt)]
 #[structopt(author, about)]
 struct Opts {
-    /// Domain names to check, separated by comma e.g. sha512.badssl.com,expired.badssl.com
-    #[structopt(short, long, env = "DOMAIN_NAMES")]
-    domain_names: String,
-    /// Cron
+    /// Domain names to check, separated by comma e.g. sha512.badssl.com,expired.badssl.com,
+    /// defaults to domain names in configuration file
+    #[structopt(short, long, env = "DOMAIN_NAMES", required_unless = "config")]
+    domain_names: Option<String>,
+    /// Configuration file in TOML format, see `hcc --config`
+    #[structopt(long, env = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+    /// Only check domain names tagged with all tags in configuration file, separated by comma
+    /// e.g. prod,eu
+    #[structopt(long, env = "TAGS")]
+    tags: Option<String>,
+    /// Default schedule, cron expression with seconds e.g. "0 */5 * * * * *" or interval e.g.
+    /// 1h, domain names could override it with schedule of their own or of their tags in
+    /// configuration file
     #[structopt(short, long, env = "CRON", default_value = "0 */5 * * * * *")]
     cron: String,
     /// Pushover API key
@@ -26,6 +43,28 @@ struct Opts {
     /// Pushover user key,
     #[structopt(short = "u", long = "user", env = "PUSHOVER_USER")]
     pushover_user: String,
+    /// JSON file of snoozed domain names, managed by `hcc snooze`
+    #[structopt(long, env = "SNOOZE_FILE")]
+    snooze_file: Option<PathBuf>,
+    /// JSON file of certificates seen in Certificate Transparency logs, when ct is configured
+    /// in configuration file
+    #[structopt(long, env = "CT_STATE_FILE", default_value = "hcc-ct.json")]
+    ct_state_file: PathBuf,
+    /// URL to ping after each successful run e.g. of healthchecks.io or Cronitor, so monitoring
+    /// notices when the daemon stops running
+    #[structopt(long, env = "HEARTBEAT_URL")]
+    heartbeat_url: Option<String>,
+    /// Only notify domain names whose state, certificate or bucket of remaining days changed
+    /// since the previous run, or set CHANGED_ONLY=1
+    #[structopt(long)]
+    changed_only: bool,
+    /// JSON file of outcome of each domain name in the previous run, for --changed-only
+    #[structopt(long, env = "STATE_FILE", default_value = "hcc-state.json")]
+    state_file: PathBuf,
+    /// JSON file of remaining days of each domain name in the previous run, so hooks fire only
+    /// as certificate crosses a threshold
+    #[structopt(long, env = "HOOK_STATE_FILE", default_value = "hcc-hooks.json")]
+    hook_state_file: PathBuf,
 }
 
 const PUSHOVER_API: &str = "https://api.pushover.net/1/messages.json";
@@ -38,12 +77,76 @@ async fn main() -> anyhow::Result<()> {
 
     pretty_env_logger::init();
 
-    let opts: Opts = Opts::from_args();
-    let schedule = Schedule::from_str(&opts.cron)?;
+    let mut opts: Opts = Opts::from_args();
+    // clap reads environment variables only for options taking a value
+    opts.changed_only |= env::var("CHANGED_ONLY")
+        .is_ok_and(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"));
+    let default_plan: Plan = opts.cron.parse()?;
+    let config = match opts.config {
+        Some(ref path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let split = |s: &Option<String>| -> Vec<String> {
+        s.iter()
+            .flat_map(|s| s.split(','))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    let domain_names = split(&opts.domain_names);
+    let domain_names: Vec<&str> = domain_names.iter().map(AsRef::as_ref).collect();
+    let domain_names = config.select(&domain_names, &split(&opts.tags));
+    let domain_names = config.expand_ports(&domain_names, &[]);
+
+    // domain names sharing schedule are checked together
+    let mut by_schedule: BTreeMap<Option<&str>, Vec<&str>> = BTreeMap::new();
+    for name in &domain_names {
+        by_schedule
+            .entry(config.schedule_of(name))
+            .or_default()
+            .push(name);
+    }
+    let now = Utc::now();
+    let mut groups = vec![];
+    for (schedule, domain_names) in by_schedule {
+        let plan = match schedule {
+            Some(s) => s
+                .parse()
+                .with_context(|| format!("invalid schedule of {0}", domain_names.join(",")))?,
+            None => default_plan.clone(),
+        };
+        info!(
+            "check HTTPS certficates of {} with schedule {}",
+            domain_names.join(","),
+            schedule.unwrap_or(&opts.cron)
+        );
+        groups.push(Group {
+            next: plan.after(&now),
+            plan,
+            job: Job::Check(domain_names),
+        });
+    }
+    if let Some(ref ct) = config.ct {
+        let plan = match ct.schedule {
+            Some(ref s) => s.parse().context("invalid schedule of ct")?,
+            None => default_plan.clone(),
+        };
+        info!(
+            "monitor Certificate Transparency logs for {} with schedule {}",
+            ct.domains.join(","),
+            ct.schedule.as_deref().unwrap_or(&opts.cron)
+        );
+        let monitor = Monitor::load(ct.clone(), &opts.ct_state_file)?;
+        groups.push(Group {
+            next: plan.after(&now),
+            plan,
+            job: Job::Ct(monitor),
+        });
+    }
 
-    info!("check HTTPS certficates with cron {}", &opts.cron);
-    for datetime in schedule.upcoming(Utc) {
-        info!("check certificate of {} at {}", opts.domain_names, datetime);
+    while let Some(datetime) = groups.iter().filter_map(|g| g.next).min() {
+        info!("next check at {}", datetime);
         loop {
             if Utc::now() > datetime {
                 break;
@@ -51,41 +154,197 @@ async fn main() -> anyhow::Result<()> {
                 tokio::time::sleep(Duration::from_millis(999)).await;
             }
         }
-        let instant = Instant::now();
-        let domain_names: Vec<_> = opts.domain_names.split(',').collect();
-        check_domain_names(&opts, &domain_names).await?;
-        let duration = Instant::now() - instant;
-        info!("done in {}ms", duration.as_millis());
+        for group in groups.iter_mut().filter(|g| g.next == Some(datetime)) {
+            let instant = Instant::now();
+            match group.job {
+                Job::Check(ref domain_names) => {
+                    info!(
+                        "check certificate of {} at {}",
+                        domain_names.join(","),
+                        datetime
+                    );
+                    check_domain_names(&opts, &config, domain_names).await?;
+                }
+                Job::Ct(ref mut monitor) => {
+                    info!(
+                        "poll Certificate Transparency logs for {} at {}",
+                        monitor.domains().join(","),
+                        datetime
+                    );
+                    poll_ct(&opts, monitor).await?;
+                }
+            }
+            let duration = Instant::now() - instant;
+            info!("done in {}ms", duration.as_millis());
+            if let Some(ref url) = opts.heartbeat_url {
+                heartbeat(url).await;
+            }
+            group.next = group.plan.after(&Utc::now());
+        }
     }
 
     Ok(())
 }
 
-async fn check_domain_names(opts: &Opts, domain_names: &[&str]) -> anyhow::Result<()> {
+/// Job run on the same schedule
+struct Group<'a> {
+    plan: Plan,
+    job: Job<'a>,
+    /// When to check next, none if schedule has no upcoming time
+    next: Option<DateTime<Utc>>,
+}
+
+enum Job<'a> {
+    /// Check certificates of domain names
+    Check(Vec<&'a str>),
+    /// Poll Certificate Transparency logs for certificates issued unexpectedly
+    Ct(Monitor),
+}
+
+/// Ping heartbeat URL, failure is only logged since the run itself succeeded
+async fn heartbeat(url: &str) {
+    let pinged = reqwest::Client::new()
+        .get(url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    match pinged {
+        Ok(_) => info!("pinged heartbeat"),
+        Err(e) => warn!("failed to ping heartbeat: {}", e),
+    }
+}
+
+/// Send notification to Pushover
+fn notify(
+    client: &reqwest::Client,
+    opts: &Opts,
+    title: &str,
+    message: &str,
+) -> impl std::future::Future<Output = reqwest::Result<reqwest::Response>> {
+    let form = [
+        ("message", message),
+        ("user", &opts.pushover_user),
+        ("token", &opts.pushover_token),
+        ("title", title),
+    ];
+    client.post(PUSHOVER_API).form(&form).send()
+}
+
+/// Notify certificates newly logged for owned domains and issued by unexpected issuers, a
+/// domain failing to be searched is skipped until next poll
+async fn poll_ct(opts: &Opts, monitor: &mut Monitor) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let mut futs = vec![];
+    for domain in monitor.domains().to_vec() {
+        let issuances = match ct::fetch(&client, &domain).await {
+            Ok(issuances) => issuances,
+            Err(e) => {
+                warn!("{:#}", e);
+                continue;
+            }
+        };
+        for issuance in monitor.unexpected(issuances) {
+            let names: Vec<&str> = issuance.names().collect();
+            let message = format!(
+                "certificate for {} issued by {}\nvalid from {} to {}\n{}",
+                names.join(", "),
+                issuance.issuer_name,
+                issuance.not_before,
+                issuance.not_after,
+                issuance.url()
+            );
+            info!(
+                "notify unexpected certificate {} of {}",
+                issuance.id, domain
+            );
+            let title = format!("Unexpected certificate - {}", domain);
+            futs.push(notify(&client, opts, &title, &message));
+        }
+    }
+    futures::future::try_join_all(futs).await?;
+    monitor.save()
+}
+
+async fn check_domain_names(
+    opts: &Opts,
+    config: &Config,
+    domain_names: &[&str],
+) -> anyhow::Result<()> {
     let check_client = CheckClient::new();
     let results = check_client.check_certificates(domain_names)?;
 
+    let snoozes = match opts.snooze_file {
+        Some(ref path) => Snoozes::load(path)?,
+        None => Snoozes::default(),
+    };
+    let now = Utc::now();
+    let mut state = if opts.changed_only {
+        Some(RunState::load(&opts.state_file)?)
+    } else {
+        None
+    };
+    let mut hook_state = if config.hooks.is_empty() {
+        None
+    } else {
+        Some(HookState::load(&opts.hook_state_file)?)
+    };
+
     let mut futs = vec![];
 
     let pushover_client = reqwest::Client::new();
-    for result in results {
+    let grace_in_days = config.grace_in_days.unwrap_or(7);
+    for mut result in results {
+        if let Some(until) = snoozes.until(result.domain_name, &now) {
+            info!("{} is snoozed until {}", result.domain_name, until);
+            continue;
+        }
+
+        result.tags = config.tags_of(result.domain_name).to_vec();
+        if let Some(ref mut hook_state) = hook_state {
+            for hook in &config.hooks {
+                if hook_state.fires(hook, &result, grace_in_days) {
+                    info!("fire hook of {}", result.domain_name);
+                    // hooks run commands and block on webhooks
+                    if let Err(e) = tokio::task::block_in_place(|| hook.fire(&result)) {
+                        warn!("hook of {} failed: {:#}", result.domain_name, e);
+                    }
+                }
+                hook_state.record(hook, &result, grace_in_days);
+            }
+        }
+
+        if let Some(ref mut state) = state {
+            let changed = state.changed(&result, grace_in_days);
+            state.record(&result, grace_in_days);
+            if !changed {
+                info!("{} is unchanged since the previous run", result.domain_name);
+                continue;
+            }
+        }
+
         let state_icon = result.state_icon(true);
         let sentence = result.sentence();
 
-        let message = format!("{} {}", state_icon, sentence);
-        let form = [
-            ("message", &message),
-            ("user", &opts.pushover_user),
-            ("token", &opts.pushover_token),
-            (
-                "title",
-                &format!("HTTP Certificate Check - {}", result.domain_name),
-            ),
-        ];
-        futs.push(pushover_client.post(PUSHOVER_API).form(&form).send());
+        let mut message = format!("{} {}\ncheck ID: {}", state_icon, sentence, result.id);
+        let tags = config.tags_of(result.domain_name);
+        if !tags.is_empty() {
+            message.push_str(&format!("\ntags: {}", tags.join(", ")));
+        }
+        let title = format!("HTTP Certificate Check - {}", result.domain_name);
+        info!("notify {} of check {}", result.domain_name, result.id);
+        futs.push(notify(&pushover_client, opts, &title, &message));
     }
 
+    // hooks have fired whether notifications are sent or not
+    if let Some(hook_state) = hook_state {
+        hook_state.save(&opts.hook_state_file)?;
+    }
     futures::future::try_join_all(futs).await?;
+    // state is saved once notified, so a failed notification is sent again next run
+    if let Some(state) = state {
+        state.save(&opts.state_file)?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
+
+/// When domain names are checked, by cron expression or fixed interval
+#[derive(Clone, Debug)]
+pub enum Plan {
+    /// Cron expression with seconds and optional year e.g. 0 0 * * * * *
+    Cron(Box<Schedule>),
+    /// Fixed interval e.g. 1h
+    Every(Duration),
+}
+
+impl FromStr for Plan {
+    type Err = anyhow::Error;
+
+    /// Parse cron expression, or interval in seconds, minutes, hours or days e.g. 90s, 30m, 1h
+    /// or 1d, cron expressions are told apart by their spaces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains(char::is_whitespace) {
+            let schedule =
+                Schedule::from_str(s).with_context(|| format!("invalid cron expression {0}", s))?;
+            return Ok(Plan::Cron(Box::new(schedule)));
+        }
+        let (n, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        let n: i64 = n
+            .parse()
+            .with_context(|| format!("invalid interval {0}", s))?;
+        let interval = match unit {
+            "" | "s" => Duration::seconds(n),
+            "m" => Duration::minutes(n),
+            "h" => Duration::hours(n),
+            "d" => Duration::days(n),
+            _ => bail!("unknown unit of interval {0}, expect s, m, h or d", s),
+        };
+        if n == 0 {
+            bail!("interval {0} must be positive", s);
+        }
+        Ok(Plan::Every(interval))
+    }
+}
+
+impl Plan {
+    /// When to check next after given time, none if cron expression has no upcoming time
+    pub fn after(&self, time: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Plan::Cron(schedule) => schedule.after(time).next(),
+            Plan::Every(interval) => Some(*time + *interval),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::schedule::Plan;
+
+    #[test]
+    fn test_parse() {
+        assert!(matches!("0 */5 * * * * *".parse(), Ok(Plan::Cron(_))));
+        assert!(matches!("1h".parse(), Ok(Plan::Every(d)) if d == Duration::hours(1)));
+        assert!(matches!("90".parse(), Ok(Plan::Every(d)) if d == Duration::seconds(90)));
+        assert!("1w".parse::<Plan>().is_err());
+        assert!("0m".parse::<Plan>().is_err());
+        assert!("0 */5 * *".parse::<Plan>().is_err());
+    }
+
+    #[test]
+    fn test_after() {
+        let now = Utc.ymd(2021, 6, 1).and_hms(12, 34, 56);
+        let hourly: Plan = "0 0 * * * * *".parse().unwrap();
+        assert_eq!(
+            Some(Utc.ymd(2021, 6, 1).and_hms(13, 0, 0)),
+            hourly.after(&now)
+        );
+        let daily: Plan = "1d".parse().unwrap();
+        assert_eq!(
+            Some(Utc.ymd(2021, 6, 2).and_hms(12, 34, 56)),
+            daily.after(&now)
+        );
+        let past: Plan = "0 0 0 1 1 * 2000".parse().unwrap();
+        assert_eq!(None, past.after(&now));
+    }
+}
@@ -0,0 +1,151 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use warp::http::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use warp::http::StatusCode;
+use warp::{Filter, Rejection, Reply};
+
+/// Credentials of basic authentication as USER:PASSWORD
+#[derive(Clone, Debug, PartialEq)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl FromStr for BasicAuth {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (username, password) = s
+            .split_once(':')
+            .context("expect USER:PASSWORD for basic authentication")?;
+        Ok(BasicAuth {
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+}
+
+/// Accepted credentials of API, any request is accepted when none is configured
+#[derive(Clone, Debug, Default)]
+pub struct Auth {
+    token: Option<String>,
+    basic: Option<String>,
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+impl Auth {
+    pub fn new(token: Option<String>, basic: Option<BasicAuth>) -> Self {
+        Auth {
+            token,
+            basic: basic.map(|b| base64::encode(format!("{0}:{1}", b.username, b.password))),
+        }
+    }
+
+    /// Whether value of Authorization header carries accepted credentials
+    pub fn authorize(&self, header: Option<&str>) -> bool {
+        if self.token.is_none() && self.basic.is_none() {
+            return true;
+        }
+        let header = match header {
+            Some(header) => header,
+            None => return false,
+        };
+        let accepted = |scheme: &str, expected: &Option<String>| match expected {
+            Some(expected) => header.strip_prefix(scheme).is_some_and(|given| {
+                constant_time_eq(given.trim().as_bytes(), expected.as_bytes())
+            }),
+            None => false,
+        };
+        accepted("Bearer ", &self.token) || accepted("Basic ", &self.basic)
+    }
+
+    /// Reject request without accepted credentials
+    pub fn filter(self) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+        warp::header::optional::<String>(AUTHORIZATION.as_str())
+            .and_then(move |header: Option<String>| {
+                let authorized = self.authorize(header.as_deref());
+                async move {
+                    if authorized {
+                        Ok(())
+                    } else {
+                        Err(warp::reject::custom(Unauthorized))
+                    }
+                }
+            })
+            .untuple_one()
+    }
+
+    /// Reply 401 with challenge of configured scheme to unauthorized request
+    pub async fn recover(self, rejection: Rejection) -> Result<impl Reply, Rejection> {
+        if rejection.find::<Unauthorized>().is_none() {
+            return Err(rejection);
+        }
+        let challenge = if self.basic.is_some() {
+            "Basic realm=\"hcc\""
+        } else {
+            "Bearer"
+        };
+        let mut reply =
+            warp::reply::with_status("unauthorized", StatusCode::UNAUTHORIZED).into_response();
+        reply
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, HeaderValue::from_static(challenge));
+        Ok(reply)
+    }
+}
+
+/// Compare without leaking position of first difference through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use warp::Filter;
+
+    use crate::auth::{Auth, BasicAuth};
+
+    #[test]
+    fn test_authorize() {
+        assert!(Auth::default().authorize(None));
+
+        let auth = Auth::new(Some("secret".into()), None);
+        assert!(auth.authorize(Some("Bearer secret")));
+        assert!(!auth.authorize(Some("Bearer secreT")));
+        assert!(!auth.authorize(Some("Basic secret")));
+        assert!(!auth.authorize(None));
+
+        let basic: BasicAuth = "admin:p:ss".parse().unwrap();
+        assert_eq!("p:ss", basic.password);
+        let auth = Auth::new(None, Some(basic));
+        assert!(auth.authorize(Some("Basic YWRtaW46cDpzcw==")));
+        assert!(!auth.authorize(Some("Bearer YWRtaW46cDpzcw==")));
+
+        assert!("admin".parse::<BasicAuth>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_filter() {
+        let auth = Auth::new(Some("secret".into()), None);
+        let route = auth
+            .clone()
+            .filter()
+            .map(|| "ok")
+            .recover(move |r| auth.clone().recover(r));
+
+        let res = warp::test::request().reply(&route).await;
+        assert_eq!(401, res.status());
+        assert_eq!("Bearer", res.headers()["www-authenticate"]);
+
+        let res = warp::test::request()
+            .header("authorization", "Bearer secret")
+            .reply(&route)
+            .await;
+        assert_eq!(200, res.status());
+    }
+}
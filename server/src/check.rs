@@ -0,0 +1,178 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use warp::http::StatusCode;
+
+use hcc::{CheckClient, CheckResultJSON, StartTls};
+
+use crate::health::Health;
+use crate::results::Results;
+use crate::ErrorMessage;
+
+/// Body of POST /check, domain names are checked immediately with options
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CheckRequest {
+    /// One or many domain names, optionally with port e.g. example.com:8443
+    #[serde(alias = "domains")]
+    pub domain_names: Vec<String>,
+    /// Port of domain names without one, defaults to 443 or well-known port of STARTTLS
+    pub port: Option<u16>,
+    /// Upgrade plaintext connection to TLS with protocol, smtp, xmpp, xmpp-server or ldap
+    pub starttls: Option<String>,
+    /// Server name to send in handshake instead of host
+    pub sni: Option<String>,
+    /// Grace period in days
+    pub grace_in_days: Option<i64>,
+    /// Seconds to wait for connection and response of server
+    pub timeout: Option<u64>,
+}
+
+impl CheckRequest {
+    /// Client configured by options of request
+    pub fn client(&self) -> anyhow::Result<CheckClient> {
+        if self.domain_names.is_empty() {
+            anyhow::bail!("no domain name to check");
+        }
+        let starttls = self
+            .starttls
+            .as_deref()
+            .map(str::parse::<StartTls>)
+            .transpose()?;
+        let mut builder = CheckClient::builder();
+        builder
+            .elapsed(true)
+            .sni(self.sni.clone())
+            .starttls(starttls)
+            .timeout(self.timeout.map(Duration::from_secs));
+        match (self.port, starttls) {
+            (Some(port), _) => builder.port(port),
+            (None, Some(starttls)) => builder.port(starttls.default_port()),
+            (None, None) => &mut builder,
+        };
+        if let Some(grace_in_days) = self.grace_in_days {
+            builder.grace_in_days(grace_in_days);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Check domain names in request immediately, results are recorded and always replied as list,
+/// located at location of server if any, invalid request is replied with 400
+pub async fn check(
+    request: CheckRequest,
+    store: Arc<Results>,
+    health: Arc<Health>,
+    location: Option<String>,
+) -> Result<impl warp::Reply, Infallible> {
+    let bad_request = |e: anyhow::Error| {
+        let message = ErrorMessage {
+            message: format!("{:#}", e),
+        };
+        Ok(warp::reply::with_status(
+            warp::reply::json(&message),
+            StatusCode::BAD_REQUEST,
+        ))
+    };
+    let client = match request.client() {
+        Ok(client) => client,
+        Err(e) => return bad_request(e),
+    };
+    let in_flight = health.begin();
+    let checked = tokio::task::spawn_blocking(move || {
+        let domain_names: Vec<&str> = request.domain_names.iter().map(AsRef::as_ref).collect();
+        let results = client.check_certificates(&domain_names)?;
+        Ok::<_, anyhow::Error>(results.iter().map(CheckResultJSON::new).collect::<Vec<_>>())
+    })
+    .await;
+    let mut json = match checked {
+        Ok(Ok(json)) => json,
+        Ok(Err(e)) => return bad_request(e),
+        Err(e) => return bad_request(e.into()),
+    };
+    in_flight.succeed();
+    for result in json.iter_mut() {
+        result.location = location.clone();
+    }
+    store.record_all(json.clone()).await;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json),
+        StatusCode::OK,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    use warp::http::StatusCode;
+    use warp::Reply;
+
+    use crate::check::{check, CheckRequest};
+    use crate::health::Health;
+    use crate::results::Results;
+
+    #[test]
+    fn test_client() {
+        let request = CheckRequest {
+            domain_names: vec!["mail.example.com".into()],
+            starttls: Some("smtp".into()),
+            ..Default::default()
+        };
+        assert!(request.client().is_ok());
+
+        let request = CheckRequest {
+            domain_names: vec!["mail.example.com".into()],
+            starttls: Some("pop3".into()),
+            ..Default::default()
+        };
+        assert!(request.client().is_err());
+        assert!(CheckRequest::default().client().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        // nothing listens on port of dropped listener, so connection is refused
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let request = CheckRequest {
+            domain_names: vec!["127.0.0.1".into()],
+            port: Some(port),
+            sni: Some("localhost".into()),
+            ..Default::default()
+        };
+        let store = Arc::new(Results::default());
+        let location = Some("eu-west".to_string());
+        let reply = check(
+            request,
+            store.clone(),
+            Arc::new(Health::default()),
+            location,
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(StatusCode::OK, reply.status());
+        let results = store.list();
+        assert_eq!(1, results.len());
+        assert_eq!("connect_failed", results[0].error.as_ref().unwrap().kind());
+        assert_eq!(Some("eu-west"), results[0].location.as_deref());
+
+        let reply = check(
+            CheckRequest::default(),
+            Arc::new(Results::default()),
+            Arc::new(Health::default()),
+            None,
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(StatusCode::BAD_REQUEST, reply.status());
+    }
+}
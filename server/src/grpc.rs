@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use hcc::{CheckClient, CheckResultJSON};
+
+use crate::auth::Auth;
+use crate::health::Health;
+use crate::results::Results;
+
+pub mod proto {
+    tonic::include_proto!("hcc");
+}
+
+use proto::checker_server::Checker;
+pub use proto::checker_server::CheckerServer;
+use proto::{CheckRequest, CheckResult, ListResultsReply, ListResultsRequest, WatchResultsRequest};
+
+impl From<CheckResultJSON> for CheckResult {
+    fn from(json: CheckResultJSON) -> Self {
+        CheckResult {
+            id: json.id,
+            state: json.state,
+            checked_at: json.checked_at,
+            days: json.days,
+            domain_name: json.domain_name,
+            expired_at: json.expired_at,
+            elapsed: json.elapsed as u64,
+            findings: json.findings,
+            error_kind: json
+                .error
+                .as_ref()
+                .map(|e| e.kind().to_string())
+                .unwrap_or_default(),
+            error: json.error.map(|e| e.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Interceptor rejecting requests without accepted credentials in authorization metadata
+#[allow(clippy::result_large_err)] // signature required by tonic
+pub fn authorize(auth: &Auth, request: Request<()>) -> Result<Request<()>, Status> {
+    let header = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok());
+    if auth.authorize(header) {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("unauthorized"))
+    }
+}
+
+/// gRPC service sharing results with REST API
+pub struct CheckerService {
+    pub results: Arc<Results>,
+    pub health: Arc<Health>,
+}
+
+#[tonic::async_trait]
+impl Checker for CheckerService {
+    async fn check(&self, request: Request<CheckRequest>) -> Result<Response<CheckResult>, Status> {
+        let domain_name = request.into_inner().domain_name;
+        let in_flight = self.health.begin();
+        let json = tokio::task::spawn_blocking(move || {
+            // client remembers when it's built, so build one for each request as POST /check does
+            let client = CheckClient::builder().elapsed(true).build();
+            let domain_names = [domain_name.as_str()];
+            let results = client.check_certificates(&domain_names)?;
+            Ok::<_, anyhow::Error>(CheckResultJSON::new(&results[0]))
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::invalid_argument(format!("{:?}", e)))?;
+        in_flight.succeed();
+        self.results.clone().record_all(vec![json.clone()]).await;
+        Ok(Response::new(json.into()))
+    }
+
+    async fn list_results(
+        &self,
+        _request: Request<ListResultsRequest>,
+    ) -> Result<Response<ListResultsReply>, Status> {
+        let results = self.results.list().into_iter().map(Into::into).collect();
+        Ok(Response::new(ListResultsReply { results }))
+    }
+
+    type WatchResultsStream = ReceiverStream<Result<CheckResult, Status>>;
+
+    async fn watch_results(
+        &self,
+        _request: Request<WatchResultsRequest>,
+    ) -> Result<Response<Self::WatchResultsStream>, Status> {
+        let mut receiver = self.results.subscribe();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            loop {
+                let item = match receiver.recv().await {
+                    Ok(json) => Ok(json.into()),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        Err(Status::data_loss(format!("missed {0} result(s)", n)))
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                // watcher hung up
+                if tx.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use tonic::Request;
+
+    use hcc::CheckResultJSON;
+
+    use crate::grpc::proto::checker_server::Checker;
+    use crate::grpc::proto::ListResultsRequest;
+    use crate::grpc::CheckerService;
+    use crate::health::Health;
+    use crate::results::Results;
+
+    #[tokio::test]
+    async fn test_list_results() {
+        let service = CheckerService {
+            results: Arc::new(Results::default()),
+            health: Arc::new(Health::default()),
+        };
+        service.results.record(CheckResultJSON {
+            domain_name: "example.com".into(),
+            state: "OK".into(),
+            elapsed: 42,
+            ..Default::default()
+        });
+        let reply = service
+            .list_results(Request::new(ListResultsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(1, reply.results.len());
+        assert_eq!("example.com", reply.results[0].domain_name);
+        assert_eq!(42, reply.results[0].elapsed);
+    }
+}
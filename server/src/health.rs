@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Liveness and load of server, shared by REST and gRPC
+pub struct Health {
+    started_at: Instant,
+    in_flight: AtomicUsize,
+    last_progress: Mutex<Instant>,
+    last_success: Mutex<Option<Instant>>,
+}
+
+/// Report of health served by /healthz and /readyz
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// Whether checks are in flight without any completing within stall timeout
+    pub stalled: bool,
+    pub uptime_seconds: u64,
+    /// Number of checks in flight
+    pub queue_depth: usize,
+    /// Seconds since the last check completed without error, none if nothing succeeded yet
+    pub last_success_age_seconds: Option<u64>,
+}
+
+/// Check in flight, counted until dropped
+pub struct InFlight<'a>(&'a Health);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+        *self.0.last_progress.lock().unwrap() = Instant::now();
+    }
+}
+
+impl InFlight<'_> {
+    /// Mark check completed without error
+    pub fn succeed(self) {
+        *self.0.last_success.lock().unwrap() = Some(Instant::now());
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        let now = Instant::now();
+        Health {
+            started_at: now,
+            in_flight: AtomicUsize::new(0),
+            last_progress: Mutex::new(now),
+            last_success: Mutex::new(None),
+        }
+    }
+}
+
+impl Health {
+    /// Count a check in flight until returned guard is dropped
+    pub fn begin(&self) -> InFlight<'_> {
+        if self.in_flight.fetch_add(1, Ordering::SeqCst) == 0 {
+            // stall is measured from when server gets busy
+            *self.last_progress.lock().unwrap() = Instant::now();
+        }
+        InFlight(self)
+    }
+
+    pub fn report(&self, stall_timeout: Duration) -> HealthReport {
+        self.report_at(Instant::now(), stall_timeout)
+    }
+
+    fn report_at(&self, now: Instant, stall_timeout: Duration) -> HealthReport {
+        let queue_depth = self.in_flight.load(Ordering::SeqCst);
+        let last_progress = *self.last_progress.lock().unwrap();
+        HealthReport {
+            stalled: queue_depth > 0 && now.duration_since(last_progress) > stall_timeout,
+            uptime_seconds: now.duration_since(self.started_at).as_secs(),
+            queue_depth,
+            last_success_age_seconds: self
+                .last_success
+                .lock()
+                .unwrap()
+                .map(|t| now.duration_since(t).as_secs()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::health::Health;
+
+    #[test]
+    fn test_report() {
+        let health = Health::default();
+        let timeout = Duration::from_secs(60);
+        let later = Instant::now() + Duration::from_secs(120);
+
+        let report = health.report_at(later, timeout);
+        assert!(!report.stalled);
+        assert_eq!(0, report.queue_depth);
+        assert_eq!(None, report.last_success_age_seconds);
+
+        let in_flight = health.begin();
+        let report = health.report_at(later, timeout);
+        assert!(report.stalled);
+        assert_eq!(1, report.queue_depth);
+
+        in_flight.succeed();
+        let report = health.report_at(later, timeout);
+        assert!(!report.stalled);
+        assert_eq!(0, report.queue_depth);
+        assert!(report.last_success_age_seconds.is_some());
+    }
+}
@@ -1,51 +1,199 @@
 #![forbid(unsafe_code)]
 use std::convert::Infallible;
 use std::env;
+use std::fs;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::Context;
 use log::info;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+use tonic::transport::{Identity, ServerTlsConfig};
+use warp::http::StatusCode;
 use warp::Filter;
 
 use hcc::{CheckClient, CheckResultJSON};
 
+use crate::auth::{Auth, BasicAuth};
+use crate::grpc::{CheckerServer, CheckerService};
+use crate::health::Health;
+use crate::results::Results;
+
+mod auth;
+mod check;
+mod grpc;
+mod health;
+mod results;
+mod store;
+
 #[derive(Debug, StructOpt)]
 #[structopt(author, about)]
 struct Opts {
     /// host:port to be bound to the server
     #[structopt(short, long, default_value = "127.0.0.1:9292")]
     bind: String,
+    /// host:port to be bound to the gRPC service, disabled if not given
+    #[structopt(long)]
+    grpc_bind: Option<String>,
+    /// Seconds without any check completing while checks are in flight, after which /healthz
+    /// and /readyz report the server as stalled
+    #[structopt(long, default_value = "300")]
+    stall_timeout: u64,
+    /// Number of checks in flight, at or beyond which /readyz reports the server as not ready
+    #[structopt(long, default_value = "64")]
+    max_queue_depth: usize,
+    /// Bearer token required by API except /healthz and /readyz
+    #[structopt(long, env = "HCC_SERVER_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Credentials of basic authentication required by API except /healthz and /readyz, as
+    /// USER:PASSWORD
+    #[structopt(long, env = "HCC_SERVER_BASIC_AUTH", hide_env_values = true)]
+    basic_auth: Option<BasicAuth>,
+    /// Certificate chain in PEM format to serve API over HTTPS, and gRPC over TLS, requires
+    /// --tls-key
+    #[structopt(long, requires = "tls-key")]
+    tls_cert: Option<PathBuf>,
+    /// Private key in PEM format to serve API over HTTPS, and gRPC over TLS, requires
+    /// --tls-cert
+    #[structopt(long, requires = "tls-cert")]
+    tls_key: Option<PathBuf>,
+    /// Database to store history of results in, sqlite:PATH, postgres://USER@HOST/NAME when
+    /// built with feature postgres, or memory to keep results until restart
+    #[structopt(
+        long,
+        env = "HCC_DATABASE",
+        default_value = "sqlite:hcc-server.db",
+        hide_env_values = true
+    )]
+    database: String,
+    /// Location of server e.g. eu-west, results are reported as seen from it when server runs
+    /// as agent of `hcc check --agent`
+    #[structopt(long, env = "HCC_LOCATION")]
+    location: Option<String>,
 }
 
+/// Longest body of request accepted
+const MAX_BODY: u64 = 64 * 1024;
+
 #[derive(Serialize)]
-struct ErrorMessage {
-    message: String,
+pub(crate) struct ErrorMessage {
+    pub(crate) message: String,
 }
 
 async fn show_domain_name(
     domain_names: String,
     client: Arc<CheckClient>,
+    store: Arc<Results>,
+    health: Arc<Health>,
+    location: Option<String>,
 ) -> Result<impl warp::Reply, Infallible> {
     let domain_names: Vec<&str> = domain_names.split(',').map(|s| s.trim()).collect();
+    let in_flight = health.begin();
     let results = match client.check_certificates(domain_names.as_slice()) {
-        Ok(r) => r,
+        Ok(r) => {
+            in_flight.succeed();
+            r
+        }
         Err(e) => {
             return Ok(warp::reply::json(&ErrorMessage {
                 message: format!("{:?}", e),
             }));
         }
     };
-    if results.len() == 1 {
-        let json = CheckResultJSON::new(results.first().unwrap());
-        Ok(warp::reply::json(&json))
+    let mut json: Vec<CheckResultJSON> = results.iter().map(CheckResultJSON::new).collect();
+    for result in json.iter_mut() {
+        result.location = location.clone();
+    }
+    store.record_all(json.clone()).await;
+    if json.len() == 1 {
+        Ok(warp::reply::json(&json[0]))
     } else {
-        let json: Vec<CheckResultJSON> = results.iter().map(|r| CheckResultJSON::new(&r)).collect();
         Ok(warp::reply::json(&json))
     }
 }
 
+/// Query of history of domain name
+#[derive(Deserialize)]
+struct HistoryQuery {
+    /// Maximum number of results, 100 by default
+    limit: Option<usize>,
+}
+
+/// Results of domain name in store, the latest first
+fn show_history(
+    domain_name: String,
+    query: HistoryQuery,
+    store: Arc<Results>,
+) -> Box<dyn warp::Reply> {
+    match store.history(&domain_name, query.limit.unwrap_or(100)) {
+        Ok(history) => Box::new(warp::reply::json(&history)),
+        Err(e) => Box::new(warp::reply::with_status(
+            warp::reply::json(&ErrorMessage {
+                message: format!("{:#}", e),
+            }),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+/// Liveness, unavailable when server is stalled
+fn healthz(health: Arc<Health>, stall_timeout: Duration) -> impl warp::Reply {
+    let report = health.report(stall_timeout);
+    let status = if report.stalled {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    warp::reply::with_status(warp::reply::json(&report), status)
+}
+
+/// Readiness, unavailable when server is stalled or too busy
+fn readyz(
+    health: Arc<Health>,
+    stall_timeout: Duration,
+    max_queue_depth: usize,
+) -> impl warp::Reply {
+    let report = health.report(stall_timeout);
+    let status = if report.stalled || report.queue_depth >= max_queue_depth {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    warp::reply::with_status(warp::reply::json(&report), status)
+}
+
+/// Push each result as Server-Sent Event named result as checks complete
+fn watch_results(results: Arc<Results>) -> impl warp::Reply {
+    // results missed by slow clients are skipped
+    let events = BroadcastStream::new(results.subscribe())
+        .filter_map(|r| r.ok())
+        .map(|json| warp::sse::Event::default().event("result").json_data(json));
+    warp::sse::reply(warp::sse::keep_alive().stream(events))
+}
+
+fn with_health(
+    health: Arc<Health>,
+) -> impl Filter<Extract = (Arc<Health>,), Error = Infallible> + Clone {
+    warp::any().map(move || health.clone())
+}
+
+fn with_results(
+    results: Arc<Results>,
+) -> impl Filter<Extract = (Arc<Results>,), Error = Infallible> + Clone {
+    warp::any().map(move || results.clone())
+}
+
+fn with_location(
+    location: Option<String>,
+) -> impl Filter<Extract = (Option<String>,), Error = Infallible> + Clone {
+    warp::any().map(move || location.clone())
+}
+
 fn with_client(
     client: Arc<CheckClient>,
 ) -> impl Filter<Extract = (Arc<CheckClient>,), Error = Infallible> + Clone {
@@ -61,18 +209,113 @@ async fn main() -> anyhow::Result<()> {
 
     let opts: Opts = Opts::from_args();
     let client = Arc::new(CheckClient::builder().elapsed(true).build());
+    let results = Arc::new(Results::new(store::open(&opts.database)?)?);
+    let health = Arc::new(Health::default());
+    let auth = Auth::new(opts.token.clone(), opts.basic_auth.clone());
+    let stall_timeout = Duration::from_secs(opts.stall_timeout);
+    let max_queue_depth = opts.max_queue_depth;
+
+    let grpc = match opts.grpc_bind {
+        Some(ref grpc_bind) => {
+            let addr: SocketAddr = grpc_bind.parse()?;
+            let service = CheckerService {
+                results: results.clone(),
+                health: health.clone(),
+            };
+            info!("gRPC served on {0}", grpc_bind);
+            let grpc_auth = auth.clone();
+            #[allow(clippy::result_large_err)] // signature required by tonic
+            let interceptor = move |request| grpc::authorize(&grpc_auth, request);
+            let mut builder = tonic::transport::Server::builder();
+            // gRPC carries the same credentials as HTTP, so it is served over TLS alike
+            if let (Some(ref cert), Some(ref key)) = (&opts.tls_cert, &opts.tls_key) {
+                let cert = fs::read(cert).with_context(|| format!("failed to read {0:?}", cert))?;
+                let key = fs::read(key).with_context(|| format!("failed to read {0:?}", key))?;
+                let identity = Identity::from_pem(cert, key);
+                builder = builder.tls_config(ServerTlsConfig::new().identity(identity))?;
+            }
+            let server = builder
+                .add_service(CheckerServer::with_interceptor(service, interceptor))
+                .serve(addr);
+            Some(server)
+        }
+        None => None,
+    };
+    // server fails as a whole when gRPC fails e.g. to bind, rather than going on with HTTP alone
+    let grpc = async move {
+        match grpc {
+            Some(server) => server.await.context("gRPC server failed"),
+            None => Ok(()),
+        }
+    };
+
+    let healthz = warp::path!("healthz")
+        .and(with_health(health.clone()))
+        .map(move |health| healthz(health, stall_timeout));
+    let readyz = warp::path!("readyz")
+        .and(with_health(health.clone()))
+        .map(move |health| readyz(health, stall_timeout, max_queue_depth));
+
+    let watch_results = warp::path!("events")
+        .and(warp::get())
+        .and(auth.clone().filter())
+        .and(with_results(results.clone()))
+        .map(watch_results);
+
+    let check = warp::path!("check")
+        .and(warp::post())
+        .and(auth.clone().filter())
+        .and(warp::body::content_length_limit(MAX_BODY))
+        .and(warp::body::json())
+        .and(with_results(results.clone()))
+        .and(with_health(health.clone()))
+        .and(with_location(opts.location.clone()))
+        .and_then(check::check);
+
+    let show_history = warp::path!("history" / String)
+        .and(warp::get())
+        .and(auth.clone().filter())
+        .and(warp::query::<HistoryQuery>())
+        .and(with_results(results.clone()))
+        .map(show_history);
 
     let show_domain_name = warp::path!(String)
+        .and(auth.clone().filter())
         .and(with_client(client))
+        .and(with_results(results))
+        .and(with_health(health))
+        .and(with_location(opts.location.clone()))
         .and_then(show_domain_name);
 
     let routes = warp::any()
-        .and(show_domain_name)
+        .and(
+            healthz
+                .or(readyz)
+                .or(watch_results)
+                .or(check)
+                .or(show_history)
+                .or(show_domain_name),
+        )
+        .recover(move |r| auth.clone().recover(r))
         .with(warp::log("hcc_server"));
 
     let addr: SocketAddr = opts.bind.parse()?;
     info!("Served on {0}", opts.bind);
-    warp::serve(routes).bind(addr).await;
+    let http = async move {
+        match (opts.tls_cert, opts.tls_key) {
+            (Some(cert), Some(key)) => {
+                warp::serve(routes)
+                    .tls()
+                    .cert_path(cert)
+                    .key_path(key)
+                    .bind(addr)
+                    .await
+            }
+            _ => warp::serve(routes).bind(addr).await,
+        }
+        Ok(())
+    };
+    tokio::try_join!(http, grpc)?;
 
     Ok(())
 }
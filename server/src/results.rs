@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+use tokio::sync::broadcast;
+
+use hcc::CheckResultJSON;
+
+use crate::store::{MemoryStore, Store};
+
+/// Capacity of channel to watchers, slow watchers miss older results
+const CAPACITY: usize = 64;
+
+/// Latest result of each domain name checked by server, broadcast to watchers as checks
+/// complete, and saved to store as history
+pub struct Results {
+    latest: Mutex<BTreeMap<String, CheckResultJSON>>,
+    sender: broadcast::Sender<CheckResultJSON>,
+    store: Box<dyn Store>,
+}
+
+impl Default for Results {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Results {
+            latest: Mutex::new(BTreeMap::new()),
+            sender,
+            store: Box::new(MemoryStore::default()),
+        }
+    }
+}
+
+impl Results {
+    /// Results saved to store, starting with the latest results in store e.g. from before
+    /// restart
+    pub fn new(store: Box<dyn Store>) -> anyhow::Result<Self> {
+        let latest = store
+            .latest()?
+            .into_iter()
+            .map(|r| (r.domain_name.clone(), r))
+            .collect();
+        Ok(Results {
+            latest: Mutex::new(latest),
+            store,
+            ..Default::default()
+        })
+    }
+
+    /// Record result, save it to store and send it to watchers
+    pub fn record(&self, result: CheckResultJSON) {
+        // result is still served when store fails, only history misses it
+        if let Err(e) = self.store.save(&result) {
+            warn!("failed to save result of {0}: {1:#}", result.domain_name, e);
+        }
+        // no watcher is not an error
+        let _ = self.sender.send(result.clone());
+        self.latest
+            .lock()
+            .unwrap()
+            .insert(result.domain_name.clone(), result);
+    }
+
+    /// Record results on a thread where blocking is allowed, saving to store blocks
+    pub async fn record_all(self: Arc<Self>, results: Vec<CheckResultJSON>) {
+        let recorded = tokio::task::spawn_blocking(move || {
+            for result in results {
+                self.record(result);
+            }
+        })
+        .await;
+        if let Err(e) = recorded {
+            warn!("failed to record results: {0}", e);
+        }
+    }
+
+    /// Latest results ordered by domain name
+    pub fn list(&self) -> Vec<CheckResultJSON> {
+        self.latest.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Results of domain name in store, the latest first, at most limit
+    pub fn history(&self, domain_name: &str, limit: usize) -> anyhow::Result<Vec<CheckResultJSON>> {
+        self.store.history(domain_name, limit)
+    }
+
+    /// Receive results recorded from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<CheckResultJSON> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hcc::CheckResultJSON;
+
+    use crate::results::Results;
+    use crate::store::{MemoryStore, Store};
+
+    fn build(domain_name: &str, days: i64) -> CheckResultJSON {
+        CheckResultJSON {
+            domain_name: domain_name.into(),
+            days,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let results = Results::default();
+        let mut receiver = results.subscribe();
+        results.record(build("b.example.com", 1));
+        results.record(build("a.example.com", 2));
+        results.record(build("b.example.com", 3));
+
+        let list = results.list();
+        assert_eq!(2, list.len());
+        assert_eq!("a.example.com", list[0].domain_name);
+        assert_eq!(3, list[1].days);
+
+        assert_eq!(1, receiver.try_recv().unwrap().days);
+        assert_eq!(2, results.history("b.example.com", 10).unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_record_all() {
+        let results = Arc::new(Results::default());
+        results
+            .clone()
+            .record_all(vec![build("a.example.com", 1), build("b.example.com", 2)])
+            .await;
+        assert_eq!(2, results.list().len());
+        assert_eq!(1, results.history("a.example.com", 10).unwrap().len());
+    }
+
+    #[test]
+    fn test_new() {
+        let store = MemoryStore::default();
+        store.save(&build("a.example.com", 1)).unwrap();
+        store.save(&build("a.example.com", 2)).unwrap();
+        let results = Results::new(Box::new(store)).unwrap();
+        let list = results.list();
+        assert_eq!(1, list.len());
+        assert_eq!(2, list[0].days);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context};
+use rusqlite::{params, Connection};
+
+use hcc::CheckResultJSON;
+
+/// Storage of results, so history survives restarts and could be shared by servers
+pub trait Store: Send + Sync {
+    /// Append result to history of its domain name
+    fn save(&self, result: &CheckResultJSON) -> anyhow::Result<()>;
+
+    /// Latest result of each domain name ordered by domain name
+    fn latest(&self) -> anyhow::Result<Vec<CheckResultJSON>>;
+
+    /// Results of domain name, the latest first, at most limit
+    fn history(&self, domain_name: &str, limit: usize) -> anyhow::Result<Vec<CheckResultJSON>>;
+}
+
+/// Open store by URL, `memory`, `sqlite:PATH` or `postgres://...` when built with feature
+/// postgres
+pub fn open(url: &str) -> anyhow::Result<Box<dyn Store>> {
+    if url == "memory" {
+        return Ok(Box::new(MemoryStore::default()));
+    }
+    if let Some(path) = url.strip_prefix("sqlite:") {
+        return Ok(Box::new(SqliteStore::open(path)?));
+    }
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Box::new(pg::PostgresStore::connect(url)?));
+        #[cfg(not(feature = "postgres"))]
+        bail!("PostgreSQL is not supported, build with feature postgres");
+    }
+    bail!(
+        "unknown database {0}, expect memory, sqlite:PATH or postgres://...",
+        url
+    )
+}
+
+/// Results kept in memory, lost on restart
+#[derive(Default)]
+pub struct MemoryStore {
+    results: Mutex<BTreeMap<String, Vec<CheckResultJSON>>>,
+}
+
+impl Store for MemoryStore {
+    fn save(&self, result: &CheckResultJSON) -> anyhow::Result<()> {
+        self.results
+            .lock()
+            .unwrap()
+            .entry(result.domain_name.clone())
+            .or_default()
+            .push(result.clone());
+        Ok(())
+    }
+
+    fn latest(&self) -> anyhow::Result<Vec<CheckResultJSON>> {
+        let results = self.results.lock().unwrap();
+        Ok(results.values().filter_map(|h| h.last()).cloned().collect())
+    }
+
+    fn history(&self, domain_name: &str, limit: usize) -> anyhow::Result<Vec<CheckResultJSON>> {
+        let results = self.results.lock().unwrap();
+        let history = results.get(domain_name).map_or(&[][..], Vec::as_slice);
+        Ok(history.iter().rev().take(limit).cloned().collect())
+    }
+}
+
+/// Results in SQLite database file
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open database file, created with table of results if missing
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path).with_context(|| format!("failed to open {0:?}", path))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS results (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                domain_name TEXT NOT NULL,
+                json TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS results_domain_name ON results (domain_name, seq);",
+        )
+        .with_context(|| format!("failed to create table in {0:?}", path))?;
+        Ok(SqliteStore {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn query(
+        &self,
+        sql: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> anyhow::Result<Vec<CheckResultJSON>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(sql)?;
+        let rows = statement.query_map(params, |row| row.get::<_, String>(0))?;
+        rows.map(|json| Ok(serde_json::from_str(&json?)?)).collect()
+    }
+}
+
+impl Store for SqliteStore {
+    fn save(&self, result: &CheckResultJSON) -> anyhow::Result<()> {
+        let json = serde_json::to_string(result)?;
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO results (domain_name, json) VALUES (?1, ?2)",
+            params![result.domain_name, json],
+        )?;
+        Ok(())
+    }
+
+    fn latest(&self) -> anyhow::Result<Vec<CheckResultJSON>> {
+        self.query(LATEST, &[])
+    }
+
+    fn history(&self, domain_name: &str, limit: usize) -> anyhow::Result<Vec<CheckResultJSON>> {
+        self.query(
+            "SELECT json FROM results WHERE domain_name = ?1 ORDER BY seq DESC LIMIT ?2",
+            params![domain_name, limit as i64],
+        )
+    }
+}
+
+/// Latest result of each domain name, shared by SQL backends
+const LATEST: &str = "SELECT json FROM results
+    WHERE seq IN (SELECT MAX(seq) FROM results GROUP BY domain_name)
+    ORDER BY domain_name";
+
+#[cfg(feature = "postgres")]
+mod pg {
+    use std::sync::Mutex;
+    use std::thread;
+
+    use anyhow::Context;
+    use postgres::{Client, NoTls};
+
+    use hcc::CheckResultJSON;
+
+    use crate::store::{Store, LATEST};
+
+    /// Results in PostgreSQL database
+    pub struct PostgresStore {
+        client: Mutex<Client>,
+    }
+
+    /// Run blocking client on a thread of its own, since client drives a runtime of its own
+    /// which could not be started within runtime of server
+    fn off_runtime<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+        thread::scope(|s| s.spawn(f).join().expect("database thread panicked"))
+    }
+
+    impl PostgresStore {
+        /// Connect to database, table of results is created if missing
+        pub fn connect(url: &str) -> anyhow::Result<Self> {
+            off_runtime(|| {
+                let mut client =
+                    Client::connect(url, NoTls).context("failed to connect to database")?;
+                client
+                    .batch_execute(
+                        "CREATE TABLE IF NOT EXISTS results (
+                            seq BIGSERIAL PRIMARY KEY,
+                            domain_name TEXT NOT NULL,
+                            json TEXT NOT NULL
+                        );
+                        CREATE INDEX IF NOT EXISTS results_domain_name
+                            ON results (domain_name, seq);",
+                    )
+                    .context("failed to create table")?;
+                Ok(PostgresStore {
+                    client: Mutex::new(client),
+                })
+            })
+        }
+
+        fn query(
+            &self,
+            sql: &str,
+            params: &[&(dyn postgres::types::ToSql + Sync)],
+        ) -> anyhow::Result<Vec<CheckResultJSON>> {
+            let rows = off_runtime(|| self.client.lock().unwrap().query(sql, params))?;
+            rows.iter()
+                .map(|row| Ok(serde_json::from_str(row.get::<_, &str>(0))?))
+                .collect()
+        }
+    }
+
+    impl Store for PostgresStore {
+        fn save(&self, result: &CheckResultJSON) -> anyhow::Result<()> {
+            let json = serde_json::to_string(result)?;
+            off_runtime(|| {
+                self.client.lock().unwrap().execute(
+                    "INSERT INTO results (domain_name, json) VALUES ($1, $2)",
+                    &[&result.domain_name, &json],
+                )
+            })?;
+            Ok(())
+        }
+
+        fn latest(&self) -> anyhow::Result<Vec<CheckResultJSON>> {
+            self.query(LATEST, &[])
+        }
+
+        fn history(&self, domain_name: &str, limit: usize) -> anyhow::Result<Vec<CheckResultJSON>> {
+            self.query(
+                "SELECT json FROM results WHERE domain_name = $1 ORDER BY seq DESC LIMIT $2",
+                &[&domain_name, &(limit as i64)],
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hcc::CheckResultJSON;
+
+    use crate::store::{open, MemoryStore, SqliteStore, Store};
+
+    fn build(domain_name: &str, days: i64) -> CheckResultJSON {
+        CheckResultJSON {
+            domain_name: domain_name.into(),
+            days,
+            ..Default::default()
+        }
+    }
+
+    fn exercise(store: &dyn Store) {
+        store.save(&build("b.example.com", 1)).unwrap();
+        store.save(&build("a.example.com", 2)).unwrap();
+        store.save(&build("b.example.com", 3)).unwrap();
+
+        let latest = store.latest().unwrap();
+        assert_eq!(2, latest.len());
+        assert_eq!("a.example.com", latest[0].domain_name);
+        assert_eq!(3, latest[1].days);
+
+        let history = store.history("b.example.com", 10).unwrap();
+        assert_eq!(
+            vec![3, 1],
+            history.iter().map(|r| r.days).collect::<Vec<_>>()
+        );
+        assert_eq!(1, store.history("b.example.com", 1).unwrap().len());
+        assert!(store.history("c.example.com", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_memory() {
+        exercise(&MemoryStore::default());
+    }
+
+    #[test]
+    fn test_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hcc-server.db");
+        exercise(&SqliteStore::open(&path).unwrap());
+        // results survive reopening
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(2, store.latest().unwrap().len());
+    }
+
+    #[test]
+    fn test_open() {
+        assert!(open("memory").is_ok());
+        assert!(open("mysql://localhost/hcc").is_err());
+    }
+}